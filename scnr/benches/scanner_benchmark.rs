@@ -1,6 +1,6 @@
-use std::{fs, sync::LazyLock, time::Duration};
+use std::{fs, hint::black_box, sync::LazyLock, time::Duration};
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
 use scnr::{Scanner, ScannerBuilder, ScannerMode};
 
 const PAR_SCANNER_INPUT: &str = include_str!("./input_1.par");
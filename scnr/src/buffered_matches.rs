@@ -0,0 +1,438 @@
+use std::collections::VecDeque;
+
+use crate::{
+    internal::find_matches_impl::ScanStep, Checkpoint, FindMatches, Match, PeekResult, Position,
+    PositionProvider, Result, ScannerModeSwitcher, ScnrError,
+};
+
+/// A match in the lookahead buffer together with the state of the scanner before it was found.
+#[derive(Debug)]
+struct BufferedMatch<'h> {
+    step: ScanStep,
+    checkpoint: Checkpoint<'h>,
+}
+
+/// An iterator over all non-overlapping matches that buffers the peeked matches.
+///
+/// [FindMatches::peek_n] scans the input behind the current position on each call, and
+/// [FindMatches::next_match] scans it once more. A parser that peeks k matches before each match
+/// it consumes therefore scans each match up to k + 1 times. `BufferedMatches` scans each match
+/// only once. The peeked matches are kept in a buffer from which the following peek operations,
+/// [BufferedMatches::next_match] and [BufferedMatches::advance_to] take them.
+///
+/// The results are the same as those of [FindMatches]. Especially the peek operation stops at a
+/// match that triggers a mode switch. If the scanner mode or the offset is set from outside, the
+/// buffer is discarded and the input is scanned again from the current position.
+///
+/// * `'h` represents the lifetime of the haystack being searched.
+///
+/// This iterator can be created with the [`FindMatches::buffered`] method.
+///
+/// ```rust
+/// use scnr::{PeekResult, ScannerBuilder};
+///
+/// let scanner = ScannerBuilder::new()
+///     .add_patterns([r"[a-z]+", r"\s+"])
+///     .build()
+///     .unwrap();
+/// let mut buffered = scanner.find_iter("ab cd ef").buffered();
+/// let PeekResult::Matches(peeked) = buffered.peek_n(3) else {
+///     panic!("Expected three matches");
+/// };
+/// // The peeked matches are taken from the buffer.
+/// assert_eq!(buffered.next_match(), Some(peeked[0]));
+/// assert_eq!(buffered.buffered_len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct BufferedMatches<'h> {
+    // The iterator that scans the matches. Its state is the state behind the last buffered match.
+    inner: FindMatches<'h>,
+    // The scanned but not yet consumed matches, including those of skipped token types, because
+    // their mode switches stop the peek operations.
+    buffer: VecDeque<BufferedMatch<'h>>,
+    // True if the inner iterator found no further match behind the buffered matches.
+    exhausted: bool,
+    // The error at unmatched input behind the buffered matches that a peek operation has found.
+    // It is returned when the buffered matches are consumed.
+    pending_error: Option<ScnrError>,
+    // The error at which the last call of `next_match` stopped.
+    last_error: Option<ScnrError>,
+}
+
+impl<'h> BufferedMatches<'h> {
+    /// Creates a new `BufferedMatches` iterator.
+    pub(crate) fn new(inner: FindMatches<'h>) -> Self {
+        Self {
+            inner,
+            buffer: VecDeque::new(),
+            exhausted: false,
+            pending_error: None,
+            last_error: None,
+        }
+    }
+
+    /// Returns the number of buffered matches, i.e. matches that are scanned but not yet consumed.
+    /// Matches of skipped token types are not counted.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.iter().filter(|m| !m.step.skipped).count()
+    }
+
+    /// Retrieve the current byte offset from the start of the haystack.
+    /// See [FindMatches::offset].
+    pub fn offset(&self) -> usize {
+        match self.buffer.front() {
+            Some(front) => front.checkpoint.offset(),
+            None => self.inner.offset(),
+        }
+    }
+
+    /// Returns the next match in the haystack.
+    /// See [FindMatches::next_match].
+    #[inline]
+    pub fn next_match(&mut self) -> Option<Match> {
+        match self.try_next_match() {
+            Ok(matched) => {
+                self.last_error = None;
+                matched
+            }
+            Err(error) => {
+                self.last_error = Some(error);
+                None
+            }
+        }
+    }
+
+    /// Returns the next match in the haystack or an error at unmatched input.
+    /// See [FindMatches::try_next_match].
+    pub fn try_next_match(&mut self) -> Result<Option<Match>> {
+        while let Some(buffered) = self.buffer.pop_front() {
+            if !buffered.step.skipped {
+                return Ok(Some(buffered.step.matched));
+            }
+        }
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+        self.inner.try_next_match()
+    }
+
+    /// Returns the error at which the last call of [BufferedMatches::next_match] returned `None`.
+    /// See [FindMatches::last_error].
+    pub fn last_error(&self) -> Option<&ScnrError> {
+        self.last_error.as_ref()
+    }
+
+    /// Peeks n matches ahead without consuming the matches.
+    /// See [FindMatches::peek_n] for the conditions under which the peek operation stops.
+    ///
+    /// Only the matches that are not yet buffered are scanned.
+    pub fn peek_n(&mut self, n: usize) -> PeekResult {
+        let mut matches = Vec::with_capacity(n);
+        let mut index = 0;
+        while matches.len() < n {
+            if index == self.buffer.len() && !self.scan_next() {
+                break;
+            }
+            let step = self.buffer[index].step;
+            if step.skipped_input {
+                // Unmatched input stops the peek operation.
+                break;
+            }
+            index += 1;
+            if !step.skipped {
+                matches.push(step.matched);
+            }
+            if let Some(mode) = step.mode_switch {
+                return PeekResult::MatchesReachedModeSwitch((matches, mode));
+            }
+        }
+        if matches.len() == n {
+            PeekResult::Matches(matches)
+        } else if matches.is_empty() {
+            PeekResult::NotFound
+        } else {
+            PeekResult::MatchesReachedEnd(matches)
+        }
+    }
+
+    /// Advance the haystack to the given position.
+    /// See [FindMatches::advance_to].
+    ///
+    /// Buffered matches that end at or before the position are consumed, unless one of them
+    /// triggers a mode switch, which [FindMatches::advance_to] wouldn't conduct. In this case and
+    /// if the position lies within a buffered match the buffer is discarded.
+    /// A pending error at unmatched input behind the consumed matches is scanned again.
+    pub fn advance_to(&mut self, position: usize) -> usize {
+        let consumed = self
+            .buffer
+            .iter()
+            .take_while(|m| m.step.matched.end() <= position)
+            .count();
+        let mode_switch = self
+            .buffer
+            .iter()
+            .take(consumed)
+            .any(|m| m.step.mode_switch.is_some());
+        if !mode_switch {
+            self.buffer.drain(..consumed);
+            match self.buffer.front() {
+                // The inner iterator stands at the position of a pending error, which the new
+                // position can lie behind.
+                None => self.pending_error = None,
+                Some(front) if front.step.matched.start() >= position => return self.offset(),
+                Some(_) => self.discard_buffer(),
+            }
+        } else {
+            self.discard_buffer();
+        }
+        self.inner.advance_to(position)
+    }
+
+    /// Returns the underlying iterator over the matches. Its state is the state of this iterator,
+    /// i.e. the buffered matches are scanned again.
+    pub fn into_inner(mut self) -> FindMatches<'h> {
+        self.discard_buffer();
+        self.inner
+    }
+
+    /// Scans the next match into the buffer. Returns false if no match is found.
+    /// An error at unmatched input ends the peek operation like in [FindMatches::peek_n]. The
+    /// error is kept and returned by [BufferedMatches::try_next_match] when it reaches the
+    /// position.
+    fn scan_next(&mut self) -> bool {
+        if self.exhausted || self.pending_error.is_some() {
+            return false;
+        }
+        let checkpoint = self.inner.checkpoint();
+        match self.inner.try_next_step() {
+            Ok(Some(step)) => {
+                self.buffer.push_back(BufferedMatch { step, checkpoint });
+                true
+            }
+            Ok(None) => {
+                self.exhausted = true;
+                false
+            }
+            Err(error) => {
+                self.pending_error = Some(error);
+                false
+            }
+        }
+    }
+
+    /// Discards the buffered matches and sets the inner iterator back to the state before the
+    /// first of them.
+    fn discard_buffer(&mut self) {
+        if let Some(front) = self.buffer.front() {
+            self.inner.restore(&front.checkpoint);
+            self.buffer.clear();
+        }
+        self.exhausted = false;
+        self.pending_error = None;
+    }
+}
+
+impl Iterator for BufferedMatches<'_> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_match()
+    }
+}
+
+impl PositionProvider for BufferedMatches<'_> {
+    fn position(&self, offset: usize) -> Position {
+        self.inner.position(offset)
+    }
+
+    /// Discards the buffered matches and sets the offset of the haystack to the given position.
+    fn set_offset(&mut self, offset: usize) {
+        self.discard_buffer();
+        self.inner.set_offset(offset);
+    }
+}
+
+impl ScannerModeSwitcher for BufferedMatches<'_> {
+    /// Discards the buffered matches, because they could have been found in another scanner
+    /// mode, and sets the current scanner mode.
+    fn set_mode(&mut self, mode: usize) {
+        self.discard_buffer();
+        self.inner.set_mode(mode);
+    }
+
+    fn current_mode(&self) -> usize {
+        match self.buffer.front() {
+            Some(front) => front.checkpoint.mode(),
+            None => self.inner.current_mode(),
+        }
+    }
+
+    fn mode_name(&self, index: usize) -> Option<&str> {
+        self.inner.mode_name(index)
+    }
+
+    fn mode_stack_depth(&self) -> usize {
+        match self.buffer.front() {
+            Some(front) => front.checkpoint.mode_stack.len(),
+            None => self.inner.mode_stack_depth(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        test_support::{pattern, scanner_mode},
+        ModeTransition, Scanner, ScannerBuilder, ScnrErrorKind, UnmatchedInput,
+    };
+
+    fn scanner() -> Scanner {
+        let code = scanner_mode(
+            "CODE",
+            [
+                pattern(r"[a-z]+", 0),
+                pattern(r"\u{22}", 1),
+                pattern(r"\}", 2),
+                pattern(r"\s+", 5).with_skip(true),
+                pattern(r"//", 6).with_skip(true).with_name("Comment"),
+            ],
+            &[
+                (1, ModeTransition::Push(1)),
+                (2, ModeTransition::Pop),
+                (6, ModeTransition::Push(2)),
+            ],
+        );
+        let string = scanner_mode(
+            "STRING",
+            [
+                pattern(r"\$\{", 3),
+                pattern(r"[^\u{22}$]+", 4),
+                pattern(r"\u{22}", 1),
+            ],
+            &[(1, ModeTransition::Pop), (3, ModeTransition::Push(0))],
+        );
+        let comment = scanner_mode(
+            "COMMENT",
+            [pattern(r"[^\n]+", 7), pattern(r"\n", 8)],
+            &[(8, ModeTransition::Pop)],
+        );
+        ScannerBuilder::new()
+            .add_scanner_modes(&[code, string, comment])
+            .build()
+            .unwrap()
+    }
+
+    const INPUT: &str = "a \"b${ c # \"d\"}\" // e\nf # g";
+
+    fn assert_same_state(find_iter: &FindMatches, buffered: &BufferedMatches) {
+        assert_eq!(find_iter.offset(), buffered.offset());
+        assert_eq!(find_iter.current_mode(), buffered.current_mode());
+        assert_eq!(find_iter.mode_stack_depth(), buffered.mode_stack_depth());
+    }
+
+    #[test]
+    fn test_buffered_matches_like_find_matches() {
+        let scanner = scanner();
+        for n in 0..6 {
+            let mut find_iter = scanner.find_iter(INPUT);
+            let mut buffered = scanner.find_iter(INPUT).buffered();
+            loop {
+                assert_eq!(find_iter.peek_n(n), buffered.peek_n(n), "n = {}", n);
+                // A second peek is taken from the buffer.
+                assert_eq!(find_iter.peek_n(n), buffered.peek_n(n), "n = {}", n);
+                assert_same_state(&find_iter, &buffered);
+                let matched = find_iter.next_match();
+                assert_eq!(matched, buffered.next_match(), "n = {}", n);
+                if matched.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_buffered_matches_invalidation() {
+        let scanner = scanner();
+        let mut find_iter = scanner.find_iter(INPUT);
+        let mut buffered = scanner.find_iter(INPUT).buffered();
+
+        // Consume the peeked matches with advance_to.
+        let PeekResult::MatchesReachedModeSwitch((peeked, 1)) = buffered.peek_n(3) else {
+            panic!("Expected a mode switch");
+        };
+        assert_eq!(buffered.buffered_len(), 2);
+        let end = peeked[0].end();
+        assert_eq!(find_iter.advance_to(end), buffered.advance_to(end));
+        assert_eq!(buffered.buffered_len(), 1);
+        assert_same_state(&find_iter, &buffered);
+
+        // The parser switches the scanner mode on its own.
+        let end = peeked[1].end();
+        find_iter.advance_to(end);
+        buffered.advance_to(end);
+        assert_eq!(buffered.buffered_len(), 0);
+        find_iter.set_mode(1);
+        buffered.set_mode(1);
+        assert_eq!(find_iter.peek_n(2), buffered.peek_n(2));
+        find_iter.set_mode(0);
+        buffered.set_mode(0);
+        assert_same_state(&find_iter, &buffered);
+        assert_eq!(find_iter.next_match(), buffered.next_match());
+
+        // Advance into the middle of a buffered match.
+        buffered.peek_n(3);
+        assert_eq!(find_iter.advance_to(10), buffered.advance_to(10));
+        assert_eq!(find_iter.peek_n(3), buffered.peek_n(3));
+
+        // Set the offset behind the buffered matches.
+        find_iter.set_offset(21);
+        buffered.set_offset(21);
+        assert_same_state(&find_iter, &buffered);
+        assert_eq!(
+            find_iter.collect::<Vec<_>>(),
+            buffered.into_inner().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_buffered_matches_unmatched_input_error() {
+        let scanner = scanner().with_unmatched_input(UnmatchedInput::Error);
+        let mut find_iter = scanner.find_iter("a b # c");
+        let mut buffered = scanner.find_iter("a b # c").buffered();
+        // The peek operation stops at the unmatched input, but the error is kept.
+        assert_eq!(find_iter.peek_n(3), buffered.peek_n(3));
+        assert_eq!(buffered.buffered_len(), 2);
+        assert_eq!(buffered.by_ref().count(), 2);
+        assert!(matches!(
+            buffered.last_error().map(|error| &*error.source),
+            Some(ScnrErrorKind::UnmatchedInput { offset: 4, .. })
+        ));
+        assert!(buffered.try_next_match().is_err());
+
+        // Continue behind the unmatched character.
+        buffered.advance_to(5);
+        assert_eq!(buffered.next_match().map(|m| m.range()), Some(6..7));
+        assert!(buffered.last_error().is_none());
+    }
+
+    #[test]
+    fn test_buffered_matches_advance_past_unmatched_input_error() {
+        let scanner = ScannerBuilder::new()
+            .with_unmatched_input(UnmatchedInput::Error)
+            .add_patterns([r"[a-z]+", r"\s+"])
+            .build()
+            .unwrap();
+        let mut find_iter = scanner.find_iter("a b # c");
+        let mut buffered = scanner.find_iter("a b # c").buffered();
+        assert_eq!(find_iter.peek_n(5), buffered.peek_n(5));
+        for _ in 0..3 {
+            assert_eq!(find_iter.next_match(), buffered.next_match());
+        }
+        // The error that the peek operation has found lies before the new position.
+        assert_eq!(find_iter.advance_to(5), buffered.advance_to(5));
+        assert_same_state(&find_iter, &buffered);
+        assert_eq!(find_iter.next_match(), buffered.next_match());
+        assert!(buffered.last_error().is_none());
+    }
+}
@@ -0,0 +1,593 @@
+//! Module with the generation of Rust source code for compiled scanners.
+//!
+//! The scanner modes are compiled in a build script and the minimized DFAs are written as static
+//! tables to a Rust source file. The generated file defines the static `SCANNER_MODES` and a
+//! function `scanner()` that creates a [Scanner] from them. No regex is parsed or compiled at
+//! runtime. The scanner behaves exactly like a scanner that is created by the
+//! [crate::ScannerBuilder] from the same scanner modes, i.e. [Scanner::find_iter] returns a
+//! [crate::FindMatches] iterator.
+//!
+//! A build script generates the scanner like this:
+//! ```rust,no_run
+//! use scnr::{Pattern, ScannerMode};
+//!
+//! let scanner_modes = vec![ScannerMode::new(
+//!     "INITIAL",
+//!     vec![
+//!         Pattern::new(r"[a-zA-Z_]\w*".to_string(), 0),
+//!         Pattern::new(r"\s+".to_string(), 1),
+//!     ],
+//!     vec![],
+//! )];
+//! let source = scnr::codegen::generate(&scanner_modes).expect("Code generation error");
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! std::fs::write(std::path::Path::new(&out_dir).join("scanner.rs"), source).unwrap();
+//! ```
+//! The generated file is then included into a module of the crate:
+//! ```rust,ignore
+//! mod scanner {
+//!     include!(concat!(env!("OUT_DIR"), "/scanner.rs"));
+//! }
+//!
+//! let scanner = scanner::scanner();
+//! for ma in scanner.find_iter("hello world") {
+//!     println!("{:?}", ma);
+//! }
+//! ```
+//!
+//! The generated file can only be used with the same version of this crate. Otherwise its
+//! compilation fails with a message that asks to generate it again.
+//!
+//! This module is not available with the feature `regex_automata`.
+
+use std::fmt::Write;
+
+use crate::{
+    internal::{
+        compiled_dfa::{CompiledDfa, StateData},
+        compiled_scanner_mode::CompiledScannerMode,
+        CompiledLookahead, CompiledLookbehind, ScannerImpl, StateSetID, SymbolID, TerminalID,
+        TokenNames,
+    },
+    ModeTransition, Scanner,
+};
+#[cfg(feature = "compiler")]
+use crate::{CompileLimits, Result, ScannerMode};
+
+/// The value in the static tables that marks a missing symbol, a missing transition or a missing
+/// state to continue with.
+pub const NONE: u32 = u32::MAX;
+
+/// The maximum width of the lines with table entries.
+const MAX_LINE_WIDTH: usize = 100;
+
+/// The DFA of a scanner mode, of a lookahead or of a lookbehind as static tables.
+/// The states are numbered from 0, which is the start state.
+#[derive(Debug)]
+pub struct StaticDfa {
+    /// The patterns of the DFA in priority order.
+    pub patterns: &'static [&'static str],
+    /// The terminal ids of the DFA in priority order.
+    pub terminal_ids: &'static [u32],
+    /// The symbols of the ASCII characters or [NONE].
+    pub ascii: [u32; 128],
+    /// The sorted and disjoint ranges of the non-ASCII characters with their symbols.
+    pub ranges: &'static [(char, char, u32)],
+    /// The sorted and disjoint ranges of the raw bytes with their symbols. Raw bytes are the bytes
+    /// of a byte input that are not part of a valid UTF-8 sequence.
+    pub bytes: &'static [(u8, u8, u32)],
+    /// The number of symbols.
+    pub symbol_count: usize,
+    /// The transition table with a row per state and a column per symbol. The entries are the
+    /// target states or [NONE].
+    pub transitions: &'static [u32],
+    /// The accepting terminals of each state in priority order. Each terminal is accompanied by the
+    /// state to continue with after a match of a non-greedy pattern with a lookahead or [NONE].
+    pub accepting: &'static [&'static [(u32, u32)]],
+    /// The target states of the zero-width assertions of each state.
+    pub looks: &'static [&'static [u32]],
+    /// The lookaheads of the terminals.
+    pub lookaheads: &'static [StaticLookaround],
+    /// The lookbehinds of the terminals. Their DFAs are created from the reversed patterns.
+    pub lookbehinds: &'static [StaticLookaround],
+}
+
+/// A lookahead or a lookbehind of a terminal as static tables.
+#[derive(Debug)]
+pub struct StaticLookaround {
+    /// The terminal id the lookaround belongs to.
+    pub terminal_id: u32,
+    /// If the lookaround is positive or negative.
+    pub is_positive: bool,
+    /// The DFA of the lookaround.
+    pub dfa: StaticDfa,
+}
+
+/// A compiled scanner mode as static tables.
+#[derive(Debug)]
+pub struct StaticScannerMode {
+    /// The name of the scanner mode.
+    pub name: &'static str,
+    /// The transitions to other scanner modes as pairs of terminal id and mode transition,
+    /// sorted by terminal id.
+    pub transitions: &'static [(u32, ModeTransition)],
+    /// The terminal ids of the patterns that are marked as skipped, sorted by terminal id.
+    pub skipped: &'static [u32],
+    /// The names of the token types of the scanner mode as pairs of terminal id and name, sorted
+    /// by terminal id.
+    pub token_names: &'static [(u32, &'static str)],
+    /// The DFA of the scanner mode.
+    pub dfa: StaticDfa,
+}
+
+/// Checks if a generated scanner was generated by this version of the crate.
+/// The generated source calls it in a constant assertion.
+pub const fn is_compatible(version: &str) -> bool {
+    let version = version.as_bytes();
+    let expected = env!("CARGO_PKG_VERSION").as_bytes();
+    if version.len() != expected.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < version.len() {
+        if version[i] != expected[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Compiles the scanner modes with the default [CompileLimits] and returns the Rust source of the
+/// generated scanner.
+#[cfg(feature = "compiler")]
+pub fn generate(scanner_modes: &[ScannerMode]) -> Result<String> {
+    let scanner = Scanner {
+        inner: ScannerImpl::try_from_scanner_modes(scanner_modes, &CompileLimits::default())?,
+    };
+    Ok(generate_from_scanner(&scanner))
+}
+
+/// Returns the Rust source of the generated scanner for an already built scanner.
+/// Use this function if the scanner is built with other [CompileLimits].
+pub fn generate_from_scanner(scanner: &Scanner) -> String {
+    let mut out = String::new();
+    let scanner_modes = &scanner.inner.scanner_modes;
+    // Writing to a string can't fail.
+    let _ = write_scanner(&mut out, scanner_modes, scanner.inner.token_names());
+    out
+}
+
+fn write_scanner(
+    out: &mut String,
+    scanner_modes: &[CompiledScannerMode],
+    token_names: &TokenNames,
+) -> std::fmt::Result {
+    writeln!(
+        out,
+        "// This file was generated by scnr::codegen. Do not edit it by hand."
+    )?;
+    writeln!(out)?;
+    writeln!(out, "const _: () = assert!(")?;
+    writeln!(
+        out,
+        "    scnr::codegen::is_compatible({:?}),",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(
+        out,
+        "    \"The scanner was generated by another version of scnr. Please generate it again.\""
+    )?;
+    writeln!(out, ");")?;
+    writeln!(out)?;
+    writeln!(out, "const N: u32 = scnr::codegen::NONE;")?;
+    writeln!(out)?;
+    writeln!(out, "/// The compiled scanner modes.")?;
+    writeln!(
+        out,
+        "pub static SCANNER_MODES: &[scnr::codegen::StaticScannerMode] = &["
+    )?;
+    for scanner_mode in scanner_modes {
+        writeln!(out, "    scnr::codegen::StaticScannerMode {{")?;
+        writeln!(out, "        name: {:?},", scanner_mode.name)?;
+        write!(out, "        transitions: &[")?;
+        for (i, (terminal_id, transition)) in scanner_mode.transitions.iter().enumerate() {
+            if i > 0 {
+                write!(out, ", ")?;
+            }
+            write!(
+                out,
+                "({}, scnr::ModeTransition::{:?})",
+                terminal_id, transition
+            )?;
+        }
+        writeln!(out, "],")?;
+        write!(out, "        skipped: &[")?;
+        for (i, terminal_id) in scanner_mode.skipped.iter().enumerate() {
+            if i > 0 {
+                write!(out, ", ")?;
+            }
+            write!(out, "{}", terminal_id)?;
+        }
+        writeln!(out, "],")?;
+        write!(out, "        token_names: &[")?;
+        let mut terminal_ids = scanner_mode.dfa.terminal_ids.clone();
+        terminal_ids.sort();
+        terminal_ids.dedup();
+        let names = terminal_ids.iter().filter_map(|terminal_id| {
+            token_names
+                .name(terminal_id.as_usize())
+                .map(|name| (terminal_id, name))
+        });
+        for (i, (terminal_id, name)) in names.enumerate() {
+            if i > 0 {
+                write!(out, ", ")?;
+            }
+            write!(out, "({}, {:?})", terminal_id, name)?;
+        }
+        writeln!(out, "],")?;
+        write!(out, "        dfa: ")?;
+        write_dfa(out, &scanner_mode.dfa, 2)?;
+        writeln!(out, ",")?;
+        writeln!(out, "    }},")?;
+    }
+    writeln!(out, "];")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// Creates the scanner from the compiled scanner modes."
+    )?;
+    writeln!(out, "pub fn scanner() -> scnr::Scanner {{")?;
+    writeln!(out, "    scnr::Scanner::from(SCANNER_MODES)")?;
+    writeln!(out, "}}")
+}
+
+/// Writes the DFA as struct expression. The first line is not indented, because it continues the
+/// current line.
+fn write_dfa(out: &mut String, dfa: &CompiledDfa, level: usize) -> std::fmt::Result {
+    let indent = "    ".repeat(level);
+    writeln!(out, "scnr::codegen::StaticDfa {{")?;
+    write!(out, "{}    patterns: &[", indent)?;
+    write_entries(
+        out,
+        level + 1,
+        dfa.patterns.iter().map(|pattern| format!("{:?}", pattern)),
+    )?;
+    writeln!(out, "],")?;
+    write!(out, "{}    terminal_ids: &[", indent)?;
+    write_entries(
+        out,
+        level + 1,
+        dfa.terminal_ids
+            .iter()
+            .map(|terminal_id| terminal_id.to_string()),
+    )?;
+    writeln!(out, "],")?;
+    write!(out, "{}    ascii: [", indent)?;
+    write_entries(
+        out,
+        level + 1,
+        dfa.alphabet
+            .ascii_symbols()
+            .iter()
+            .map(|symbol| symbol.map_or("N".to_string(), |s| s.to_string())),
+    )?;
+    writeln!(out, "],")?;
+    write!(out, "{}    ranges: &[", indent)?;
+    write_entries(
+        out,
+        level + 1,
+        dfa.alphabet.ranges().iter().map(|(start, end, symbol)| {
+            format!(
+                "('\\u{{{:x}}}', '\\u{{{:x}}}', {})",
+                *start as u32, *end as u32, symbol
+            )
+        }),
+    )?;
+    writeln!(out, "],")?;
+    write!(out, "{}    bytes: &[", indent)?;
+    write_entries(
+        out,
+        level + 1,
+        dfa.alphabet
+            .bytes()
+            .iter()
+            .map(|(start, end, symbol)| format!("({:#04x}, {:#04x}, {})", start, end, symbol)),
+    )?;
+    writeln!(out, "],")?;
+    writeln!(out, "{}    symbol_count: {},", indent, dfa.alphabet.len())?;
+    write!(out, "{}    transitions: &[", indent)?;
+    write_entries(out, level + 1, dfa.transitions.iter().map(state_entry))?;
+    writeln!(out, "],")?;
+    write!(out, "{}    accepting: &[", indent)?;
+    write_entries(
+        out,
+        level + 1,
+        dfa.states.iter().map(|state| {
+            let accepting = state
+                .accepting
+                .iter()
+                .map(|(terminal_id, drop_target)| {
+                    format!(
+                        "({}, {})",
+                        terminal_id,
+                        drop_target.map_or("N".to_string(), |target| target.to_string())
+                    )
+                })
+                .collect::<Vec<_>>();
+            format!("&[{}]", accepting.join(", "))
+        }),
+    )?;
+    writeln!(out, "],")?;
+    write!(out, "{}    looks: &[", indent)?;
+    write_rows(
+        out,
+        level + 1,
+        dfa.states
+            .iter()
+            .map(|state| state.looks.iter().map(state_entry).collect()),
+    )?;
+    writeln!(out, "],")?;
+    let mut lookaheads = dfa
+        .lookaheads
+        .iter()
+        .map(|(terminal_id, lookahead)| (*terminal_id, lookahead.is_positive, &*lookahead.nfa))
+        .collect::<Vec<_>>();
+    lookaheads.sort_by_key(|(terminal_id, _, _)| *terminal_id);
+    write!(out, "{}    lookaheads: ", indent)?;
+    write_lookarounds(out, &lookaheads, level + 1)?;
+    let mut lookbehinds = dfa
+        .lookbehinds
+        .iter()
+        .map(|(terminal_id, lookbehind)| (*terminal_id, lookbehind.is_positive, &*lookbehind.nfa))
+        .collect::<Vec<_>>();
+    lookbehinds.sort_by_key(|(terminal_id, _, _)| *terminal_id);
+    write!(out, "{}    lookbehinds: ", indent)?;
+    write_lookarounds(out, &lookbehinds, level + 1)?;
+    write!(out, "{}}}", indent)
+}
+
+/// Writes the lookarounds of a DFA as slice expression followed by a comma.
+fn write_lookarounds(
+    out: &mut String,
+    lookarounds: &[(TerminalID, bool, &CompiledDfa)],
+    level: usize,
+) -> std::fmt::Result {
+    if lookarounds.is_empty() {
+        return writeln!(out, "&[],");
+    }
+    let indent = "    ".repeat(level);
+    writeln!(out, "&[")?;
+    for (terminal_id, is_positive, dfa) in lookarounds {
+        writeln!(out, "{}    scnr::codegen::StaticLookaround {{", indent)?;
+        writeln!(out, "{}        terminal_id: {},", indent, terminal_id)?;
+        writeln!(out, "{}        is_positive: {},", indent, is_positive)?;
+        write!(out, "{}        dfa: ", indent)?;
+        write_dfa(out, dfa, level + 2)?;
+        writeln!(out, ",")?;
+        writeln!(out, "{}    }},", indent)?;
+    }
+    writeln!(out, "{}],", indent)
+}
+
+/// Writes the entries of a table. Short tables are written in one line, longer tables are
+/// wrapped into lines of at most [MAX_LINE_WIDTH] characters.
+fn write_entries(
+    out: &mut String,
+    level: usize,
+    entries: impl Iterator<Item = String>,
+) -> std::fmt::Result {
+    let entries = entries.collect::<Vec<_>>();
+    let indent = "    ".repeat(level);
+    let single_line = entries.join(", ");
+    if indent.len() + single_line.len() + 8 <= MAX_LINE_WIDTH {
+        return write!(out, "{}", single_line);
+    }
+    writeln!(out)?;
+    write_lines(out, level, entries)?;
+    write!(out, "{}", indent)
+}
+
+/// Writes a table whose entries are slices. The rows are written like the entries of
+/// [write_entries], but a row that doesn't fit into a line is wrapped itself.
+fn write_rows(
+    out: &mut String,
+    level: usize,
+    rows: impl Iterator<Item = Vec<String>>,
+) -> std::fmt::Result {
+    let rows = rows.collect::<Vec<_>>();
+    let indent = "    ".repeat(level);
+    let row = |entries: &[String]| format!("&[{}]", entries.join(", "));
+    let fits = |entries: &[String]| indent.len() + 4 + row(entries).len() + 2 <= MAX_LINE_WIDTH;
+    if rows.iter().all(|entries| fits(entries)) {
+        return write_entries(out, level, rows.iter().map(|entries| row(entries)));
+    }
+    writeln!(out)?;
+    // The short rows before the next long row.
+    let mut short_rows = Vec::new();
+    for entries in rows {
+        if fits(&entries) {
+            short_rows.push(row(&entries));
+            continue;
+        }
+        write_lines(out, level, std::mem::take(&mut short_rows))?;
+        write!(out, "{}    &[", indent)?;
+        write_entries(out, level + 1, entries.into_iter())?;
+        writeln!(out, "],")?;
+    }
+    write_lines(out, level, short_rows)?;
+    write!(out, "{}", indent)
+}
+
+/// Writes the entries into lines of at most [MAX_LINE_WIDTH] characters. Each line is indented
+/// one level deeper than the given level and ends with a comma.
+fn write_lines(out: &mut String, level: usize, entries: Vec<String>) -> std::fmt::Result {
+    let indent = "    ".repeat(level);
+    let mut line = String::new();
+    for entry in entries {
+        if !line.is_empty() && indent.len() + 4 + line.len() + entry.len() + 2 > MAX_LINE_WIDTH {
+            writeln!(out, "{}    {}", indent, line.trim_end())?;
+            line.clear();
+        }
+        line.push_str(&entry);
+        line.push_str(", ");
+    }
+    if !line.is_empty() {
+        writeln!(out, "{}    {}", indent, line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Returns the table entry of a state.
+fn state_entry(state: &StateSetID) -> String {
+    if state.id() == NONE {
+        "N".to_string()
+    } else {
+        state.to_string()
+    }
+}
+
+/// Converts a table entry into an optional id.
+fn optional(entry: u32) -> Option<u32> {
+    (entry != NONE).then_some(entry)
+}
+
+impl From<&StaticDfa> for CompiledDfa {
+    fn from(dfa: &StaticDfa) -> Self {
+        let ascii = dfa.ascii.map(|symbol| optional(symbol).map(SymbolID::new));
+        let ranges = dfa
+            .ranges
+            .iter()
+            .map(|(start, end, symbol)| (*start, *end, SymbolID::new(*symbol)))
+            .collect();
+        let bytes = dfa
+            .bytes
+            .iter()
+            .map(|(start, end, symbol)| (*start, *end, SymbolID::new(*symbol)))
+            .collect();
+        let states = dfa
+            .accepting
+            .iter()
+            .zip(dfa.looks)
+            .map(|(accepting, looks)| StateData {
+                accepting: accepting
+                    .iter()
+                    .map(|(terminal_id, drop_target)| {
+                        (
+                            TerminalID::new(*terminal_id),
+                            optional(*drop_target).map(StateSetID::new),
+                        )
+                    })
+                    .collect(),
+                looks: looks
+                    .iter()
+                    .map(|target| StateSetID::new(*target))
+                    .collect(),
+            })
+            .collect();
+        CompiledDfa {
+            patterns: dfa.patterns.iter().map(|p| p.to_string()).collect(),
+            terminal_ids: dfa
+                .terminal_ids
+                .iter()
+                .map(|id| TerminalID::new(*id))
+                .collect(),
+            alphabet: crate::internal::Alphabet::from_tables(
+                ascii,
+                ranges,
+                bytes,
+                dfa.symbol_count,
+            ),
+            states,
+            transitions: dfa
+                .transitions
+                .iter()
+                .map(|target| StateSetID::new(*target))
+                .collect(),
+            lookaheads: dfa
+                .lookaheads
+                .iter()
+                .map(|lookahead| {
+                    (
+                        TerminalID::new(lookahead.terminal_id),
+                        CompiledLookahead {
+                            nfa: Box::new((&lookahead.dfa).into()),
+                            is_positive: lookahead.is_positive,
+                        },
+                    )
+                })
+                .collect(),
+            lookbehinds: dfa
+                .lookbehinds
+                .iter()
+                .map(|lookbehind| {
+                    (
+                        TerminalID::new(lookbehind.terminal_id),
+                        CompiledLookbehind {
+                            nfa: Box::new((&lookbehind.dfa).into()),
+                            is_positive: lookbehind.is_positive,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A generated scanner is created from its static scanner modes.
+impl From<&[StaticScannerMode]> for Scanner {
+    fn from(scanner_modes: &[StaticScannerMode]) -> Self {
+        // The names were consistent when the scanner was generated.
+        let token_names =
+            TokenNames::try_from_names(scanner_modes.iter().flat_map(|scanner_mode| {
+                scanner_mode
+                    .token_names
+                    .iter()
+                    .map(|(terminal_id, name)| (*terminal_id as usize, *name))
+            }))
+            .expect("The token names of a generated scanner are consistent");
+        let scanner_modes = scanner_modes
+            .iter()
+            .map(|scanner_mode| CompiledScannerMode {
+                name: scanner_mode.name.to_string(),
+                dfa: (&scanner_mode.dfa).into(),
+                transitions: scanner_mode
+                    .transitions
+                    .iter()
+                    .map(|(terminal_id, transition)| (TerminalID::new(*terminal_id), *transition))
+                    .collect(),
+                skipped: scanner_mode
+                    .skipped
+                    .iter()
+                    .map(|terminal_id| TerminalID::new(*terminal_id))
+                    .collect(),
+            })
+            .collect();
+        Scanner {
+            inner: ScannerImpl::from_compiled_scanner_modes(scanner_modes, token_names),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+    use crate::Pattern;
+
+    #[test]
+    fn test_is_compatible() {
+        assert!(is_compatible(env!("CARGO_PKG_VERSION")));
+        assert!(!is_compatible("0.0.1"));
+        assert!(!is_compatible(""));
+    }
+
+    #[test]
+    fn test_generate_invalid_pattern() {
+        let scanner_modes = vec![ScannerMode::new(
+            "INITIAL",
+            vec![Pattern::new("[a-z".to_string(), 0)],
+            vec![],
+        )];
+        assert!(generate(&scanner_modes).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Module with the units in which the columns of positions are counted.
+
+#[cfg(feature = "scnr_unicode")]
+use seshat::unicode::Segmentation;
+
+use crate::internal::Haystack;
+
+/// The unit in which the column numbers of [crate::Position]s are counted.
+///
+/// The unit is set with [crate::FindMatches::with_column_unit]. The default is
+/// [ColumnUnit::Byte], which is the cheapest one, because the column is calculated from the byte
+/// offsets alone. All other units count the characters of the line up to the offset.
+/// `StreamMatches` always counts the columns in bytes, because it doesn't keep the lines.
+///
+/// ```rust
+/// use scnr::{ColumnUnit, MatchExtIterator, ScannerBuilder};
+///
+/// let scanner = ScannerBuilder::new()
+///     .add_patterns([r"\S+", r"\s+"])
+///     .build()
+///     .unwrap();
+/// let columns = |unit: ColumnUnit| {
+///     scanner
+///         .find_iter("äö\t𝄞 x")
+///         .with_column_unit(unit)
+///         .with_positions()
+///         .map(|m| m.start_position().column)
+///         .collect::<Vec<_>>()
+/// };
+/// assert_eq!(columns(ColumnUnit::Byte), vec![1, 5, 6, 10, 11]);
+/// assert_eq!(columns(ColumnUnit::Char), vec![1, 3, 4, 5, 6]);
+/// assert_eq!(columns(ColumnUnit::Utf16), vec![1, 3, 4, 6, 7]);
+/// assert_eq!(columns(ColumnUnit::CharsWithTabs { tab_width: 4 }), vec![1, 3, 5, 6, 7]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ColumnUnit {
+    /// The column is the byte offset from the start of the line plus one.
+    #[default]
+    Byte,
+    /// The column counts the characters, i.e. the Unicode scalar values.
+    Char,
+    /// The column counts the UTF-16 code units, as the Language Server Protocol does by default.
+    Utf16,
+    /// The column counts the extended grapheme clusters, i.e. the characters as perceived by the
+    /// user.
+    ///
+    /// This unit is available with the feature `scnr_unicode`.
+    #[cfg(feature = "scnr_unicode")]
+    Grapheme,
+    /// The column counts the characters, but a tab advances the column to the next tab stop. The
+    /// tab stops are `tab_width` columns apart. A `tab_width` of zero counts a tab as one column.
+    ///
+    /// Each other character counts as one column, also wide characters like `字` and combining
+    /// marks. So this is not the column at which a terminal displays the character.
+    CharsWithTabs {
+        /// The distance between two tab stops.
+        tab_width: usize,
+    },
+}
+
+impl ColumnUnit {
+    /// Returns the column of the position after the given text, which starts at the beginning of
+    /// a line. Columns are 1-based.
+    pub(crate) fn column(self, line_prefix: Haystack) -> usize {
+        let chars = || line_prefix.char_indices(0).map(|(_, c)| c.as_char());
+        let width = match self {
+            ColumnUnit::Byte => line_prefix.len(),
+            ColumnUnit::Char => chars().count(),
+            ColumnUnit::Utf16 => chars().map(char::len_utf16).sum(),
+            #[cfg(feature = "scnr_unicode")]
+            ColumnUnit::Grapheme => match line_prefix {
+                Haystack::Str(s) => s.break_graphemes().count(),
+                Haystack::Bytes(b) => String::from_utf8_lossy(b).break_graphemes().count(),
+            },
+            ColumnUnit::CharsWithTabs { tab_width } => chars().fold(0, |width, c| {
+                if c == '\t' && tab_width > 0 {
+                    (width / tab_width + 1) * tab_width
+                } else {
+                    width + 1
+                }
+            }),
+        };
+        width + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column() {
+        let line = Haystack::Str("a\u{0301}b\t字\u{1F600}");
+        assert_eq!(ColumnUnit::Byte.column(line), 13);
+        assert_eq!(ColumnUnit::Char.column(line), 7);
+        assert_eq!(ColumnUnit::Utf16.column(line), 8);
+        #[cfg(feature = "scnr_unicode")]
+        assert_eq!(ColumnUnit::Grapheme.column(line), 6);
+        assert_eq!(ColumnUnit::CharsWithTabs { tab_width: 8 }.column(line), 11);
+        assert_eq!(ColumnUnit::CharsWithTabs { tab_width: 0 }.column(line), 7);
+        assert_eq!(ColumnUnit::Char.column(Haystack::Str("")), 1);
+        // Each invalid byte of a byte haystack is a character of its own.
+        assert_eq!(ColumnUnit::Char.column(Haystack::Bytes(b"a\xFF\xFEb")), 5);
+    }
+}
@@ -0,0 +1,111 @@
+//! Module with the limits that are checked during the compilation of a scanner.
+
+/// Limits that guard the compilation of a scanner against state explosion.
+///
+/// Bounded repetitions like `[0-9a-f]{1,64}` are expanded into copies of their sub-expression, and
+/// the construction of the DFA of a scanner mode can in the worst case create exponentially many
+/// states. Without limits a single typo in a pattern can make the compilation run for a very long
+/// time.
+///
+/// If a limit is exceeded the scanner builder returns an error of kind
+/// [crate::ScnrErrorKind::CompileLimitExceeded].
+///
+/// With the feature `regex_automata` only `max_repetition` is checked. The size of the automata
+/// is then restricted by the size limits of the `regex_automata` crate.
+///
+/// ```rust
+/// use scnr::{CompileLimits, ScannerBuilder};
+///
+/// let result = ScannerBuilder::new()
+///     .with_compile_limits(CompileLimits {
+///         max_repetition: 100,
+///         ..Default::default()
+///     })
+///     .add_patterns([r"[0-9a-f]{1,1000}"])
+///     .build();
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompileLimits {
+    /// The maximum number of NFA states of a scanner mode.
+    pub max_nfa_states: usize,
+    /// The maximum number of DFA states of a scanner mode before minimization.
+    pub max_dfa_states: usize,
+    /// The maximum count of a counted repetition like `{1,64}`.
+    pub max_repetition: u32,
+}
+
+#[cfg(feature = "compiler")]
+impl CompileLimits {
+    /// Checks the count of a counted repetition.
+    pub(crate) fn check_repetition(&self, count: u32) -> crate::Result<()> {
+        if count > self.max_repetition {
+            Err(CompileLimit::Repetition(self.max_repetition).into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the number of NFA states.
+    #[cfg(not(feature = "regex_automata"))]
+    pub(crate) fn check_nfa_states(&self, states: usize) -> crate::Result<()> {
+        if states > self.max_nfa_states {
+            Err(CompileLimit::NfaStates(self.max_nfa_states).into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the number of DFA states.
+    #[cfg(not(feature = "regex_automata"))]
+    pub(crate) fn check_dfa_states(&self, states: usize) -> crate::Result<()> {
+        if states > self.max_dfa_states {
+            Err(CompileLimit::DfaStates(self.max_dfa_states).into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for CompileLimits {
+    fn default() -> Self {
+        Self {
+            max_nfa_states: 100_000,
+            max_dfa_states: 10_000,
+            max_repetition: 1_000,
+        }
+    }
+}
+
+/// The limit of the [CompileLimits] that was exceeded.
+/// The value is the configured maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompileLimit {
+    /// The maximum number of NFA states of a scanner mode.
+    NfaStates(usize),
+    /// The maximum number of DFA states of a scanner mode.
+    DfaStates(usize),
+    /// The maximum count of a counted repetition.
+    Repetition(u32),
+}
+
+impl std::fmt::Display for CompileLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompileLimit::NfaStates(max) => write!(f, "The maximum of {} NFA states", max),
+            CompileLimit::DfaStates(max) => write!(f, "The maximum of {} DFA states", max),
+            CompileLimit::Repetition(max) => write!(f, "The maximum repetition count {}", max),
+        }
+    }
+}
+
+impl From<CompileLimit> for crate::ScnrError {
+    /// The pattern and the scanner mode are filled in by the callers that know them.
+    fn from(limit: CompileLimit) -> Self {
+        crate::ScnrError::new(crate::ScnrErrorKind::CompileLimitExceeded {
+            limit,
+            pattern: String::new(),
+            mode: String::new(),
+        })
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::CompileLimit;
+
 /// The result type for the `scrn` crate.
 pub type Result<T> = std::result::Result<T, ScnrError>;
 
@@ -17,6 +19,28 @@ impl ScnrError {
             source: Box::new(kind),
         }
     }
+
+    /// Sets the pattern of a [ScnrErrorKind::CompileLimitExceeded] error if it is not set yet.
+    /// Other errors are returned unchanged.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn with_pattern(mut self, offending_pattern: &str) -> Self {
+        if let ScnrErrorKind::CompileLimitExceeded { pattern, .. } = self.source.as_mut() {
+            if pattern.is_empty() {
+                *pattern = offending_pattern.to_string();
+            }
+        }
+        self
+    }
+
+    /// Sets the scanner mode of a [ScnrErrorKind::CompileLimitExceeded] error.
+    /// Other errors are returned unchanged.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn with_mode(mut self, mode_name: &str) -> Self {
+        if let ScnrErrorKind::CompileLimitExceeded { mode, .. } = self.source.as_mut() {
+            *mode = mode_name.to_string();
+        }
+        self
+    }
 }
 
 impl std::fmt::Display for ScnrError {
@@ -29,13 +53,14 @@ impl std::fmt::Display for ScnrError {
 #[derive(Error, Debug)]
 pub enum ScnrErrorKind {
     /// An error occurred during the parsing of the regex syntax.
+    #[cfg(feature = "compiler")]
     #[error("'{1}' {0}")]
     RegexSyntaxError(regex_syntax::ast::Error, String),
 
     /// An error occurred during the parsing of the regex syntax.
     #[cfg(feature = "regex_automata")]
     #[error(transparent)]
-    RegexBuildError(#[from] regex_automata::dfa::dense::BuildError),
+    RegexBuildError(#[from] regex_automata::meta::BuildError),
 
     /// A std::io error occurred.
     #[error(transparent)]
@@ -49,8 +74,61 @@ pub enum ScnrErrorKind {
     /// tokens.
     #[error("Empty tokens are not allowed.")]
     EmptyToken,
+
+    /// A limit of the [crate::CompileLimits] was exceeded during the compilation of a scanner.
+    #[error("{limit} is exceeded by pattern '{pattern}' in scanner mode '{mode}'")]
+    CompileLimitExceeded {
+        /// The limit that was exceeded.
+        limit: CompileLimit,
+        /// The pattern that exceeded the limit.
+        pattern: String,
+        /// The name of the scanner mode the pattern belongs to.
+        mode: String,
+    },
+
+    /// A token name is used for different token types or a token type has different names, see
+    /// [crate::Pattern::with_name].
+    #[error("The name '{name}' of token type {token_type} conflicts with another token name")]
+    TokenNameConflict {
+        /// The conflicting name.
+        name: String,
+        /// The token type the name is given to.
+        token_type: usize,
+    },
+
+    /// A token in a stream doesn't fit into the buffer of a [crate::StreamMatches] iterator.
+    #[cfg(not(feature = "regex_automata"))]
+    #[error("The token at offset {offset} exceeds the stream buffer limit of {limit} bytes")]
+    BufferLimitExceeded {
+        /// The limit of the buffer size in bytes.
+        limit: usize,
+        /// The offset in the stream where the token starts.
+        offset: usize,
+    },
+
+    /// No pattern of the current scanner mode matches the input and the scanner was configured
+    /// with [crate::UnmatchedInput::Error].
+    #[error("No pattern of scanner mode '{mode}' matches the input at offset {offset}")]
+    UnmatchedInput {
+        /// The offset of the first unmatched character.
+        offset: usize,
+        /// The name of the current scanner mode.
+        mode: String,
+    },
+
+    /// A serialized scanner could not be loaded. It was created by another version of the crate
+    /// or with another backend, or its data are corrupt.
+    #[cfg(feature = "serde")]
+    #[error("Invalid serialized scanner: {0}")]
+    InvalidSerializedScanner(String),
+
+    /// An error occurred during the serialization or deserialization of a scanner.
+    #[cfg(feature = "serde")]
+    #[error(transparent)]
+    SerializationError(#[from] serde_json::Error),
 }
 
+#[cfg(feature = "compiler")]
 impl From<regex_syntax::ast::Error> for ScnrError {
     fn from(error: regex_syntax::ast::Error) -> Self {
         ScnrError::new(ScnrErrorKind::RegexSyntaxError(error, "!".to_string()))
@@ -62,3 +140,10 @@ impl From<std::io::Error> for ScnrError {
         ScnrError::new(ScnrErrorKind::IoError(error))
     }
 }
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for ScnrError {
+    fn from(error: serde_json::Error) -> Self {
+        ScnrError::new(ScnrErrorKind::SerializationError(error))
+    }
+}
@@ -1,32 +1,89 @@
+use std::sync::Arc;
+
 use log::trace;
 
 use crate::{
-    internal::{find_matches_impl::FindMatchesImpl, ScannerImpl},
-    Match, Position, PositionProvider, ScannerModeSwitcher,
+    internal::{
+        find_matches_impl::{FindMatchesImpl, ScanStep},
+        CharIndices, Haystack, ModeStack, ScannerImpl,
+    },
+    BufferedMatches, ColumnUnit, LineIndex, Match, Position, PositionProvider, Result,
+    ScannerModeSwitcher, ScnrError, Tokens, TypedMatches,
 };
 
 /// The result of a peek operation.
+///
+/// The matches are of type [Match] by default. [crate::FindTokens::peek_n] returns
+/// [crate::Token] values instead. The peek operations that follow mode switches return the
+/// matches together with their scanner modes, see [FindMatches::peek_n_across_modes].
 #[derive(Debug, PartialEq)]
-pub enum PeekResult {
+pub enum PeekResult<T = Match> {
     /// The peek operation found n matches.
-    Matches(Vec<Match>),
+    Matches(Vec<T>),
     /// The peek operation found less than n matches because the end of the haystack was reached.
-    MatchesReachedEnd(Vec<Match>),
+    MatchesReachedEnd(Vec<T>),
     /// The peek operation found less than n matches because the last token type would have
     /// triggered a mode switch. The matches are returned along with the index of the new mode that
     /// would be switched to on the last match.
-    MatchesReachedModeSwitch((Vec<Match>, usize)),
+    MatchesReachedModeSwitch((Vec<T>, usize)),
     /// The peek operation found no matches.
     NotFound,
 }
 
+impl<T> PeekResult<T> {
+    /// Converts the matches of the result with the given function.
+    pub(crate) fn map<U>(self, f: impl FnMut(T) -> U) -> PeekResult<U> {
+        match self {
+            PeekResult::Matches(matches) => {
+                PeekResult::Matches(matches.into_iter().map(f).collect())
+            }
+            PeekResult::MatchesReachedEnd(matches) => {
+                PeekResult::MatchesReachedEnd(matches.into_iter().map(f).collect())
+            }
+            PeekResult::MatchesReachedModeSwitch((matches, mode)) => {
+                PeekResult::MatchesReachedModeSwitch((matches.into_iter().map(f).collect(), mode))
+            }
+            PeekResult::NotFound => PeekResult::NotFound,
+        }
+    }
+}
+
+/// The state of a [FindMatches] iterator, which is captured with [FindMatches::checkpoint] and
+/// restored with [FindMatches::restore].
+///
+/// A checkpoint holds the position in the haystack, the current scanner mode and the mode stack.
+/// It can be restored several times, but only on the iterator it was taken from.
+#[derive(Debug, Clone)]
+pub struct Checkpoint<'h> {
+    pub(crate) char_indices: CharIndices<'h>,
+    pub(crate) offset: usize,
+    pub(crate) last_position: usize,
+    pub(crate) last_char: char,
+    pub(crate) mode: usize,
+    pub(crate) mode_stack: ModeStack,
+}
+
+impl Checkpoint<'_> {
+    /// Returns the byte offset from the start of the haystack at which the checkpoint was taken,
+    /// see [FindMatches::offset].
+    pub fn offset(&self) -> usize {
+        self.last_position + self.offset
+    }
+
+    /// Returns the scanner mode that was active when the checkpoint was taken.
+    pub fn mode(&self) -> usize {
+        self.mode
+    }
+}
+
 /// An iterator over all non-overlapping matches.
 ///
 /// The iterator yields [`Match`] values until no more matches could be found.
 ///
 /// * `'h` represents the lifetime of the haystack being searched.
 ///
-/// This iterator can be created with the [`crate::Scanner::find_iter`] method.
+/// This iterator can be created with the [`crate::Scanner::find_iter`] method or with the
+/// [`crate::Scanner::find_iter_bytes`] method.
 #[derive(Debug)]
 pub struct FindMatches<'h> {
     inner: FindMatchesImpl<'h>,
@@ -34,7 +91,7 @@ pub struct FindMatches<'h> {
 
 impl<'h> FindMatches<'h> {
     /// Creates a new `FindMatches` iterator.
-    pub(crate) fn new(scanner_impl: ScannerImpl, input: &'h str) -> Self {
+    pub(crate) fn new(scanner_impl: ScannerImpl, input: Haystack<'h>) -> Self {
         Self {
             inner: FindMatchesImpl::new(scanner_impl, input),
         }
@@ -60,6 +117,120 @@ impl<'h> FindMatches<'h> {
         self.inner.set_offset(position);
     }
 
+    /// Captures the state of the iterator, so that a backtracking parser can return to it later
+    /// with [FindMatches::restore].
+    ///
+    /// In contrast to [FindMatches::set_offset], which only moves the position in the haystack,
+    /// a checkpoint also captures the current scanner mode and the mode stack.
+    ///
+    /// ```rust
+    /// use scnr::{Pattern, ScannerBuilder, ScannerMode, ScannerModeSwitcher};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_scanner_modes(&[
+    ///         ScannerMode::new(
+    ///             "INITIAL",
+    ///             vec![
+    ///                 Pattern::new(r"[a-z]+".to_string(), 1),
+    ///                 Pattern::new("\"".to_string(), 2),
+    ///             ],
+    ///             vec![(2, 1)],
+    ///         ),
+    ///         ScannerMode::new(
+    ///             "STRING",
+    ///             vec![
+    ///                 Pattern::new("[^\"]+".to_string(), 3),
+    ///                 Pattern::new("\"".to_string(), 2),
+    ///             ],
+    ///             vec![(2, 0)],
+    ///         ),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    /// let mut find_iter = scanner.find_iter("a\"b c\"d");
+    /// find_iter.next();
+    /// let checkpoint = find_iter.checkpoint();
+    /// let first = find_iter.by_ref().map(|m| m.token_type()).collect::<Vec<_>>();
+    /// assert_eq!(first, vec![2, 3, 2, 1]);
+    /// // Backtrack across the mode switches.
+    /// find_iter.restore(&checkpoint);
+    /// assert_eq!(find_iter.current_mode(), 0);
+    /// let second = find_iter.map(|m| m.token_type()).collect::<Vec<_>>();
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint<'h> {
+        self.inner.checkpoint()
+    }
+
+    /// Restores the state of the iterator that was captured with [FindMatches::checkpoint].
+    ///
+    /// Like taking the checkpoint, this takes constant time, because the mode stack is shared
+    /// between the checkpoint and the iterator. The line offsets that the iterator recorded are
+    /// kept, so positions stay correct. An error at unmatched input that the iterator has found
+    /// behind the checkpoint is dropped, see [FindMatches::last_error].
+    pub fn restore(&mut self, checkpoint: &Checkpoint<'h>) {
+        self.inner.restore(checkpoint);
+    }
+
+    /// Sets the unit in which the column numbers of the positions are counted, see [ColumnUnit].
+    /// The positions are returned by [PositionProvider::position] and are contained in the
+    /// matches of [crate::MatchExtIterator::with_positions].
+    pub fn with_column_unit(mut self, column_unit: ColumnUnit) -> Self {
+        self.inner.set_column_unit(column_unit);
+        self
+    }
+
+    /// Returns the unit in which the column numbers of the positions are counted.
+    pub fn column_unit(&self) -> ColumnUnit {
+        self.inner.column_unit()
+    }
+
+    /// Sets a complete index of the lines of the haystack, which is then used to calculate the
+    /// positions instead of the line offsets that the iterator records while it advances.
+    ///
+    /// Without an index the line of an offset the iterator hasn't passed yet is not known, and only
+    /// `\n` terminates a line. The index is built over the whole haystack with configurable line
+    /// terminators, see [LineIndex]. It is shared by all iterators that are given the same
+    /// [Arc].
+    ///
+    /// Note that with an index the end position of a match that ends with a line terminator is
+    /// the start of the next line.
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use scnr::{LineIndex, LineTerminators, MatchExtIterator, PositionProvider, ScannerBuilder};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_patterns([r"[a-z]+", r"\r"])
+    ///     .build()
+    ///     .unwrap();
+    /// let input = "ab\rcd";
+    /// let line_index = Arc::new(LineIndex::new(input, LineTerminators::Ascii));
+    /// let find_iter = scanner.find_iter(input).with_line_index(line_index);
+    /// // The position of an offset that is not yet scanned is known.
+    /// assert_eq!(find_iter.position(4).line, 2);
+    /// let lines = find_iter
+    ///     .with_positions()
+    ///     .map(|m| m.start_position().line)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(lines, vec![1, 1, 2]);
+    /// ```
+    pub fn with_line_index(mut self, line_index: Arc<LineIndex>) -> Self {
+        self.inner.set_line_index(line_index);
+        self
+    }
+
+    /// Returns the line index if one is set with [FindMatches::with_line_index].
+    pub fn line_index(&self) -> Option<&Arc<LineIndex>> {
+        self.inner.line_index()
+    }
+
+    /// Returns the number of the last recorded line that starts at or before the given offset and
+    /// the offset of its start.
+    pub(crate) fn line_of(&self, offset: usize) -> (usize, usize) {
+        self.inner.line_of(offset)
+    }
+
     /// Retrieve the current byte offset from the start of the haystack.
     /// This is the end offset of the last match found by the iterator.
     #[inline]
@@ -72,10 +243,17 @@ impl<'h> FindMatches<'h> {
     /// If a match is found, the function advances the iterator to the end of the match and returns
     /// the match.
     ///
-    /// If no match is found, the function repeatedly advances the haystack by one and tries again
-    /// until a match is found or the iterator is exhausted.
+    /// If no match is found, the unmatched input is handled as configured by
+    /// [crate::UnmatchedInput]. By default the function repeatedly advances the haystack by one and
+    /// tries again until a match is found or the iterator is exhausted.
+    ///
+    /// If the iterator is exhausted and no match is found, `None` is returned. `None` is also
+    /// returned at unmatched input if the scanner is configured with
+    /// [crate::UnmatchedInput::Error]. Use [FindMatches::last_error] or
+    /// [FindMatches::try_next_match] to distinguish both cases.
     ///
-    /// If the iterator is exhausted and no match is found, `None` is returned.
+    /// Matches of patterns that are marked as skipped, see [crate::Pattern::with_skip], are not
+    /// returned. Their mode transitions are applied nevertheless.
     ///
     /// This method is also used in the implementation of the `Iterator` trait for the `FindMatches`.
     #[inline]
@@ -83,6 +261,60 @@ impl<'h> FindMatches<'h> {
         self.inner.next_match()
     }
 
+    /// Returns the next match in the haystack like [FindMatches::next_match], but returns an error
+    /// of kind [crate::ScnrErrorKind::UnmatchedInput] if the scanner is configured with
+    /// [crate::UnmatchedInput::Error] and no pattern matches the input.
+    ///
+    /// The position is not changed by the error, so the same error is returned again until the
+    /// position is moved, e.g. with [FindMatches::advance_to].
+    ///
+    /// ```rust
+    /// use scnr::{ScannerBuilder, ScnrErrorKind, UnmatchedInput};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .with_unmatched_input(UnmatchedInput::Error)
+    ///     .add_patterns([r"[a-z]+", r"\s+"])
+    ///     .build()
+    ///     .unwrap();
+    /// let mut find_iter = scanner.find_iter("ab #c");
+    /// assert_eq!(find_iter.by_ref().count(), 2);
+    /// let error = find_iter.try_next_match().unwrap_err();
+    /// assert!(matches!(
+    ///     *error.source,
+    ///     ScnrErrorKind::UnmatchedInput { offset: 3, .. }
+    /// ));
+    /// // Continue behind the unmatched character.
+    /// find_iter.advance_to(4);
+    /// assert_eq!(find_iter.try_next_match().unwrap().map(|m| m.range()), Some(4..5));
+    /// ```
+    pub fn try_next_match(&mut self) -> Result<Option<Match>> {
+        self.inner.try_next_match()
+    }
+
+    /// Returns the error of kind [crate::ScnrErrorKind::UnmatchedInput] at which the last call of
+    /// [FindMatches::next_match] returned `None`. It is reset by the next call.
+    ///
+    /// This is useful with the `Iterator` trait, whose `next` method can't return the error.
+    ///
+    /// ```rust
+    /// use scnr::{ScannerBuilder, ScnrErrorKind, UnmatchedInput};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .with_unmatched_input(UnmatchedInput::Error)
+    ///     .add_patterns([r"[a-z]+", r"\s+"])
+    ///     .build()
+    ///     .unwrap();
+    /// let mut find_iter = scanner.find_iter("ab #c");
+    /// assert_eq!(find_iter.by_ref().count(), 2);
+    /// assert!(matches!(
+    ///     find_iter.last_error().map(|error| &*error.source),
+    ///     Some(ScnrErrorKind::UnmatchedInput { offset: 3, .. })
+    /// ));
+    /// ```
+    pub fn last_error(&self) -> Option<&ScnrError> {
+        self.inner.last_error()
+    }
+
     /// Peeks n matches ahead without consuming the matches.
     /// The function returns [PeekResult].
     ///
@@ -90,10 +322,65 @@ impl<'h> FindMatches<'h> {
     /// triggered by the last match. The mode switch is not conducted by the peek operation to not
     /// change the state of the scanner as well as to avoid a mix of tokens from different modes
     /// being returned.
+    ///
+    /// Matches of patterns that are marked as skipped are neither returned nor counted against
+    /// `n`. A mode switch that is triggered by a skipped match also stops the peek operation.
+    ///
+    /// Unmatched input stops the peek operation unless the scanner is configured with
+    /// [crate::UnmatchedInput::Token]. Then the run of unmatched characters is returned as one
+    /// match like by [FindMatches::next_match].
     pub fn peek_n(&mut self, n: usize) -> PeekResult {
         self.inner.peek_n(n)
     }
 
+    /// Peeks n matches ahead without consuming the matches like [FindMatches::peek_n], but
+    /// follows the mode switches that are triggered by the peeked matches instead of stopping at
+    /// them. Each match is returned together with the index of the scanner mode it was found in.
+    ///
+    /// The mode switches are simulated on a copy of the current scanner mode and the mode stack.
+    /// The state of the iterator is not changed. The result is never
+    /// [PeekResult::MatchesReachedModeSwitch]. Note that a parser that switches the scanner mode on
+    /// its own can get different matches than the peek operation predicts.
+    ///
+    /// ```rust
+    /// use scnr::{Pattern, PeekResult, ScannerBuilder, ScannerMode, ScannerModeSwitcher};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_scanner_modes(&[
+    ///         ScannerMode::new(
+    ///             "INITIAL",
+    ///             vec![
+    ///                 Pattern::new(r"[a-z]+".to_string(), 1),
+    ///                 Pattern::new("\"".to_string(), 2),
+    ///             ],
+    ///             vec![(2, 1)],
+    ///         ),
+    ///         ScannerMode::new(
+    ///             "STRING",
+    ///             vec![
+    ///                 Pattern::new("[^\"]+".to_string(), 3),
+    ///                 Pattern::new("\"".to_string(), 2),
+    ///             ],
+    ///             vec![(2, 0)],
+    ///         ),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    /// let mut find_iter = scanner.find_iter("a\"b c\"d");
+    /// let PeekResult::Matches(peeked) = find_iter.peek_n_across_modes(4) else {
+    ///     panic!("Expected four matches");
+    /// };
+    /// let peeked = peeked
+    ///     .iter()
+    ///     .map(|(m, mode)| (m.token_type(), *mode))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(peeked, vec![(1, 0), (2, 0), (3, 1), (2, 1)]);
+    /// assert_eq!(find_iter.current_mode(), 0);
+    /// ```
+    pub fn peek_n_across_modes(&mut self, n: usize) -> PeekResult<(Match, usize)> {
+        self.inner.peek_n_across_modes(n)
+    }
+
     /// Advance the haystack to the given position.
     /// The function is used to skip a given number of characters in the haystack.
     /// It can be used after a peek operation to skip the characters of the peeked matches.
@@ -109,6 +396,24 @@ impl<'h> FindMatches<'h> {
     pub fn advance_to(&mut self, position: usize) -> usize {
         self.inner.advance_to(position)
     }
+
+    /// Converts the iterator into an iterator that buffers the peeked matches, so that they are not
+    /// scanned again when they are peeked again or consumed, see [BufferedMatches].
+    pub fn buffered(self) -> BufferedMatches<'h> {
+        BufferedMatches::new(self)
+    }
+
+    /// Returns the next match including matches of skipped token types together with the details
+    /// of the search. This is used by [BufferedMatches].
+    pub(crate) fn try_next_step(&mut self) -> Result<Option<ScanStep>> {
+        self.inner.try_next_step()
+    }
+
+    /// Converts the iterator into an iterator over matches with the token types of `T`.
+    /// The scanner should be built from the scanner modes of `T`, see [Tokens::scanner].
+    pub fn typed<T: Tokens>(self) -> TypedMatches<'h, T> {
+        TypedMatches::new(self)
+    }
 }
 
 impl Iterator for FindMatches<'_> {
@@ -121,8 +426,9 @@ impl Iterator for FindMatches<'_> {
 
 impl PositionProvider for FindMatches<'_> {
     /// Returns the line and column numbers of the given offset.
-    /// The line number is the index of the line offset in the vector plus one.
-    /// The column number is the offset minus the line offset.
+    /// The line number is the index of the line offset in the vector plus one, or it is taken from
+    /// the line index if one is set.
+    /// The column number is counted from the line offset to the offset, see [ColumnUnit].
     /// If the offset is greater than the length of the haystack, the function returns the last
     /// recorded line and the column number is calculated from the last recorded position.
     fn position(&self, offset: usize) -> Position {
@@ -156,4 +462,8 @@ impl ScannerModeSwitcher for FindMatches<'_> {
     fn mode_name(&self, index: usize) -> Option<&str> {
         self.inner.mode_name(index)
     }
+
+    fn mode_stack_depth(&self) -> usize {
+        self.inner.mode_stack_depth()
+    }
 }
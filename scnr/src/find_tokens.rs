@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use crate::{
+    internal::Haystack, Checkpoint, ColumnUnit, FindMatches, LineIndex, Match, PeekResult,
+    Position, PositionProvider, Result, ScannerModeSwitcher, ScnrError, Token,
+};
+
+/// An iterator over all non-overlapping matches that yields [Token] values.
+///
+/// A token holds the matched text and the positions of the match in addition to the token type and
+/// the span, so that the caller doesn't need to slice the haystack for each match.
+///
+/// * `'h` represents the lifetime of the haystack being searched.
+///
+/// This iterator can be created with the [`crate::Scanner::find_tokens`] method.
+///
+/// ```rust
+/// use scnr::ScannerBuilder;
+///
+/// let scanner = ScannerBuilder::new()
+///     .add_patterns([r"[a-z]+", r"\s+"])
+///     .build()
+///     .unwrap();
+/// let tokens = scanner
+///     .find_tokens("ab\ncd")
+///     .map(|t| (t.text(), t.start_position().line, t.start_position().column))
+///     .collect::<Vec<_>>();
+/// assert_eq!(tokens, vec![("ab", 1, 1), ("\n", 1, 3), ("cd", 2, 1)]);
+/// ```
+#[derive(Debug)]
+pub struct FindTokens<'h> {
+    inner: FindMatches<'h>,
+    input: &'h str,
+}
+
+impl<'h> FindTokens<'h> {
+    /// Creates a new `FindTokens` iterator.
+    pub(crate) fn new(inner: FindMatches<'h>, input: &'h str) -> Self {
+        Self { inner, input }
+    }
+
+    /// Set the offset in the haystack to the given position relative to the start of the haystack.
+    /// See [FindMatches::with_offset].
+    pub fn with_offset(self, offset: usize) -> Self {
+        Self {
+            inner: self.inner.with_offset(offset),
+            input: self.input,
+        }
+    }
+
+    /// Sets the unit in which the column numbers of the positions are counted.
+    /// See [FindMatches::with_column_unit].
+    pub fn with_column_unit(self, column_unit: ColumnUnit) -> Self {
+        Self {
+            inner: self.inner.with_column_unit(column_unit),
+            input: self.input,
+        }
+    }
+
+    /// Sets a complete index of the lines of the haystack that is used to calculate the positions.
+    /// See [FindMatches::with_line_index].
+    pub fn with_line_index(self, line_index: Arc<LineIndex>) -> Self {
+        Self {
+            inner: self.inner.with_line_index(line_index),
+            input: self.input,
+        }
+    }
+
+    /// Retrieve the current byte offset from the start of the haystack.
+    /// See [FindMatches::offset].
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.inner.offset()
+    }
+
+    /// Returns the next token in the haystack.
+    /// See [FindMatches::next_match].
+    #[inline]
+    pub fn next_token(&mut self) -> Option<Token<'h>> {
+        self.inner.next_match().map(|m| self.token(m))
+    }
+
+    /// Returns the next token in the haystack or an error at unmatched input.
+    /// See [FindMatches::try_next_match].
+    pub fn try_next_token(&mut self) -> Result<Option<Token<'h>>> {
+        Ok(self.inner.try_next_match()?.map(|m| self.token(m)))
+    }
+
+    /// Returns the error at which the last call of [FindTokens::next_token] returned `None`.
+    /// See [FindMatches::last_error].
+    pub fn last_error(&self) -> Option<&ScnrError> {
+        self.inner.last_error()
+    }
+
+    /// Peeks n tokens ahead without consuming them.
+    /// See [FindMatches::peek_n] for the conditions under which the peek operation stops.
+    pub fn peek_n(&mut self, n: usize) -> PeekResult<Token<'h>> {
+        let peeked = self.inner.peek_n(n);
+        let current_line = self.inner.line_of(self.inner.offset());
+        peeked.map(|m| self.peeked_token(m, current_line))
+    }
+
+    /// Peeks n tokens ahead without consuming them and follows the mode switches that are
+    /// triggered by the peeked tokens. Each token is returned with the scanner mode it was scanned
+    /// in. See [FindMatches::peek_n_across_modes].
+    pub fn peek_n_across_modes(&mut self, n: usize) -> PeekResult<(Token<'h>, usize)> {
+        let peeked = self.inner.peek_n_across_modes(n);
+        let current_line = self.inner.line_of(self.inner.offset());
+        peeked.map(|(m, mode)| (self.peeked_token(m, current_line), mode))
+    }
+
+    /// Captures the state of the iterator.
+    /// See [FindMatches::checkpoint].
+    pub fn checkpoint(&self) -> Checkpoint<'h> {
+        self.inner.checkpoint()
+    }
+
+    /// Restores the state of the iterator that was captured with [FindTokens::checkpoint].
+    /// See [FindMatches::restore].
+    pub fn restore(&mut self, checkpoint: &Checkpoint<'h>) {
+        self.inner.restore(checkpoint);
+    }
+
+    /// Advance the haystack to the given position.
+    /// See [FindMatches::advance_to].
+    pub fn advance_to(&mut self, position: usize) -> usize {
+        self.inner.advance_to(position)
+    }
+
+    /// Returns the underlying iterator over the matches.
+    pub fn into_inner(self) -> FindMatches<'h> {
+        self.inner
+    }
+
+    /// Creates a token from a match that was consumed by the inner iterator.
+    fn token(&self, m: Match) -> Token<'h> {
+        Token::new(
+            m.token_type(),
+            m.span(),
+            self.inner.position(m.start()),
+            self.inner.position(m.end()),
+            &self.input[m.range()],
+        )
+    }
+
+    /// Creates a token from a peeked match. The current line is the number and the start offset of
+    /// the line of the current offset.
+    fn peeked_token(&self, m: Match, (line, line_start): (usize, usize)) -> Token<'h> {
+        if self.inner.line_index().is_some() {
+            // The line index knows the lines behind the current offset.
+            return self.token(m);
+        }
+        // The line offsets behind the current offset are not yet known to the inner iterator, so
+        // the positions are calculated from the start of the current line.
+        let text = &self.input[m.range()];
+        let start_position = self.position_from(line_start, line, m.start());
+        let end_position = match text.strip_suffix('\n') {
+            // Like with consumed matches, the end position of a match that ends with a newline is
+            // still on the line of the newline.
+            Some(stripped) => {
+                let before = self.position_from(line_start, line, m.start() + stripped.len());
+                Position::new(before.line, before.column + 1)
+            }
+            None => self.position_from(line_start, line, m.end()),
+        };
+        Token::new(m.token_type(), m.span(), start_position, end_position, text)
+    }
+
+    /// Calculates the position of the offset by counting the lines from the given line start.
+    fn position_from(&self, line_start: usize, line: usize, offset: usize) -> Position {
+        let text = &self.input[line_start..offset];
+        let (line, line_start) = match text.rfind('\n') {
+            Some(i) => (line + text.matches('\n').count(), line_start + i + 1),
+            None => (line, line_start),
+        };
+        let column = self
+            .inner
+            .column_unit()
+            .column(Haystack::Str(&self.input[line_start..offset]));
+        Position::new(line, column)
+    }
+}
+
+impl<'h> Iterator for FindTokens<'h> {
+    type Item = Token<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+    }
+}
+
+impl PositionProvider for FindTokens<'_> {
+    fn position(&self, offset: usize) -> Position {
+        self.inner.position(offset)
+    }
+
+    fn set_offset(&mut self, offset: usize) {
+        self.inner.set_offset(offset);
+    }
+}
+
+impl ScannerModeSwitcher for FindTokens<'_> {
+    fn set_mode(&mut self, mode: usize) {
+        self.inner.set_mode(mode);
+    }
+
+    fn current_mode(&self) -> usize {
+        self.inner.current_mode()
+    }
+
+    fn mode_name(&self, index: usize) -> Option<&str> {
+        self.inner.mode_name(index)
+    }
+
+    fn mode_stack_depth(&self) -> usize {
+        self.inner.mode_stack_depth()
+    }
+}
@@ -0,0 +1,283 @@
+use std::{ops::Range, sync::Arc};
+
+use crate::{
+    internal::{Haystack, ModeStack, ScannerImpl},
+    Match, ScannerModeSwitcher, Span, UnmatchedInput,
+};
+
+/// A match together with the scanner mode in which it was found.
+///
+/// A vector of these tokens is the result of [crate::Scanner::tokenize] and the input of
+/// [crate::Scanner::retokenize].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TokenWithMode {
+    // The match of the token.
+    matched: Match,
+    // The index of the scanner mode in which the token was found.
+    mode: usize,
+    // The mode stack at the start of the token. Consecutive tokens share the same stack.
+    mode_stack: Arc<[usize]>,
+    // The start of the input that the scanner has read to find the token, including the unmatched
+    // input before it and the input read by lookbehinds. The character before it can have been
+    // read as well, e.g. by a zero-width assertion.
+    scan_start: usize,
+    // The end of the input that the scanner has read to find the token, including the unmatched
+    // input before it. It is one past the end of the input if the scanner has checked for the end
+    // of the input.
+    scan_end: usize,
+}
+
+impl TokenWithMode {
+    /// Returns the match of the token.
+    #[inline]
+    pub fn matched(&self) -> Match {
+        self.matched
+    }
+
+    /// Returns the index of the scanner mode in which the token was found. A mode switch that is
+    /// triggered by the token itself is not included.
+    #[inline]
+    pub fn mode(&self) -> usize {
+        self.mode
+    }
+
+    /// Returns the mode stack at the start of the token, see [crate::ModeTransition]. The last
+    /// entry is the top of the stack.
+    #[inline]
+    pub fn mode_stack(&self) -> &[usize] {
+        &self.mode_stack
+    }
+
+    /// Returns the start of the input that the scanner has read to find the token, including the
+    /// unmatched input before it and the input read by lookbehinds. Because the character before
+    /// this position can have been read as well, only an edit that ends before this position
+    /// doesn't change the token.
+    #[inline]
+    pub fn scan_start(&self) -> usize {
+        self.scan_start
+    }
+
+    /// Returns the end of the input that the scanner has read to find the token, including the
+    /// unmatched input before it. An edit behind this position doesn't change the token.
+    #[inline]
+    pub fn scan_end(&self) -> usize {
+        self.scan_end
+    }
+}
+
+/// An edit of a text. The bytes in `range` of the old text are replaced by `replacement`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TextEdit<'a> {
+    /// The byte range of the old text that is replaced.
+    pub range: Range<usize>,
+    /// The text that replaces the range.
+    pub replacement: &'a str,
+}
+
+impl<'a> TextEdit<'a> {
+    /// Creates a new text edit.
+    pub fn new(range: Range<usize>, replacement: &'a str) -> Self {
+        Self { range, replacement }
+    }
+
+    /// Maps an offset of the old text behind the edited range to the new text.
+    fn shift(&self, offset: usize) -> usize {
+        debug_assert!(offset >= self.range.end);
+        offset - self.range.len() + self.replacement.len()
+    }
+}
+
+/// Scans the text from the given offset in the current scanner mode of the scanner and calls
+/// `on_token` for each token found. The scan stops if `on_token` returns false.
+fn scan(
+    scanner_impl: &mut ScannerImpl,
+    text: &str,
+    offset: usize,
+    mut on_token: impl FnMut(TokenWithMode) -> bool,
+) {
+    let haystack = Haystack::Str(text);
+    let mut position = offset;
+    let mut scan_start = offset;
+    let mut scan_end = offset;
+    // The mode stack of the scanner and its copy in the tokens, which is only updated when the
+    // stack changes.
+    let mut current_stack = scanner_impl.mode_stack().clone();
+    let mut mode_stack: Arc<[usize]> = current_stack.to_vec().into();
+    // The start of a run of unmatched characters that is returned as one token.
+    let mut unmatched_start = None;
+    while position < text.len() {
+        let mode = scanner_impl.current_mode();
+        let char_indices = haystack.char_indices(position);
+        let (matched, read_start, read_end) =
+            scanner_impl.peek_from_partial(haystack, char_indices);
+        scan_start = scan_start.min(read_start);
+        scan_end = scan_end.max(position + read_end);
+        if let Some(matched) = matched {
+            if let Some(start) = unmatched_start.take() {
+                // The run has ended because of the match, so the scan of the run includes the
+                // scan of the match.
+                let token = unmatched_token(
+                    scanner_impl,
+                    start..position,
+                    &mode_stack,
+                    scan_start..scan_end,
+                );
+                if !on_token(token) {
+                    return;
+                }
+                scan_start = read_start;
+                scan_end = position + read_end;
+            }
+            scanner_impl.execute_possible_mode_switch(&matched);
+            let matched = Match::new(
+                matched.token_type(),
+                Span::new(position + matched.start(), position + matched.end()),
+            );
+            position = matched.end();
+            let token = TokenWithMode {
+                matched,
+                mode,
+                mode_stack: mode_stack.clone(),
+                scan_start,
+                scan_end,
+            };
+            scan_start = position;
+            scan_end = position;
+            if current_stack != *scanner_impl.mode_stack() {
+                current_stack = scanner_impl.mode_stack().clone();
+                mode_stack = current_stack.to_vec().into();
+            }
+            if !on_token(token) {
+                return;
+            }
+        } else {
+            if let UnmatchedInput::Token(_) = scanner_impl.unmatched_input() {
+                unmatched_start.get_or_insert(position);
+            }
+            // No match at the current position, so the character is skipped.
+            let mut char_indices = haystack.char_indices(position);
+            char_indices.next();
+            position += char_indices.offset();
+        }
+    }
+    if let Some(start) = unmatched_start {
+        on_token(unmatched_token(
+            scanner_impl,
+            start..position,
+            &mode_stack,
+            scan_start..scan_end,
+        ));
+    }
+}
+
+/// Creates the token of a run of unmatched characters. The scanner must be configured with
+/// [UnmatchedInput::Token]. The `scan` range holds the start and the end of the input that the
+/// scanner has read to find it.
+fn unmatched_token(
+    scanner_impl: &ScannerImpl,
+    range: Range<usize>,
+    mode_stack: &Arc<[usize]>,
+    scan: Range<usize>,
+) -> TokenWithMode {
+    let UnmatchedInput::Token(token_type) = scanner_impl.unmatched_input() else {
+        unreachable!("Unmatched input is only returned as token with UnmatchedInput::Token");
+    };
+    TokenWithMode {
+        matched: Match::new(token_type, range.into()),
+        mode: scanner_impl.current_mode(),
+        mode_stack: mode_stack.clone(),
+        scan_start: scan.start,
+        scan_end: scan.end,
+    }
+}
+
+/// Scans the whole text, see [crate::Scanner::tokenize].
+pub(crate) fn tokenize(mut scanner_impl: ScannerImpl, text: &str) -> Vec<TokenWithMode> {
+    scanner_impl.reset();
+    let mut tokens = Vec::new();
+    scan(&mut scanner_impl, text, 0, |token| {
+        tokens.push(token);
+        true
+    });
+    tokens
+}
+
+/// Updates the tokens after an edit, see [crate::Scanner::retokenize].
+pub(crate) fn retokenize(
+    mut scanner_impl: ScannerImpl,
+    text: &str,
+    tokens: &mut Vec<TokenWithMode>,
+    edit: &TextEdit,
+) -> Range<usize> {
+    debug_assert_eq!(
+        text.get(edit.range.start..edit.range.start + edit.replacement.len()),
+        Some(edit.replacement),
+        "The text doesn't contain the replacement at the start of the edit."
+    );
+    // The first token whose scan has read the edited input. The last token is always scanned
+    // again, because the scan of the unmatched input behind it is not recorded.
+    let restart = tokens
+        .iter()
+        .position(|t| t.scan_end > edit.range.start)
+        .unwrap_or(tokens.len())
+        .min(tokens.len().saturating_sub(1));
+    // The scan of a token starts at the end of the previous token.
+    let offset = restart
+        .checked_sub(1)
+        .map_or(0, |previous| tokens[previous].matched.end());
+    if let Some(token) = tokens.get(restart) {
+        scanner_impl.set_mode(token.mode);
+        scanner_impl.set_mode_stack(&ModeStack::from(&*token.mode_stack));
+    }
+
+    // The scan can only continue with the old tokens behind the last one that has read the
+    // edited input or the character before the end of the edit. Besides the tokens before the
+    // edit these are the tokens that start directly at the end of the edit, because zero-width
+    // assertions at their start check the character before them, and tokens whose lookbehinds
+    // have read back into the edit.
+    let first_candidate = tokens
+        .iter()
+        .rposition(|t| t.scan_start <= edit.range.end)
+        .map_or(0, |last| last + 1);
+
+    let mut new_tokens = Vec::new();
+    // The index of the next old token that starts behind the edit.
+    let mut old = restart;
+    let mut sync = tokens.len();
+    scan(&mut scanner_impl, text, offset, |token| {
+        while old < tokens.len()
+            && (old < first_candidate
+                || edit.shift(tokens[old].matched.start()) < token.matched.start())
+        {
+            old += 1;
+        }
+        if old < tokens.len()
+            && edit.shift(tokens[old].matched.start()) == token.matched.start()
+            && tokens[old].mode == token.mode
+            && tokens[old].mode_stack == token.mode_stack
+        {
+            // From here on the scanner finds the old tokens again. The token replaces the old
+            // one, because its scan can have read more of the unmatched input before it.
+            sync = old;
+            tokens[old] = token;
+            return false;
+        }
+        new_tokens.push(token);
+        true
+    });
+
+    if sync < tokens.len() {
+        for token in &mut tokens[sync + 1..] {
+            let span = token.matched.span();
+            token.matched = Match::new(
+                token.matched.token_type(),
+                Span::new(edit.shift(span.start), edit.shift(span.end)),
+            );
+            token.scan_start = edit.shift(token.scan_start);
+            token.scan_end = edit.shift(token.scan_end);
+        }
+    }
+    let changed = restart..restart + new_tokens.len();
+    tokens.splice(restart..sync, new_tokens);
+    changed
+}
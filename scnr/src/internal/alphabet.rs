@@ -0,0 +1,464 @@
+//! Module with the alphabet of a compiled DFA.
+//!
+//! The character classes of the patterns overlap, like `[a-z]` and `[0-9a-f]`. To make the
+//! transitions of a DFA deterministic the characters are partitioned into disjoint sets, the
+//! symbols of the alphabet. Two characters belong to the same symbol if they are contained in
+//! exactly the same character classes. Each character class is then a union of symbols, and each
+//! character belongs to at most one symbol. The raw bytes of a byte haystack, see [Unit], are
+//! partitioned the same way, but they never share a symbol with a character.
+
+#[cfg(feature = "compiler")]
+use regex_syntax::hir;
+#[cfg(feature = "compiler")]
+use rustc_hash::FxHashMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "compiler")]
+use super::CharClassID;
+use super::{ids::SymbolIDBase, SymbolID, Unit};
+
+/// The raw bytes follow the characters in the space that is partitioned. The raw byte `b` is at
+/// `RAW_BYTE_BASE + b`, which is beyond the largest character.
+#[cfg(feature = "compiler")]
+const RAW_BYTE_BASE: u32 = 0x110000;
+
+/// The alphabet of a DFA.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub(crate) struct Alphabet {
+    /// The symbols of the ASCII characters. This is the fast path for the common case.
+    #[cfg_attr(feature = "serde", serde(with = "ascii_symbols"))]
+    ascii: [Option<SymbolID>; 128],
+    /// The sorted and disjoint ranges of the non-ASCII characters that belong to a symbol.
+    ranges: Vec<(char, char, SymbolID)>,
+    /// The sorted and disjoint ranges of the raw bytes that belong to a symbol.
+    bytes: Vec<(u8, u8, SymbolID)>,
+    /// The symbols that make up each character class.
+    /// They are only needed to construct the DFA and therefore not serialized.
+    #[cfg(feature = "compiler")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    class_symbols: FxHashMap<CharClassID, Vec<SymbolID>>,
+    /// The number of symbols.
+    len: usize,
+}
+
+impl Alphabet {
+    /// Partitions the characters and raw bytes of the given character classes into symbols.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn new<'a>(
+        char_classes: impl IntoIterator<
+            Item = (CharClassID, &'a hir::ClassUnicode, &'a hir::ClassBytes),
+        >,
+    ) -> Self {
+        // The intervals of each class in the space of characters and raw bytes.
+        let char_classes = char_classes
+            .into_iter()
+            .map(|(id, chars, bytes)| {
+                let intervals = chars
+                    .iter()
+                    .map(|r| (r.start() as u32, r.end() as u32))
+                    .chain(bytes.iter().map(|r| {
+                        (
+                            RAW_BYTE_BASE + r.start() as u32,
+                            RAW_BYTE_BASE + r.end() as u32,
+                        )
+                    }))
+                    .collect::<Vec<_>>();
+                (id, intervals)
+            })
+            .collect::<Vec<_>>();
+        // The positions where the membership of a character in a class can change.
+        let mut boundaries = char_classes
+            .iter()
+            .flat_map(|(_, intervals)| intervals.iter())
+            .flat_map(|(start, end)| [*start, end + 1])
+            .collect::<Vec<_>>();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        // The classes each elementary interval between two boundaries belongs to.
+        let mut signatures = vec![Vec::new(); boundaries.len().saturating_sub(1)];
+        for (id, intervals) in &char_classes {
+            for (start, end) in intervals {
+                let first = boundaries.binary_search(start).unwrap();
+                let last = boundaries.binary_search(&(end + 1)).unwrap();
+                for signature in &mut signatures[first..last] {
+                    signature.push(*id);
+                }
+            }
+        }
+
+        let mut alphabet = Alphabet::default();
+        let mut symbols: FxHashMap<&[CharClassID], SymbolID> = FxHashMap::default();
+        for (i, signature) in signatures.iter().enumerate() {
+            if signature.is_empty() {
+                continue;
+            }
+            let symbol = *symbols.entry(signature).or_insert_with(|| {
+                let symbol = SymbolID::new(alphabet.len as SymbolIDBase);
+                alphabet.len += 1;
+                for id in signature {
+                    alphabet.class_symbols.entry(*id).or_default().push(symbol);
+                }
+                symbol
+            });
+            alphabet.add_interval(boundaries[i], boundaries[i + 1] - 1, symbol);
+        }
+        alphabet
+    }
+
+    /// Creates an alphabet from the symbols of the ASCII characters and the ranges of the non-ASCII
+    /// characters and the raw bytes. It is used to load generated scanners, which don't need the
+    /// symbols of the character classes.
+    pub(crate) fn from_tables(
+        ascii: [Option<SymbolID>; 128],
+        ranges: Vec<(char, char, SymbolID)>,
+        bytes: Vec<(u8, u8, SymbolID)>,
+        len: usize,
+    ) -> Self {
+        Alphabet {
+            ascii,
+            ranges,
+            bytes,
+            #[cfg(feature = "compiler")]
+            class_symbols: FxHashMap::default(),
+            len,
+        }
+    }
+
+    /// Assigns the characters and raw bytes in the interval `start..=end` to the symbol.
+    #[cfg(feature = "compiler")]
+    fn add_interval(&mut self, start: u32, end: u32, symbol: SymbolID) {
+        if end >= RAW_BYTE_BASE {
+            let first = (start.max(RAW_BYTE_BASE) - RAW_BYTE_BASE) as u8;
+            let last = (end - RAW_BYTE_BASE) as u8;
+            add_range(&mut self.bytes, first, last, symbol);
+            if start >= RAW_BYTE_BASE {
+                return;
+            }
+        }
+        let end = end.min(char::MAX as u32);
+        for c in start..=end.min(0x7F) {
+            self.ascii[c as usize] = Some(symbol);
+        }
+        // The surrogates are no characters.
+        let start = match start.max(0x80) {
+            0xD800..=0xDFFF => 0xE000,
+            start => start,
+        };
+        let end = match end {
+            0xD800..=0xDFFF => 0xD7FF,
+            end => end,
+        };
+        if start > end {
+            return;
+        }
+        let (start, end) = (char::from_u32(start).unwrap(), char::from_u32(end).unwrap());
+        add_range(&mut self.ranges, start, end, symbol);
+    }
+
+    /// Returns the symbol of the character or raw byte or None if it belongs to none of the
+    /// character classes.
+    #[inline(always)]
+    pub(crate) fn symbol(&self, unit: Unit) -> Option<SymbolID> {
+        match unit {
+            Unit::Char(c) if c.is_ascii() => self.ascii[c as usize],
+            Unit::Char(c) => find_symbol(&self.ranges, c),
+            Unit::Byte(b) => find_symbol(&self.bytes, b),
+        }
+    }
+
+    /// Returns the symbols of the ASCII characters.
+    pub(crate) fn ascii_symbols(&self) -> &[Option<SymbolID>; 128] {
+        &self.ascii
+    }
+
+    /// Returns the sorted and disjoint ranges of the non-ASCII characters with their symbols.
+    pub(crate) fn ranges(&self) -> &[(char, char, SymbolID)] {
+        &self.ranges
+    }
+
+    /// Returns the sorted and disjoint ranges of the raw bytes with their symbols.
+    pub(crate) fn bytes(&self) -> &[(u8, u8, SymbolID)] {
+        &self.bytes
+    }
+
+    /// Returns the symbols the character class consists of.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn symbols_of_class(&self, char_class: CharClassID) -> &[SymbolID] {
+        self.class_symbols
+            .get(&char_class)
+            .map_or(&[], |symbols| symbols.as_slice())
+    }
+
+    /// Returns the number of symbols.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks that all symbols are valid and that the ranges are sorted and disjoint.
+    /// It is used to check deserialized alphabets.
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_consistent(&self) -> bool {
+        let is_symbol = |symbol: &SymbolID| symbol.as_usize() < self.len;
+        self.ascii.iter().flatten().all(is_symbol)
+            && self
+                .ranges
+                .iter()
+                .all(|(start, end, symbol)| !start.is_ascii() && start <= end && is_symbol(symbol))
+            && self.ranges.windows(2).all(|w| w[0].1 < w[1].0)
+            && self
+                .bytes
+                .iter()
+                .all(|(start, end, symbol)| *start >= 0x80 && start <= end && is_symbol(symbol))
+            && self.bytes.windows(2).all(|w| w[0].1 < w[1].0)
+    }
+
+    /// Returns the characters of the symbol as sorted ranges.
+    /// It is used for debugging purposes.
+    #[allow(dead_code)]
+    pub(crate) fn ranges_of_symbol(&self, symbol: SymbolID) -> Vec<(char, char)> {
+        let mut ranges: Vec<(char, char)> = Vec::new();
+        for (c, s) in self.ascii.iter().enumerate() {
+            if *s != Some(symbol) {
+                continue;
+            }
+            let c = c as u8 as char;
+            match ranges.last_mut() {
+                Some((_, end)) if *end as u32 + 1 == c as u32 => *end = c,
+                _ => ranges.push((c, c)),
+            }
+        }
+        ranges.extend(
+            self.ranges
+                .iter()
+                .filter(|(_, _, s)| *s == symbol)
+                .map(|(start, end, _)| (*start, *end)),
+        );
+        ranges
+    }
+
+    /// Returns the characters and raw bytes of the symbol in the notation of a character class,
+    /// e.g. `a-z\xFF`.
+    pub(crate) fn label_of_symbol(&self, symbol: SymbolID) -> String {
+        let mut label = String::new();
+        for (start, end) in self.ranges_of_symbol(symbol) {
+            if start == end {
+                label.push_str(&start.escape_debug().to_string());
+            } else {
+                label.push_str(&format!("{}-{}", start.escape_debug(), end.escape_debug()));
+            }
+        }
+        for (start, end, _) in self.bytes.iter().filter(|(_, _, s)| *s == symbol) {
+            if start == end {
+                label.push_str(&format!("\\x{:02X}", start));
+            } else {
+                label.push_str(&format!("\\x{:02X}-\\x{:02X}", start, end));
+            }
+        }
+        label
+    }
+}
+
+/// Appends the range `start..=end` of the symbol to the sorted ranges. It is merged with the last
+/// range if they are adjacent and belong to the same symbol.
+#[cfg(feature = "compiler")]
+fn add_range<T: Copy + Into<u32>>(
+    ranges: &mut Vec<(T, T, SymbolID)>,
+    start: T,
+    end: T,
+    symbol: SymbolID,
+) {
+    match ranges.last_mut() {
+        Some((_, last_end, last_symbol))
+            if *last_symbol == symbol && (*last_end).into() + 1 == start.into() =>
+        {
+            *last_end = end;
+        }
+        _ => ranges.push((start, end, symbol)),
+    }
+}
+
+/// Returns the symbol of the range that contains the value.
+#[inline(always)]
+fn find_symbol<T: Ord>(ranges: &[(T, T, SymbolID)], value: T) -> Option<SymbolID> {
+    ranges
+        .binary_search_by(|(start, end, _)| {
+            if *end < value {
+                std::cmp::Ordering::Less
+            } else if *start > value {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|i| ranges[i].2)
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self {
+            ascii: [None; 128],
+            ranges: Vec::new(),
+            bytes: Vec::new(),
+            #[cfg(feature = "compiler")]
+            class_symbols: FxHashMap::default(),
+            len: 0,
+        }
+    }
+}
+
+/// Serde supports arrays only up to 32 elements. The ASCII table is therefore serialized as a
+/// sequence.
+#[cfg(feature = "serde")]
+mod ascii_symbols {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::internal::SymbolID;
+
+    pub(super) fn serialize<S: Serializer>(
+        ascii: &[Option<SymbolID>; 128],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ascii.as_slice().serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[Option<SymbolID>; 128], D::Error> {
+        let ascii = Vec::<Option<SymbolID>>::deserialize(deserializer)?;
+        let len = ascii.len();
+        ascii
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"128 ASCII symbols"))
+    }
+}
+
+impl std::fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for symbol in 0..self.len {
+            let symbol = SymbolID::new(symbol as SymbolIDBase);
+            writeln!(f, "S{}: [{}]", symbol, self.label_of_symbol(symbol))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "compiler")]
+mod tests {
+    use super::*;
+
+    fn class(ranges: &[(char, char)]) -> hir::ClassUnicode {
+        hir::ClassUnicode::new(
+            ranges
+                .iter()
+                .map(|(start, end)| hir::ClassUnicodeRange::new(*start, *end)),
+        )
+    }
+
+    fn bytes(ranges: &[(u8, u8)]) -> hir::ClassBytes {
+        hir::ClassBytes::new(
+            ranges
+                .iter()
+                .map(|(start, end)| hir::ClassBytesRange::new(*start, *end)),
+        )
+    }
+
+    #[test]
+    fn test_alphabet_partition() {
+        let letters = class(&[('a', 'z')]);
+        let hex_digits = class(&[('0', '9'), ('a', 'f')]);
+        let not_x = class(&[('\0', 'w'), ('y', char::MAX)]);
+        let no_bytes = hir::ClassBytes::empty();
+        let alphabet = Alphabet::new([
+            (CharClassID::new(0), &letters, &no_bytes),
+            (CharClassID::new(1), &hex_digits, &no_bytes),
+            (CharClassID::new(2), &not_x, &no_bytes),
+        ]);
+        // [\0-/:-`{-\u{10FFFF}], [0-9], [a-f], [g-w y-z] and [x]
+        assert_eq!(alphabet.len(), 5);
+        assert_eq!(
+            alphabet.symbol(Unit::Char('a')),
+            alphabet.symbol(Unit::Char('f'))
+        );
+        assert_eq!(
+            alphabet.symbol(Unit::Char('0')),
+            alphabet.symbol(Unit::Char('9'))
+        );
+        assert_eq!(
+            alphabet.symbol(Unit::Char('g')),
+            alphabet.symbol(Unit::Char('z'))
+        );
+        assert_eq!(
+            alphabet.symbol(Unit::Char(' ')),
+            alphabet.symbol(Unit::Char('ä'))
+        );
+        assert_ne!(
+            alphabet.symbol(Unit::Char('a')),
+            alphabet.symbol(Unit::Char('g'))
+        );
+        assert_ne!(
+            alphabet.symbol(Unit::Char('x')),
+            alphabet.symbol(Unit::Char('y'))
+        );
+        assert_eq!(alphabet.symbols_of_class(CharClassID::new(0)).len(), 3);
+        assert_eq!(alphabet.symbols_of_class(CharClassID::new(1)).len(), 2);
+        assert_eq!(alphabet.symbols_of_class(CharClassID::new(2)).len(), 4);
+        assert_eq!(
+            alphabet.ranges_of_symbol(alphabet.symbol(Unit::Char('g')).unwrap()),
+            vec![('g', 'w'), ('y', 'z')]
+        );
+        assert_eq!(
+            alphabet.ranges_of_symbol(alphabet.symbol(Unit::Char('ä')).unwrap()),
+            vec![
+                ('\0', '/'),
+                (':', '`'),
+                ('{', '\x7F'),
+                ('\u{80}', char::MAX)
+            ]
+        );
+
+        // A character that belongs to no class has no symbol.
+        let alphabet = Alphabet::new([(CharClassID::new(0), &letters, &no_bytes)]);
+        assert_eq!(alphabet.symbol(Unit::Char('A')), None);
+        assert_eq!(alphabet.symbol(Unit::Char('\u{10FFFF}')), None);
+    }
+
+    #[test]
+    fn test_alphabet_raw_bytes() {
+        let any_char = class(&[('\0', char::MAX)]);
+        let high_bytes = bytes(&[(0x80, 0xFF)]);
+        let byte_ff = bytes(&[(0xFF, 0xFF)]);
+        let no_chars = hir::ClassUnicode::empty();
+        let alphabet = Alphabet::new([
+            (CharClassID::new(0), &any_char, &high_bytes),
+            (CharClassID::new(1), &no_chars, &byte_ff),
+        ]);
+        // [\0-\u{10FFFF}\x80-\xFE] and [\xFF]
+        assert_eq!(alphabet.len(), 2);
+        assert_eq!(
+            alphabet.symbol(Unit::Byte(0x80)),
+            alphabet.symbol(Unit::Char('\u{10FFFF}'))
+        );
+        assert_ne!(
+            alphabet.symbol(Unit::Byte(0xFF)),
+            alphabet.symbol(Unit::Char('\u{10FFFF}'))
+        );
+        assert_eq!(alphabet.symbols_of_class(CharClassID::new(0)).len(), 2);
+        assert_eq!(alphabet.symbols_of_class(CharClassID::new(1)).len(), 1);
+        assert_eq!(
+            alphabet.label_of_symbol(alphabet.symbol(Unit::Byte(0x80)).unwrap()),
+            r"\0-\u{7f}\u{80}-\u{10ffff}\x80-\xFE"
+        );
+
+        // Characters don't share their symbols with raw bytes.
+        let alphabet = Alphabet::new([(CharClassID::new(0), &any_char, &hir::ClassBytes::empty())]);
+        assert_eq!(alphabet.symbol(Unit::Byte(0x80)), None);
+        let alphabet = Alphabet::new([(CharClassID::new(0), &no_chars, &byte_ff)]);
+        assert_eq!(alphabet.symbol(Unit::Char('\u{10FFFF}')), None);
+        assert_eq!(alphabet.symbol(Unit::Char('\u{FF}')), None);
+    }
+}
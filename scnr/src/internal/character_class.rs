@@ -1,22 +1,28 @@
 use regex_syntax::ast::Ast;
 
-use super::{CharClassID, ComparableAst};
+use super::{CharClassID, ComparableAst, Flags};
 
 /// A character class that can match a character.
 #[derive(Default, Clone)]
 pub(crate) struct CharacterClass {
     pub(crate) id: CharClassID,
     pub(crate) ast: ComparableAst,
+    /// The flags that were in effect when the character class was defined in the pattern.
+    pub(crate) flags: Flags,
 }
 
 impl CharacterClass {
-    pub(crate) fn new(id: CharClassID, ast: Ast) -> Self {
+    pub(crate) fn new(id: CharClassID, ast: Ast, flags: Flags) -> Self {
         CharacterClass {
             id,
             ast: ComparableAst(ast),
+            flags,
         }
     }
 
+    /// Returns the AST of the character class.
+    /// It is used for debugging purposes mostly in the [crate::internal::dot] module.
+    #[allow(dead_code)]
     #[inline]
     pub(crate) fn ast(&self) -> &Ast {
         &self.ast.0
@@ -27,15 +33,15 @@ impl std::fmt::Debug for CharacterClass {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "CharacterClass {{ id: {:?}, ast: {:?} }}",
-            self.id, self.ast
+            "CharacterClass {{ id: {:?}, ast: {:?}, flags: {:?} }}",
+            self.id, self.ast, self.flags
         )
     }
 }
 
 impl std::fmt::Display for CharacterClass {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "#{} '{}'", self.id, self.ast)
+        write!(f, "#{} '{}{}'", self.id, self.flags, self.ast)
     }
 }
 
@@ -43,6 +49,7 @@ impl std::hash::Hash for CharacterClass {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.id.hash(state);
         self.ast.hash(state);
+        self.flags.hash(state);
         // Do not hash the match function, because it is not relevant for equality.
         // Actually it is calculated from the AST, so it would be redundant.
     }
@@ -50,7 +57,7 @@ impl std::hash::Hash for CharacterClass {
 
 impl PartialEq for CharacterClass {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.ast == other.ast
+        self.id == other.id && self.ast == other.ast && self.flags == other.flags
     }
 }
 
@@ -58,7 +65,7 @@ impl Eq for CharacterClass {}
 
 impl PartialOrd for CharacterClass {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.id.cmp(&other.id))
+        Some(self.cmp(other))
     }
 }
 
@@ -101,9 +108,9 @@ mod tests {
         let ast1 = Literal!('a');
         let ast2 = Literal!('a');
         let ast3 = Literal!('b');
-        let class1 = CharacterClass::new(0.into(), ast1);
-        let class2 = CharacterClass::new(0.into(), ast2);
-        let class3 = CharacterClass::new(1.into(), ast3);
+        let class1 = CharacterClass::new(0.into(), ast1, Flags::default());
+        let class2 = CharacterClass::new(0.into(), ast2, Flags::default());
+        let class3 = CharacterClass::new(1.into(), ast3, Flags::default());
         assert_eq!(class1, class2);
         assert_ne!(class1, class3);
     }
@@ -112,8 +119,8 @@ mod tests {
     fn test_character_class_equality_special() {
         let ast1 = parse_regex_syntax("\r").unwrap();
         if let Ast::Literal(_) = &ast1 {
-            let class1 = CharacterClass::new(0.into(), ast1.clone());
-            let class2 = CharacterClass::new(0.into(), Literal!('\r'));
+            let class1 = CharacterClass::new(0.into(), ast1.clone(), Flags::default());
+            let class2 = CharacterClass::new(0.into(), Literal!('\r'), Flags::default());
             eprintln!("{:?} <=> {:?}", class1.ast(), class2.ast());
             assert_eq!(class1, class2);
         } else {
@@ -125,8 +132,8 @@ mod tests {
     fn test_character_class_ordering() {
         let ast1 = Literal!('a');
         let ast2 = Literal!('b');
-        let class1 = CharacterClass::new(0.into(), ast1);
-        let class2 = CharacterClass::new(1.into(), ast2);
+        let class1 = CharacterClass::new(0.into(), ast1, Flags::default());
+        let class2 = CharacterClass::new(1.into(), ast2, Flags::default());
         assert!(class1 < class2);
         assert!(class2 > class1);
     }
@@ -1,12 +1,18 @@
 use regex_syntax::ast::Ast;
 
-use super::{ids::CharClassIDBase, CharClassID, CharacterClass, ComparableAst};
-use crate::{internal::MatchFunction, Result, ScnrError};
+use super::{
+    ids::CharClassIDBase, Alphabet, CharClassID, CharacterClass, ComparableAst, Flags,
+    MatchFunction,
+};
+use crate::Result;
 
 /// CharacterClassRegistry is a registry of character classes.
 #[derive(Debug, Clone, Default)]
 pub(crate) struct CharacterClassRegistry {
     character_classes: Vec<CharacterClass>,
+    /// The match function of each character class.
+    /// The index of the vector is the character class id.
+    match_functions: Vec<MatchFunction>,
 }
 
 impl CharacterClassRegistry {
@@ -14,6 +20,7 @@ impl CharacterClassRegistry {
     pub(crate) fn new() -> Self {
         Self {
             character_classes: Vec::new(),
+            match_functions: Vec::new(),
         }
     }
 
@@ -25,22 +32,41 @@ impl CharacterClassRegistry {
     }
 
     /// Adds a character class to the registry if it is not already present and returns its ID.
-    pub(crate) fn add_character_class(&mut self, ast: &Ast) -> CharClassID {
+    /// Only the flags that are relevant for matching a single character are considered.
+    /// An error is returned if the character class uses an unsupported feature.
+    pub(crate) fn add_character_class(&mut self, ast: &Ast, flags: Flags) -> Result<CharClassID> {
         let character_class = ComparableAst(ast.clone());
+        let flags = flags.char_class_flags();
         if let Some(id) = self
             .character_classes
             .iter()
-            .position(|cc| cc.ast == character_class)
+            .position(|cc| cc.ast == character_class && cc.flags == flags)
         {
-            CharClassID::new(id as CharClassIDBase)
+            Ok(CharClassID::new(id as CharClassIDBase))
         } else {
+            let match_function: MatchFunction = (ast, flags).try_into()?;
             let id = CharClassID::new(self.character_classes.len() as CharClassIDBase);
             self.character_classes
-                .push(CharacterClass::new(id, character_class.0));
-            id
+                .push(CharacterClass::new(id, character_class.0, flags));
+            self.match_functions.push(match_function);
+            Ok(id)
         }
     }
 
+    /// Creates the alphabet for a DFA whose transitions use the given character classes.
+    pub(crate) fn create_alphabet(
+        &self,
+        char_classes: impl IntoIterator<Item = CharClassID>,
+    ) -> Alphabet {
+        let mut char_classes = char_classes.into_iter().collect::<Vec<_>>();
+        char_classes.sort_unstable();
+        char_classes.dedup();
+        Alphabet::new(char_classes.into_iter().map(|id| {
+            let match_function = &self.match_functions[id.as_usize()];
+            (id, match_function.class(), match_function.bytes())
+        }))
+    }
+
     /// Returns the character class with the given ID.
     /// It is used for debugging purposes mostly in the [crate::internal::dot] module.
     #[allow(unused)]
@@ -63,26 +89,15 @@ impl CharacterClassRegistry {
     }
 
     /// Creates a match function for the character classes in the registry.
-    ///
-    /// Safety:
-    ///     The callers ensure that the character classes in the registry are valid.
-    ///     All character classes in the registry are valid which is guaranteed by the construction
-    ///     of the registry.
+    /// The DFAs don't need it because they run on their alphabets. It is used in tests to check
+    /// the alphabets against the match functions of the character classes.
+    #[allow(dead_code)]
     pub(crate) fn create_match_char_class(
         &self,
     ) -> Result<Box<dyn (Fn(CharClassID, char) -> bool) + 'static + Send + Sync>> {
-        let match_functions =
-            self.character_classes
-                .iter()
-                .try_fold(Vec::new(), |mut acc, cc| {
-                    // trace!("Create match function for char class {:?}", cc);
-                    let match_function: MatchFunction = cc.ast().try_into()?;
-                    acc.push(match_function);
-                    Ok::<Vec<MatchFunction>, ScnrError>(acc)
-                })?;
+        let match_functions = self.match_functions.clone();
         Ok(Box::new(move |char_class, c| {
-            // trace!("Match char class #{} '{}' -> {:?}", char_class.id(), c, res);
-            unsafe { match_functions.get_unchecked(char_class.as_usize()).call(c) }
+            match_functions[char_class.as_usize()].call(c)
         }))
     }
 }
@@ -1,48 +1,81 @@
-use std::{
-    char,
-    collections::{BTreeSet, VecDeque},
-};
-
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::FxHashMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use crate::{Match, Pattern, Result, Span};
+#[cfg(any(feature = "compiler", feature = "serde"))]
+use crate::Result;
+#[cfg(feature = "compiler")]
+use crate::{CompileLimits, Pattern};
+use crate::{Match, Span};
 
+#[cfg(feature = "compiler")]
+use super::{
+    determinizer::Determinizer, minimizer::Minimizer, parse_regex_syntax, CharacterClassRegistry,
+    MultiPatternNfa, Nfa, TerminalIDBase,
+};
 use super::{
-    ids::StateSetID, minimizer::Minimizer, parse_regex_syntax, CharClassID, CharacterClassRegistry,
-    CompiledLookahead, MultiPatternNfa, Nfa, StateID, StateIDBase, TerminalID, TerminalIDBase,
+    ids::StateSetID, look_context, Alphabet, CharIndices, CompiledLookahead, CompiledLookbehind,
+    Haystack, StateIDBase, SymbolID, TerminalID, Unit, LOOK_CONTEXTS,
 };
 
+/// The target of a transition that doesn't exist. The search stops when it is reached.
+pub(crate) const DEAD_STATE: StateSetID = StateSetID::new(StateIDBase::MAX);
+
 /// A compiled DFA.
 /// It represents the DFA in a way that is optimized for matching.
 ///
-/// Although the data represent a DFA, i.e. it has no epsilon transitions, the transitions are
-/// considered non-deterministic because they involve overlapping character classes. This allows a
-/// transition to multiple states when reading a single character if that character belongs to
-/// multiple character classes.
+/// The transitions are taken on the symbols of the [Alphabet] of the DFA. The symbols are disjoint
+/// sets of characters, so each state has at most one target state per character and the search
+/// follows a single state. The transitions are stored in a dense table with one row per state.
 /// See implementation of the [find_from] method.
 ///
 /// The start state is by design always 0.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub(crate) struct CompiledDfa {
-    /// The pattern(s) of the DFA. Used for debugging purposes.
+    /// The patterns of the DFA in priority order. Used for debugging purposes.
     pub(crate) patterns: Vec<String>,
     /// The terminal ids of the DFA in priority order. Lower indices have higher priority.
     pub(crate) terminal_ids: Vec<TerminalID>,
+    /// The alphabet of the DFA. It maps the characters to the columns of the transition table.
+    pub(crate) alphabet: Alphabet,
     /// The states of the DFA.
     pub(crate) states: Vec<StateData>,
-    /// The accepting states of the DFA are represented by a vector of booleans and terminal ids.
-    /// The index of the vector is the state id.
-    /// If the value is true, the state is an accepting state.
-    /// This is an optimization to avoid the need to search the end states during the simulation.
-    pub(crate) end_states: Vec<(bool, TerminalID)>,
+    /// The transition table. The target state of the state `s` on the symbol `a` is found at the
+    /// index `s * alphabet.len() + a`. Missing transitions lead to [DEAD_STATE].
+    pub(crate) transitions: Vec<StateSetID>,
     /// An optional lookahead that is used to check if the DFA should match the input.
     pub(crate) lookaheads: FxHashMap<TerminalID, CompiledLookahead>,
+    /// An optional lookbehind that is used to check if the DFA should match the input.
+    pub(crate) lookbehinds: FxHashMap<TerminalID, CompiledLookbehind>,
+}
 
-    /// Current and next states of the DFA. They are used during the simulation of the DFA.
-    /// For performance reasons we hold them here. This avoids the need to repeatedly allocate and
-    /// drop them again during the simulation.
-    pub(crate) current_states: Vec<StateSetID>,
-    pub(crate) next_states: Vec<StateSetID>,
+/// The data of a single search that is run by [CompiledDfa::find_from].
+/// All positions except `slice_start` are relative to the start of the slice the search runs on.
+struct Search<'h> {
+    /// The whole input.
+    input: Haystack<'h>,
+    /// The start of the slice the search runs on within the input.
+    slice_start: usize,
+    /// The start position of the match.
+    match_start: usize,
+    /// The end position of the longest match found so far.
+    match_end: Option<usize>,
+    /// The terminal id of the match found so far.
+    match_terminal_id: Option<TerminalID>,
+    /// The lookbehinds that have already been checked during this search, one bit per terminal at
+    /// the index of its priority. The lookbehinds only depend on the match start, so each one is
+    /// checked at most once. Terminals whose priority exceeds the bits are checked again for
+    /// every candidate end position.
+    lookbehinds_checked: u128,
+    /// The results of the checked lookbehinds, with the same bits as `lookbehinds_checked`.
+    lookbehinds_satisfied: u128,
+    /// The start of the input that the search and its lookbehinds have read so far, as position in
+    /// the whole input. The character before it can have been read as well.
+    read_start: usize,
+    /// The end of the input that the search and its lookaheads have read so far. It is one past
+    /// the end of the input if the search has checked for the end of the input.
+    read_end: usize,
 }
 
 impl CompiledDfa {
@@ -52,128 +85,278 @@ impl CompiledDfa {
     ///
     /// If no match is found, None is returned.
     ///
-    /// We use a non-recursive implementation of the DFA simulation.
-    /// The algorithm uses a queue to store the states that are currently active.
     /// The algorithm is as follows:
-    /// 1. Add the start state to the queue.
+    /// 1. Start in the start state.
     /// 2. Take the next character from the input.
-    /// 3. If the queue is empty, stop and return the current match, if any.
-    /// 4. For each state in the queue, check if it is an end state.
-    ///    If it is, remember the current match if it is longer than the previous match found and
-    ///    its terminal id is not higher at the same length.
-    /// 5. For each state in the queue, check if there is a transition that matches the current
-    ///    character.
-    ///    If there is, add the target state to a second queue that will be used for the next
-    ///    character.
-    /// 6. Replace the queue with the second queue.
-    /// 7. If there are more characters in the input, go to step 2.
+    /// 3. If the state has zero-width assertions, move to the state that is reached by the
+    ///    assertions that hold between the previous and the current character. If it is accepting,
+    ///    handle it like in step 5.
+    /// 4. Take the transition on the symbol of the character. Stop if there is none.
+    /// 5. If the new state is accepting, remember the current match if it is longer than the
+    ///    previous match found and its terminal id is not higher at the same length.
+    /// 6. If there are more characters in the input, go to step 2. Otherwise apply step 3 at the
+    ///    end of the input.
     ///
+    /// The second value of the result is the start of the input that the search and the
+    /// lookbehinds have read, as position in the whole input. The result also depends on the
+    /// character before this position. It is the start position of the search if no lookbehind
+    /// has read further back.
+    ///
+    /// The third value of the result is the end of the input that the search has read, relative
+    /// to the start of the slice. The result only depends on the input before this position. It
+    /// is one past the end of the input if the search has checked for the end of the input. In
+    /// this case the result can change when more input follows, e.g. in a stream.
     #[inline(always)]
-    pub(crate) fn find_from(
-        &mut self,
-        input: &str,
-        char_indices: std::str::CharIndices,
-        match_char_class: &(dyn Fn(CharClassID, char) -> bool + 'static),
-    ) -> Option<Match> {
-        self.current_states.clear();
-        // Push the start state to the current states.
-        self.current_states.push(StateSetID::new(0));
-        self.next_states.clear();
-        let mut match_start = None;
-        let mut match_end = None;
-        let mut match_terminal_id = None;
-        for (index, c) in char_indices {
-            if match_start.is_none() {
-                // A potential match starts always at the first position.
-                // Is is only part of a valid match if match_end is also set in the inner for loop.
-                match_start = Some(index);
+    pub(crate) fn find_from_partial(
+        &self,
+        input: Haystack,
+        mut char_indices: CharIndices,
+    ) -> (Option<Match>, usize, usize) {
+        // The char_indices iterator can run over a slice that ends at the end of the input.
+        // Zero-width assertions and lookaheads are evaluated with respect to the whole input.
+        let slice_start = char_indices.slice_start();
+        let mut search = Search {
+            input,
+            slice_start,
+            // A potential match starts always at the first position.
+            // Is is only part of a valid match if match_end is also set.
+            match_start: char_indices.offset(),
+            match_end: None,
+            match_terminal_id: None,
+            lookbehinds_checked: 0,
+            lookbehinds_satisfied: 0,
+            read_start: slice_start + char_indices.offset(),
+            read_end: 0,
+        };
+        // The character before the start position is needed to evaluate zero-width assertions.
+        let mut prev = input
+            .char_before(slice_start + search.match_start)
+            .map(|(_, c)| c);
+        let mut state = StateSetID::new(0);
+        let mut exhausted = true;
+        while let Some((index, c)) = char_indices.next() {
+            search.read_end = search.read_end.max(char_indices.offset());
+            if !self.states[state].looks.is_empty() {
+                state = self.follow_looks(&mut search, state, index, prev, Some(c));
+            }
+            let next_state = match self.alphabet.symbol(c) {
+                Some(symbol) => self.transition(state, symbol),
+                None => DEAD_STATE,
+            };
+            if next_state == DEAD_STATE {
+                exhausted = false;
+                break;
+            }
+            state = next_state;
+            if !self.states[state].accepting.is_empty() {
+                state = self.accept(&mut search, char_indices.offset(), state);
             }
+            prev = Some(c);
+        }
+        if exhausted {
+            search.read_end = input.len() - slice_start + 1;
+            if !self.states[state].looks.is_empty() {
+                // We are at the end of the input.
+                let index = input.len() - slice_start;
+                self.follow_looks(&mut search, state, index, prev, None);
+            }
+        }
+        let Search {
+            match_start,
+            match_end,
+            match_terminal_id,
+            read_start,
+            read_end,
+            ..
+        } = search;
+        let matched = match_terminal_id.map(|match_terminal_id| {
+            // If the terminal id is set, match_end must always be set as well.
+            Match::new(
+                match_terminal_id.as_usize(),
+                Span::new(match_start, match_end.unwrap()),
+            )
+        });
+        (matched, read_start, read_end)
+    }
+
+    /// Simulates the DFA on the given input and returns the match, see [Self::find_from_partial].
+    #[cfg(all(test, feature = "compiler"))]
+    pub(crate) fn find_from(&self, input: Haystack, char_indices: CharIndices) -> Option<Match> {
+        self.find_from_partial(input, char_indices).0
+    }
 
-            for state in self.current_states.iter() {
-                if match_end.is_none() && self.end_states[*state].0 {
-                    match_end = Some(index);
+    /// Returns the target state of the transition of the state on the symbol.
+    #[inline(always)]
+    fn transition(&self, state: StateSetID, symbol: SymbolID) -> StateSetID {
+        self.transitions[state.as_usize() * self.alphabet.len() + symbol.as_usize()]
+    }
+
+    /// Returns the state that is reached from the given state via the zero-width assertions that
+    /// hold between the characters `prev` and `next`. The state must have look transitions.
+    #[inline(always)]
+    fn look_target(&self, state: StateSetID, prev: Option<Unit>, next: Option<Unit>) -> StateSetID {
+        let (prev, next) = (prev.map(Unit::as_char), next.map(Unit::as_char));
+        self.states[state].looks[look_context(prev) * LOOK_CONTEXTS + look_context(next)]
+    }
+
+    /// Moves to the state that is reached via the zero-width assertions that hold between the
+    /// characters `prev` and `next` at the given position.
+    /// If this state is accepting, it matches up to this position.
+    #[inline(always)]
+    fn follow_looks(
+        &self,
+        search: &mut Search,
+        state: StateSetID,
+        index: usize,
+        prev: Option<Unit>,
+        next: Option<Unit>,
+    ) -> StateSetID {
+        let target = self.look_target(state, prev, next);
+        // Empty matches are not allowed.
+        if target != state
+            && index > search.match_start
+            && !self.states[target].accepting.is_empty()
+        {
+            return self.accept(search, index, target);
+        }
+        target
+    }
+
+    /// Handles the case that the accepting state `state` is reached at position `end`.
+    /// Each terminal of the state is tried in priority order.
+    ///
+    /// Returns the state to continue with. It differs from `state` if a non-greedy pattern with
+    /// a lookahead has matched, because the pattern is finished then.
+    #[inline(always)]
+    fn accept(&self, search: &mut Search, end: usize, state: StateSetID) -> StateSetID {
+        let mut current = state;
+        for (terminal_id, drop_target) in &self.states[state].accepting {
+            if self.accept_terminal(search, end, *terminal_id) && drop_target.is_some() {
+                // Other non-greedy patterns can have been dropped before, so the state to
+                // continue with is taken from the current state.
+                if let Some((_, Some(target))) = self.states[current]
+                    .accepting
+                    .iter()
+                    .find(|(id, _)| id == terminal_id)
+                {
+                    current = *target;
                 }
-                for (cc, next) in &self.states[*state].transitions {
-                    if match_char_class(*cc, c) {
-                        if !self.next_states.contains(next) {
-                            self.next_states.push(*next);
-                        }
-                        if self.end_states[*next].0 {
-                            let mut lookahead_len = 0;
-                            // Check if a lookahead is present and if it is satisfied.
-                            if let Some(lookahead) = self.lookaheads.get(&self.end_states[*next].1)
-                            {
-                                // Create a CharIndices iterator starting from the current position.
-                                if let Some((_, next_slice)) =
-                                    input.split_at_checked(index + c.len_utf8())
-                                {
-                                    let char_indices = next_slice.char_indices();
-                                    let mut lookahead = lookahead.clone();
-                                    let (satisfied, len) = lookahead.satisfies_lookahead(
-                                        next_slice,
-                                        char_indices,
-                                        match_char_class,
-                                    );
-                                    if !satisfied {
-                                        continue;
-                                    }
-                                    lookahead_len = len;
-                                } else {
-                                    // We are at the end of the input.
-                                    // If the lookahead is positive it is not satisfied, otherwise
-                                    // we can accept the match.
-                                    if lookahead.is_positive {
-                                        continue;
-                                    }
-                                }
-                            }
-                            // Update the match end and terminal id if the match is longer or the
-                            // terminal id is lower.
-                            if let Some(match_end_index) = match_end.as_ref() {
-                                match (index + c.len_utf8()).cmp(&(match_end_index + lookahead_len))
-                                {
-                                    std::cmp::Ordering::Greater => {
-                                        match_end = Some(index + c.len_utf8());
-                                        match_terminal_id = Some(self.end_states[*next].1);
-                                    }
-                                    std::cmp::Ordering::Equal => {
-                                        let terminal_id =
-                                            self.priority_of(self.end_states[*next].1);
-                                        if terminal_id
-                                            < self.priority_of(match_terminal_id.unwrap())
-                                        {
-                                            match_terminal_id = Some(self.end_states[*next].1);
-                                        }
-                                    }
-                                    std::cmp::Ordering::Less => {
-                                        match_terminal_id = Some(self.end_states[*next].1);
-                                    }
-                                }
-                            } else {
-                                match_end = Some(index + c.len_utf8());
-                                match_terminal_id = Some(self.end_states[*next].1);
-                            }
-                        }
+            }
+        }
+        current
+    }
+
+    /// Handles the match of the terminal that ends at position `end`.
+    /// The match is taken if its lookbehind and its lookahead are satisfied and if it is longer
+    /// than the match found so far or if it has a higher priority.
+    ///
+    /// Returns true if the lookarounds of the terminal are satisfied.
+    #[inline(always)]
+    fn accept_terminal(&self, search: &mut Search, end: usize, terminal_id: TerminalID) -> bool {
+        // Check if a lookbehind is present and if it is satisfied.
+        if let Some(lookbehind) = self.lookbehinds.get(&terminal_id) {
+            let bit = 1u128
+                .checked_shl(self.priority_of(terminal_id) as u32)
+                .unwrap_or(0);
+            let satisfied = if search.lookbehinds_checked & bit != 0 {
+                search.lookbehinds_satisfied & bit != 0
+            } else {
+                let (satisfied, read_start) = lookbehind
+                    .satisfies_lookbehind(search.input, search.slice_start + search.match_start);
+                search.read_start = search.read_start.min(read_start);
+                search.lookbehinds_checked |= bit;
+                if satisfied {
+                    search.lookbehinds_satisfied |= bit;
+                }
+                satisfied
+            };
+            if !satisfied {
+                return false;
+            }
+        }
+        let mut lookahead_len = 0;
+        // Check if a lookahead is present and if it is satisfied.
+        if let Some(lookahead) = self.lookaheads.get(&terminal_id) {
+            // Create a CharIndices iterator starting from the current position.
+            let (satisfied, len, read_end) = lookahead.satisfies_lookahead(
+                search.input,
+                search.input.char_indices(search.slice_start + end),
+            );
+            search.read_end = search.read_end.max(end + read_end);
+            if !satisfied {
+                return false;
+            }
+            lookahead_len = len;
+        }
+        // Update the match end and terminal id if the match is longer or the terminal id is
+        // lower.
+        if let Some(match_end_index) = search.match_end.as_ref() {
+            match end.cmp(&(match_end_index + lookahead_len)) {
+                std::cmp::Ordering::Greater => {
+                    search.match_end = Some(end);
+                    search.match_terminal_id = Some(terminal_id);
+                }
+                std::cmp::Ordering::Equal => {
+                    if self.priority_of(terminal_id)
+                        < self.priority_of(search.match_terminal_id.unwrap())
+                    {
+                        search.match_terminal_id = Some(terminal_id);
                     }
                 }
+                std::cmp::Ordering::Less => {
+                    search.match_terminal_id = Some(terminal_id);
+                }
             }
-            self.current_states.clear();
-            std::mem::swap(&mut self.current_states, &mut self.next_states);
-            if self.current_states.is_empty() {
-                break;
+        } else {
+            search.match_end = Some(end);
+            search.match_terminal_id = Some(terminal_id);
+        }
+        true
+    }
+
+    /// Checks if the DFA matches a suffix of the haystack that ends at the position `end`.
+    ///
+    /// The DFA is simulated from the position `end` backwards. It is used for lookbehinds
+    /// where the DFA was created from the reversed NFA of the lookbehind pattern. The first
+    /// accepting state that is reached suffices, and empty matches are allowed here.
+    /// Zero-width assertions see the position `end` as end of the input.
+    ///
+    /// The second value of the result is the start of the input that has been read. The character
+    /// before it can have been read as well.
+    pub(crate) fn matches_suffix(&self, haystack: Haystack, end: usize) -> (bool, usize) {
+        let mut position = end;
+        let mut state = StateSetID::new(0);
+        // The character after the current position, i.e. the one that was read last.
+        let mut next = None;
+        loop {
+            let prev = haystack.char_before(position);
+            if !self.states[state].accepting.is_empty() {
+                return (true, position);
+            }
+            if !self.states[state].looks.is_empty() {
+                state = self.look_target(state, prev.map(|(_, c)| c), next);
+                if !self.states[state].accepting.is_empty() {
+                    return (true, position);
+                }
+            }
+            let Some((start, c)) = prev else {
+                return (false, position);
+            };
+            position = start;
+            state = match self.alphabet.symbol(c) {
+                Some(symbol) => self.transition(state, symbol),
+                None => DEAD_STATE,
+            };
+            if state == DEAD_STATE {
+                return (false, position);
             }
+            next = Some(c);
         }
-        match_terminal_id.map(|match_terminal_id| {
-            // If the terminal id is set, match_start and match_end must always be set as well.
-            Match::new(
-                match_terminal_id.as_usize(),
-                Span::new(match_start.unwrap(), match_end.unwrap()),
-            )
-        })
     }
 
-    /// Create a compiled NFA from a pattern.
+    /// Create a compiled DFA from a pattern.
     /// Used for testing and debugging purposes.
+    #[cfg(feature = "compiler")]
     #[allow(dead_code)]
     pub(crate) fn try_from_pattern(
         pattern: &Pattern,
@@ -181,260 +364,250 @@ impl CompiledDfa {
     ) -> Result<Self> {
         let ast = parse_regex_syntax(pattern.pattern())?;
         let mut nfa: Nfa = Nfa::try_from_ast(ast, character_class_registry)?;
-        nfa.set_terminal_id(pattern.terminal_id());
-        let mut nfa: CompiledDfa = nfa.into();
-        nfa.lookaheads = FxHashMap::default();
+        // The lookaround of the pattern is needed to handle the accepting states.
+        nfa.pattern = pattern.clone();
+        let mut dfa =
+            Self::try_from_nfa(&nfa, character_class_registry, &CompileLimits::default())?;
         if let Some(lookahead) = pattern.lookahead() {
-            let lookahead =
-                CompiledLookahead::try_from_lookahead(lookahead, character_class_registry)?;
-            nfa.lookaheads
-                .insert((pattern.terminal_id() as TerminalIDBase).into(), lookahead);
+            let lookahead = CompiledLookahead::try_from_lookahead(
+                lookahead,
+                character_class_registry,
+                &CompileLimits::default(),
+            )?;
+            dfa.add_lookahead((pattern.terminal_id() as TerminalIDBase).into(), lookahead);
         }
-        Ok(nfa)
+        if let Some(lookbehind) = pattern.lookbehind() {
+            let lookbehind = CompiledLookbehind::try_from_lookbehind(
+                lookbehind,
+                character_class_registry,
+                &CompileLimits::default(),
+            )?;
+            dfa.add_lookbehind((pattern.terminal_id() as TerminalIDBase).into(), lookbehind);
+        }
+        Ok(dfa)
     }
 
+    #[cfg(feature = "compiler")]
     pub(crate) fn try_from_patterns(
         patterns: &[Pattern],
         character_class_registry: &mut CharacterClassRegistry,
+        limits: &CompileLimits,
     ) -> Result<Self> {
-        let mp_nfa = MultiPatternNfa::try_from_patterns(patterns, character_class_registry)?;
-        let mut compiled_dfa: CompiledDfa = mp_nfa.into();
-        // Add the lookaheads to the compiled NFA.
+        let mp_nfa =
+            MultiPatternNfa::try_from_patterns(patterns, character_class_registry, limits)?;
+        let mut compiled_dfa =
+            CompiledDfa::try_from_multi_pattern_nfa(&mp_nfa, character_class_registry, limits)?;
+        // Add the lookaheads and lookbehinds to the compiled DFA.
         for pattern in patterns.iter() {
             if let Some(lookahead) = pattern.lookahead() {
-                let lookahead =
-                    CompiledLookahead::try_from_lookahead(lookahead, character_class_registry)?;
+                let lookahead = CompiledLookahead::try_from_lookahead(
+                    lookahead,
+                    character_class_registry,
+                    limits,
+                )
+                .map_err(|e| e.with_pattern(pattern.pattern()))?;
                 compiled_dfa
                     .add_lookahead((pattern.terminal_id() as TerminalIDBase).into(), lookahead);
             }
+            if let Some(lookbehind) = pattern.lookbehind() {
+                let lookbehind = CompiledLookbehind::try_from_lookbehind(
+                    lookbehind,
+                    character_class_registry,
+                    limits,
+                )
+                .map_err(|e| e.with_pattern(pattern.pattern()))?;
+                compiled_dfa
+                    .add_lookbehind((pattern.terminal_id() as TerminalIDBase).into(), lookbehind);
+            }
         }
         Ok(compiled_dfa)
     }
 
-    /// Add a lookahead for a given terminal_id to the compiled NFA.
+    /// Creates the minimized CompiledDfa of a single NFA by the subset construction.
+    ///
+    /// Note that the lookahead and the lookbehind are not set in the resulting CompiledDfa. This
+    /// must be done separately. See [CompiledDfa::try_from_pattern].
+    #[cfg(feature = "compiler")]
+    pub(crate) fn try_from_nfa(
+        nfa: &Nfa,
+        character_class_registry: &CharacterClassRegistry,
+        limits: &CompileLimits,
+    ) -> Result<Self> {
+        let dfa = Determinizer::from_nfa(nfa, character_class_registry, limits).determinize()?;
+        Ok(Minimizer::minimize(dfa))
+    }
+
+    /// Creates the minimized CompiledDfa of a multi-pattern NFA by the subset construction.
+    /// An error is returned if the number of DFA states exceeds the limit. In this case the
+    /// pattern whose NFA states led to the new DFA state is named in the error.
+    ///
+    /// Note that the lookahead and the lookbehind are not set in the resulting CompiledDfa. This
+    /// must be done separately. See [CompiledDfa::try_from_patterns].
+    #[cfg(feature = "compiler")]
+    fn try_from_multi_pattern_nfa(
+        mp_nfa: &MultiPatternNfa,
+        character_class_registry: &CharacterClassRegistry,
+        limits: &CompileLimits,
+    ) -> Result<Self> {
+        let dfa = Determinizer::from_multi_pattern_nfa(mp_nfa, character_class_registry, limits)
+            .determinize()?;
+        Ok(Minimizer::minimize(dfa))
+    }
+
+    /// Add a lookahead for a given terminal_id to the compiled DFA.
+    #[cfg(feature = "compiler")]
     pub(crate) fn add_lookahead(&mut self, terminal_id: TerminalID, lookahead: CompiledLookahead) {
         self.lookaheads.insert(terminal_id, lookahead);
     }
 
+    /// Add a lookbehind for a given terminal_id to the compiled DFA.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn add_lookbehind(
+        &mut self,
+        terminal_id: TerminalID,
+        lookbehind: CompiledLookbehind,
+    ) {
+        self.lookbehinds.insert(terminal_id, lookbehind);
+    }
+
     /// Returns the pattern for the given terminal id.
     pub(crate) fn pattern(&self, terminal_id: TerminalID) -> &str {
-        &self.patterns[terminal_id]
+        &self.patterns[self.priority_of(terminal_id)]
     }
 
-    #[inline(always)]
-    fn priority_of(&self, terminal_id: TerminalID) -> usize {
-        self.terminal_ids
+    /// Returns the transitions of the state as pairs of symbol and target state.
+    /// Transitions to the dead state are omitted.
+    pub(crate) fn transitions_of(
+        &self,
+        state: StateSetID,
+    ) -> impl Iterator<Item = (SymbolID, StateSetID)> + '_ {
+        let len = self.alphabet.len();
+        self.transitions[state.as_usize() * len..(state.as_usize() + 1) * len]
             .iter()
-            .position(|&id| id == terminal_id)
-            .unwrap()
+            .enumerate()
+            .filter(|(_, target)| **target != DEAD_STATE)
+            .map(|(symbol, target)| (SymbolID::new(symbol as _), *target))
     }
-}
 
-impl From<Nfa> for CompiledDfa {
-    /// Create a dense representation of the NFA in form of match transitions between states sets.
-    /// This is an equivalent algorithm to the subset construction for DFAs.
-    ///
-    /// Note that the lookahead is not set in the resulting CompiledDfa. This must be done
-    /// separately because a character class registry is needed to create the lookaheads.
-    /// See [CompiledDfa::try_from_pattern].
-    fn from(nfa: Nfa) -> Self {
-        // A temporary map to store the state ids of the sets of states.
-        let mut state_map: FxHashMap<BTreeSet<StateID>, StateSetID> = FxHashMap::default();
-        // A temporary set to store the transitions of the CompiledDfa.
-        // The state ids are numbers of sets of states.
-        let mut transitions: FxHashSet<(StateSetID, CharClassID, StateSetID)> =
-            FxHashSet::default();
-        // The end states of the CompiledDfa are a vector of state ids and terminal ids.
-        let mut accepting_states: Vec<(StateSetID, usize)> = Vec::new();
-        // Calculate the epsilon closure of the start state.
-        let epsilon_closure: BTreeSet<StateID> =
-            BTreeSet::from_iter(nfa.epsilon_closure(nfa.start_state));
-        // The current state id is always 0.
-        let current_state = StateSetID::new(0);
-        // Add the start state to the state map.
-        state_map.insert(epsilon_closure.clone(), current_state);
-
-        // The list of target states not yet processed.
-        let mut queue: VecDeque<StateSetID> = VecDeque::new();
-        queue.push_back(current_state);
-
-        while let Some(current_state) = queue.pop_front() {
-            let epsilon_closure = state_map
-                .iter()
-                .find(|(_, v)| **v == current_state)
-                .unwrap()
-                .0
-                .clone();
-            let target_states = nfa.get_match_transitions(epsilon_closure.iter().cloned());
-            let old_state_id = current_state;
-            // Transform the target states to a set of state ids by calculating their epsilon closure.
-            for (cc, target_state) in target_states {
-                let epsilon_closure = BTreeSet::from_iter(nfa.epsilon_closure(target_state));
-                let new_state_id_candidate = state_map.len() as StateIDBase;
-                let new_state_id = *state_map.entry(epsilon_closure.clone()).or_insert_with(|| {
-                    let new_state_id = StateSetID::new(new_state_id_candidate);
-                    // Add the new state to the queue.
-                    queue.push_back(new_state_id);
-                    new_state_id
-                });
-                if epsilon_closure.contains(&nfa.end_state)
-                    && !accepting_states.contains(&(new_state_id, nfa.pattern.terminal_id()))
-                {
-                    accepting_states.push((new_state_id, nfa.pattern.terminal_id()));
-                }
-                transitions.insert((old_state_id, cc, new_state_id));
-            }
-        }
-
-        // The transitions of the CompiledDfa.
-        let mut states: Vec<StateData> = Vec::with_capacity(transitions.len());
-        for _ in 0..state_map.len() {
-            states.push(StateData::new());
+    /// Checks the consistency of a deserialized DFA. All states, symbols and terminals that the
+    /// search can run into must exist.
+    #[cfg(feature = "serde")]
+    pub(crate) fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| {
+            Err(super::serialization::invalid_scanner(format!(
+                "{} in the DFA of '{}'",
+                reason,
+                self.patterns.join("|")
+            )))
+        };
+        let is_state = |state: &StateSetID| state.as_usize() < self.states.len();
+        if self.states.is_empty() {
+            return invalid("the start state is missing");
         }
-        for (from, cc, to) in transitions {
-            states[from].transitions.push((cc, to));
+        if self.patterns.len() != self.terminal_ids.len() {
+            return invalid("the patterns don't match the terminals");
         }
-
-        let current_states = Vec::with_capacity(states.len());
-        let next_states = Vec::with_capacity(states.len());
-        let mut end_states = vec![(false, TerminalID::new(0)); states.len()];
-        for (state, term) in accepting_states {
-            end_states[state] = (true, TerminalID::new(term as TerminalIDBase));
+        if !self.alphabet.is_consistent() {
+            return invalid("the alphabet is inconsistent");
         }
-
-        Minimizer::minimize(Self {
-            patterns: vec![nfa.pattern.pattern().to_string()],
-            terminal_ids: vec![(nfa.pattern.terminal_id() as TerminalIDBase).into()],
-            states,
-            end_states,
-            lookaheads: FxHashMap::default(),
-            current_states,
-            next_states,
-        })
-    }
-}
-
-impl From<MultiPatternNfa> for CompiledDfa {
-    /// Note that the lookahead is not set in the resulting CompiledDfa. This must be done
-    /// separately because a character class registry is needed to create the lookaheads.
-    /// See [CompiledDfa::try_from_patterns].
-    fn from(mp_nfa: MultiPatternNfa) -> Self {
-        let mut state_map: FxHashMap<BTreeSet<StateID>, StateSetID> = FxHashMap::default();
-        let mut transitions: FxHashSet<(StateSetID, CharClassID, StateSetID)> =
-            FxHashSet::default();
-        let mut accepting_states: Vec<(StateSetID, usize)> = Vec::new();
-        let mut queue: VecDeque<StateSetID> = VecDeque::new();
-
-        // Calculate the epsilon closures of the start state of the multi-pattern NFA.
-        let epsilon_closure: BTreeSet<StateID> =
-            BTreeSet::from_iter(mp_nfa.epsilon_closure(0.into()));
-        state_map.insert(epsilon_closure.clone(), 0.into());
-        queue.push_back(StateSetID::new(0));
-
-        while let Some(current_state) = queue.pop_front() {
-            let epsilon_closure = state_map
+        if self.transitions.len() != self.states.len() * self.alphabet.len()
+            || !self
+                .transitions
                 .iter()
-                .find(|(_, v)| **v == current_state)
-                .unwrap()
-                .0
-                .clone();
-            let target_states = mp_nfa.get_match_transitions(epsilon_closure.iter().cloned());
-            let old_state_id = current_state;
-            for (cc, target_state) in target_states {
-                let epsilon_closure = BTreeSet::from_iter(mp_nfa.epsilon_closure(target_state));
-                let new_state_id_candidate = state_map.len() as StateIDBase;
-                let new_state_id = *state_map.entry(epsilon_closure.clone()).or_insert_with(|| {
-                    let new_state_id = StateSetID::new(new_state_id_candidate);
-                    queue.push_back(new_state_id);
-                    new_state_id
-                });
-                let target_nfa = mp_nfa.find_nfa(target_state).expect("NFA not found");
-                if epsilon_closure
-                    .iter()
-                    .any(|s| mp_nfa.is_accepting_state(*s))
-                    && !accepting_states.contains(&(new_state_id, target_nfa.terminal_id()))
-                {
-                    accepting_states.push((new_state_id, target_nfa.terminal_id()));
-                }
-                transitions.insert((old_state_id, cc, new_state_id));
-            }
-        }
-        // The transitions of the CompiledDfa.
-        let mut states: Vec<StateData> = Vec::with_capacity(transitions.len());
-        for _ in 0..state_map.len() {
-            states.push(StateData::new());
-        }
-        for (from, cc, to) in transitions {
-            states[from].transitions.push((cc, to));
+                .all(|target| *target == DEAD_STATE || is_state(target))
+        {
+            return invalid("the transition table is inconsistent");
         }
-
-        let current_states = Vec::with_capacity(states.len());
-        let next_states = Vec::with_capacity(states.len());
-        let mut end_states = vec![(false, TerminalID::new(0)); states.len()];
-        for (state, term) in accepting_states {
-            end_states[state] = (true, TerminalID::new(term as TerminalIDBase));
+        for state in &self.states {
+            if !(state.looks.is_empty() || state.looks.len() == LOOK_CONTEXTS * LOOK_CONTEXTS)
+                || !state.looks.iter().all(is_state)
+            {
+                return invalid("the look transitions are inconsistent");
+            }
+            if !state.accepting.iter().all(|(terminal_id, drop_target)| {
+                self.terminal_ids.contains(terminal_id) && drop_target.iter().all(is_state)
+            }) {
+                return invalid("the accepting terminals are inconsistent");
+            }
         }
+        self.lookaheads
+            .values()
+            .try_for_each(|lookahead| lookahead.nfa.validate())?;
+        self.lookbehinds
+            .values()
+            .try_for_each(|lookbehind| lookbehind.nfa.validate())
+    }
 
-        Minimizer::minimize(Self {
-            patterns: vec![mp_nfa.patterns.iter().map(|p| p.pattern()).collect()],
-            terminal_ids: mp_nfa
-                .patterns
-                .iter()
-                .map(|p| (p.terminal_id() as TerminalIDBase).into())
-                .collect(),
-            states,
-            end_states,
-            lookaheads: FxHashMap::default(),
-            current_states,
-            next_states,
-        })
+    #[inline(always)]
+    fn priority_of(&self, terminal_id: TerminalID) -> usize {
+        self.terminal_ids
+            .iter()
+            .position(|&id| id == terminal_id)
+            .unwrap()
     }
 }
 
 impl std::fmt::Display for CompiledDfa {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Pattern: {}", self.patterns.join("|"))?;
+        writeln!(f, "Alphabet:")?;
+        write!(f, "{}", self.alphabet)?;
         writeln!(f, "Start state: 0")?;
-        writeln!(f, "End states: {:?}", self.end_states)?;
         for (i, state) in self.states.iter().enumerate() {
             writeln!(f, "State {}: {}", i, state)?;
+            for (symbol, target) in self.transitions_of(StateSetID::new(i as StateIDBase)) {
+                writeln!(f, "Transition: S{} -> {}", symbol, target)?;
+            }
         }
         writeln!(f, "Lookaheads:")?;
         for (terminal_id, lookahead) in &self.lookaheads {
             writeln!(f, "Lookahead: {} -> {}", terminal_id, lookahead)?;
         }
+        writeln!(f, "Lookbehinds:")?;
+        for (terminal_id, lookbehind) in &self.lookbehinds {
+            writeln!(f, "Lookbehind: {} -> {}", terminal_id, lookbehind)?;
+        }
         Ok(())
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub(crate) struct StateData {
-    /// A list of transitions from this state.
-    /// The state ids are numbers of sets of states.
-    pub(crate) transitions: Vec<(CharClassID, StateSetID)>,
-}
-
-impl StateData {
-    pub(crate) fn new() -> Self {
-        Self {
-            // Most states have only one or two transitions.
-            // Only the start state has many transitions.
-            transitions: Vec::with_capacity(2),
-        }
-    }
+    /// The terminals that match when this state is reached, in priority order.
+    /// A terminal of a non-greedy pattern with a lookahead is accompanied by the state to continue
+    /// with if the match is taken. The states of the finished pattern are removed there.
+    pub(crate) accepting: Vec<(TerminalID, Option<StateSetID>)>,
+    /// The states that are reached via zero-width assertions. The target for the characters `prev`
+    /// and `next` around the current position is found at the index
+    /// `look_context(prev) * LOOK_CONTEXTS + look_context(next)`.
+    /// The vector is empty if the state has no zero-width assertions.
+    pub(crate) looks: Vec<StateSetID>,
 }
 
 impl std::fmt::Display for StateData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (cc, next) in &self.transitions {
-            writeln!(f, "Transition: {:?} -> {}", cc, next)?;
+        for (terminal_id, drop_target) in &self.accepting {
+            match drop_target {
+                Some(target) => writeln!(f, "Accept: {} then {}", terminal_id, target)?,
+                None => writeln!(f, "Accept: {}", terminal_id)?,
+            }
+        }
+        if !self.looks.is_empty() {
+            writeln!(
+                f,
+                "Looks: {:?}",
+                self.looks.iter().map(|s| s.id()).collect::<Vec<_>>()
+            )?;
         }
         Ok(())
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "compiler"))]
 mod tests {
+    use crate::{internal::Haystack, CompileLimits};
+
     #[cfg(feature = "dot_writer")]
     static INIT: std::sync::Once = std::sync::Once::new();
 
@@ -468,14 +641,14 @@ mod tests {
     /// A macro that simplifies the rendering of a dot file for a CompiledDfa.
     #[cfg(feature = "dot_writer")]
     macro_rules! compiled_dfa_render_to {
-        ($compiled_dfa:expr, $label:expr, $character_class_registry:expr) => {
+        ($compiled_dfa:expr, $label:expr) => {
             let mut f =
                 std::fs::File::create(format!("{}/{}CompiledDfa.dot", TARGET_FOLDER, $label))
                     .unwrap();
             $crate::internal::dot::compiled_dfa_render(
                 $compiled_dfa,
                 $label,
-                $character_class_registry,
+                &$crate::internal::TokenNames::default(),
                 &mut f,
             );
         };
@@ -485,7 +658,7 @@ mod tests {
     struct TestData {
         pattern: &'static str,
         name: &'static str,
-        end_states: Vec<(bool, crate::internal::TerminalID)>,
+        state_count: usize,
         match_data: Vec<(&'static str, Option<(usize, usize)>)>,
     }
 
@@ -495,13 +668,7 @@ mod tests {
             TestData {
                 pattern: "(A*B|AC)D",
                 name: "Sedgewick",
-                end_states: vec![
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (true, 0.into()),
-                ],
+                state_count: 5,
                 match_data: vec![
                     ("AAABD", Some((0, 5))),
                     ("ACD", Some((0, 3))),
@@ -512,16 +679,7 @@ mod tests {
             TestData {
                 pattern: r#"\u{0022}(\\[\u{0022}\\/bfnrt]|u[0-9a-fA-F]{4}|[^\u{0022}\\\u0000-\u001F])*\u{0022}"#,
                 name: "JsonString",
-                end_states: vec![
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (false, 0.into()),
-                    (true, 0.into()),
-                ],
+                state_count: 4,
                 match_data: vec![
                     (r#""autumn""#, Some((0, 8))),
                     (r#""au0075tumn""#, Some((0, 12))),
@@ -531,7 +689,7 @@ mod tests {
             TestData {
                 pattern: r"[a-zA-Z_]\w*",
                 name: "Identifier",
-                end_states: vec![(false, 0.into()), (true, 0.into())],
+                state_count: 2,
                 match_data: vec![
                     ("_a", Some((0, 2))),
                     ("a", Some((0, 1))),
@@ -544,7 +702,7 @@ mod tests {
             TestData {
                 pattern: r"(0|1)*1(0|1)",
                 name: "SecondLastBitIs1",
-                end_states: vec![(false, 0.into()), (false, 0.into()), (true, 0.into())],
+                state_count: 4,
                 match_data: vec![
                     ("11010", Some((0, 5))),
                     ("11011", Some((0, 5))),
@@ -561,7 +719,7 @@ mod tests {
             TestData {
                 pattern: r"a*(a|b)b*",
                 name: "MinimalMatch",
-                end_states: vec![(false, 0.into()), (true, 0.into())],
+                state_count: 3,
                 match_data: vec![
                     ("a", Some((0, 1))),
                     ("b", Some((0, 1))),
@@ -588,20 +746,25 @@ mod tests {
             let nfa: crate::internal::Nfa =
                 crate::internal::Nfa::try_from_ast(ast, &mut character_class_registry).unwrap();
             nfa_render_to!(&nfa, test.name);
-            let mut compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::from(nfa);
+            let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_nfa(
+                &nfa,
+                &character_class_registry,
+                &CompileLimits::default(),
+            )
+            .unwrap();
             assert_eq!(
-                compiled_dfa.end_states, test.end_states,
-                "Test '{}', End states",
+                compiled_dfa.states.len(),
+                test.state_count,
+                "Test '{}', State count",
                 test.name
             );
-            compiled_dfa_render_to!(&compiled_dfa, test.name, &character_class_registry);
+            compiled_dfa_render_to!(&compiled_dfa, test.name);
             eprintln!("{}", compiled_dfa);
 
             for (id, (input, expected)) in test.match_data.iter().enumerate() {
-                let char_indices = input.char_indices();
+                let haystack = Haystack::Str(input);
                 trace!("Matching string: {}", input);
-                let match_char_class = character_class_registry.create_match_char_class().unwrap();
-                let matched = compiled_dfa.find_from(input, char_indices, &match_char_class);
+                let matched = compiled_dfa.find_from(haystack, haystack.char_indices(0));
                 assert_eq!(
                     matched,
                     expected.map(|(start, end)| crate::Match::new(0, crate::Span::new(start, end))),
@@ -631,19 +794,16 @@ mod tests {
             let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
                 &scanner_mode.patterns,
                 &mut character_class_registry,
+                &CompileLimits::default(),
             )
             .unwrap();
             if scanner_mode.name == "INITIAL" {
-                assert_eq!(compiled_dfa.patterns.len(), 1);
+                assert_eq!(compiled_dfa.patterns.len(), scanner_mode.patterns.len());
                 assert_eq!(compiled_dfa.lookaheads.len(), 1);
                 println!("{}", compiled_dfa);
                 assert!(compiled_dfa.lookaheads.contains_key(&20.into()));
             }
-            compiled_dfa_render_to!(
-                &compiled_dfa,
-                &format!("Veryl_{}_", scanner_mode.name),
-                &character_class_registry
-            );
+            compiled_dfa_render_to!(&compiled_dfa, &format!("Veryl_{}_", scanner_mode.name));
         }
     }
 
@@ -659,12 +819,13 @@ mod tests {
         let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
             &scanner_modes[0].patterns,
             &mut character_class_registry,
+            &CompileLimits::default(),
         )
         .unwrap();
-        assert_eq!(compiled_dfa.patterns.len(), 1);
+        assert_eq!(compiled_dfa.patterns.len(), scanner_modes[0].patterns.len());
         assert_eq!(compiled_dfa.lookaheads.len(), 0);
         println!("{}", compiled_dfa);
-        compiled_dfa_render_to!(&compiled_dfa, "Parol", &character_class_registry);
+        compiled_dfa_render_to!(&compiled_dfa, "Parol");
     }
 
     #[cfg(feature = "serde")]
@@ -683,6 +844,7 @@ mod tests {
         let _compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
             &scanner_modes[0].patterns,
             &mut character_class_registry,
+            &CompileLimits::default(),
         )
         .unwrap();
         // Write the result of Display of the character class registry to a file for inspection.
@@ -692,6 +854,42 @@ mod tests {
         writeln!(f, "{}", character_class_registry).unwrap();
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_match_with_lookbehind() {
+        let json = r#"
+            [
+            {
+                "name": "INITIAL",
+                "patterns": [
+                {
+                    "pattern": "b",
+                    "token_type": 5,
+                    "lookbehind": { "is_positive": true, "pattern": "a+|\\Ac" }
+                },
+                { "pattern": "b", "token_type": 6 }
+                ],
+                "transitions": []
+            }
+            ]"#;
+        let scanner_modes: Vec<crate::ScannerMode> = serde_json::from_str(json).unwrap();
+        let mut character_class_registry = crate::internal::CharacterClassRegistry::new();
+        let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
+            &scanner_modes[0].patterns,
+            &mut character_class_registry,
+            &CompileLimits::default(),
+        )
+        .expect("Failed to create compiled DFA from patterns");
+        for (input, token_type) in [("aab", 5), ("xb", 6), ("cb", 5), ("xcb", 6)] {
+            // Start the search at the 'b'
+            let char_indices = Haystack::Str(input).char_indices(input.len() - 1);
+            let matched = compiled_dfa
+                .find_from(Haystack::Str(input), char_indices)
+                .expect("Failed to match input");
+            assert_eq!(matched.token_type(), token_type, "Input '{}'", input);
+        }
+    }
+
     /// This test failed until issue [#6](https://github.com/jsinger67/scnr/issues/6) was fixed.
     #[cfg(feature = "serde")]
     #[test]
@@ -713,19 +911,119 @@ mod tests {
             ]"#;
         let scanner_modes: Vec<crate::ScannerMode> = serde_json::from_str(json).unwrap();
         let mut character_class_registry = crate::internal::CharacterClassRegistry::new();
-        let mut compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
+        let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
             &scanner_modes[0].patterns,
             &mut character_class_registry,
+            &CompileLimits::default(),
         )
         .expect("Failed to create compiled DFA from patterns");
-        let match_char_class = character_class_registry
-            .create_match_char_class()
-            .expect("Failed to create match char class function");
-        let input = "World!";
-        let char_indices = input.char_indices();
+        let input = Haystack::Str("World!");
+        let char_indices = input.char_indices(0);
         let matched = compiled_dfa
-            .find_from(input, char_indices, &match_char_class)
+            .find_from(input, char_indices)
             .expect("Failed to match input");
         assert_eq!(matched.token_type(), 7);
     }
+
+    /// The lookahead is evaluated at each candidate end of the match, and the DFA gives the same
+    /// results when it is searched again.
+    #[test]
+    fn test_lookahead_at_candidate_ends() {
+        let patterns = [
+            crate::Pattern::new(r"[a-z]+".to_string(), 0)
+                .with_lookahead(crate::Lookahead::new(true, r"\d".to_string())),
+            crate::Pattern::new(r"[a-z]".to_string(), 1),
+        ];
+        let mut character_class_registry = crate::internal::CharacterClassRegistry::new();
+        let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
+            &patterns,
+            &mut character_class_registry,
+            &CompileLimits::default(),
+        )
+        .expect("Failed to create compiled DFA from patterns");
+        for _ in 0..2 {
+            for (input, expected) in [
+                ("abc1", Some((0, 0, 3))),
+                ("abc", Some((1, 0, 1))),
+                ("a1bc", Some((0, 0, 1))),
+                ("1", None),
+            ] {
+                let haystack = Haystack::Str(input);
+                let matched = compiled_dfa.find_from(haystack, haystack.char_indices(0));
+                assert_eq!(
+                    matched,
+                    expected.map(|(token_type, start, end)| crate::Match::new(
+                        token_type,
+                        crate::Span::new(start, end)
+                    )),
+                    "Input '{}'",
+                    input
+                );
+            }
+        }
+    }
+
+    /// A non-greedy pattern with a lookahead stops at the shortest match whose lookahead is
+    /// satisfied. Until then its states must not be dropped.
+    #[test]
+    fn test_non_greedy_with_lookahead() {
+        let patterns = [
+            crate::Pattern::new(r"a+?".to_string(), 0)
+                .with_lookahead(crate::Lookahead::new(true, "b".to_string())),
+            crate::Pattern::new(r"[a-z]+?".to_string(), 1),
+        ];
+        let mut character_class_registry = crate::internal::CharacterClassRegistry::new();
+        let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
+            &patterns,
+            &mut character_class_registry,
+            &CompileLimits::default(),
+        )
+        .expect("Failed to create compiled DFA from patterns");
+        for (input, expected) in [("aaab", (0, 0, 3)), ("aaac", (1, 0, 1)), ("ab", (0, 0, 1))] {
+            let haystack = Haystack::Str(input);
+            let matched = compiled_dfa
+                .find_from(haystack, haystack.char_indices(0))
+                .expect("Failed to match input");
+            assert_eq!(
+                matched,
+                crate::Match::new(expected.0, crate::Span::new(expected.1, expected.2)),
+                "Input '{}'",
+                input
+            );
+        }
+    }
+
+    /// A non-greedy repetition only shortens itself. The other repetitions of the pattern keep
+    /// their own greediness, like in the regex crate.
+    #[test]
+    fn test_non_greedy_priorities() {
+        for (pattern, input, expected) in [
+            (r"a+b*?", "aaa", 3),
+            (r"(?:x+)+?", "xx", 2),
+            (r"(?:x+?)+", "xx", 2),
+            (r"a+?", "aaa", 1),
+            (r"a{1,3}?b", "aaab", 4),
+            (r"/\*.*?\*/", "/* a */ b */", 7),
+        ] {
+            let patterns = [crate::Pattern::new(pattern.to_string(), 0)];
+            let mut character_class_registry = crate::internal::CharacterClassRegistry::new();
+            let compiled_dfa = crate::internal::compiled_dfa::CompiledDfa::try_from_patterns(
+                &patterns,
+                &mut character_class_registry,
+                &CompileLimits::default(),
+            )
+            .expect("Failed to create compiled DFA from patterns");
+            let haystack = Haystack::Str(input);
+            let matched = compiled_dfa
+                .find_from(haystack, haystack.char_indices(0))
+                .expect("Failed to match input");
+            assert_eq!(
+                matched,
+                crate::Match::new(0, crate::Span::new(0, expected)),
+                "Pattern '{}', input '{}'",
+                pattern,
+                input
+            );
+        }
+    }
 }
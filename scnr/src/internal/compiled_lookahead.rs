@@ -1,11 +1,16 @@
 //! Module with the compiled lookahead type and functions.
 
-use crate::{Lookahead, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use super::{
-    compiled_dfa::CompiledDfa, parse_regex_syntax, CharClassID, CharacterClassRegistry, Nfa,
-};
+#[cfg(feature = "compiler")]
+use crate::{CompileLimits, Lookahead, Result};
 
+use super::{compiled_dfa::CompiledDfa, CharIndices, Haystack};
+#[cfg(feature = "compiler")]
+use super::{parse_regex_syntax, CharacterClassRegistry, Nfa};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub(crate) struct CompiledLookahead {
     /// The compiled DFA for the lookahead.
@@ -17,17 +22,23 @@ pub(crate) struct CompiledLookahead {
 
 impl CompiledLookahead {
     /// Create a new compiled lookahead from a lookahead.
+    #[cfg(feature = "compiler")]
     pub(crate) fn try_from_lookahead(
         lookahead: &Lookahead,
         character_class_registry: &mut CharacterClassRegistry,
+        limits: &CompileLimits,
     ) -> Result<Self> {
         let Lookahead {
             is_positive,
             pattern,
         } = lookahead;
         let ast = parse_regex_syntax(pattern)?;
-        let nfa: Nfa = Nfa::try_from_ast(ast, character_class_registry)?;
-        let nfa = Box::new(nfa.into());
+        let nfa: Nfa = Nfa::try_from_ast_with_limits(ast, character_class_registry, limits)?;
+        let nfa = Box::new(CompiledDfa::try_from_nfa(
+            &nfa,
+            character_class_registry,
+            limits,
+        )?);
         Ok(Self {
             nfa,
             is_positive: *is_positive,
@@ -36,23 +47,23 @@ impl CompiledLookahead {
 
     /// Check if the lookahead constraints are met.
     ///
-    /// The function returns a tuple of (bool, usize) where the bool indicates if the lookahead
-    /// is satisfied and the usize indicates the number of characters consumed.
+    /// The function returns a tuple of (bool, usize, usize) where the bool indicates if the
+    /// lookahead is satisfied and the first usize indicates the number of characters consumed. The
+    /// last usize is the end of the input that the lookahead has read, see
+    /// `CompiledDfa::find_from_partial`.
     ///
     /// The boolean value in the returned tuple is calculated based on the value of `is_positive`.
     /// If the lookahead is positive, the value is true if the input matches the lookahead.
     /// Otherwise if the lookahead is negative, the value is true if the input does not match the
     /// lookahead.
     pub(crate) fn satisfies_lookahead(
-        &mut self,
-        input: &str,
-        char_indices: std::str::CharIndices,
-        match_char_class: &(dyn Fn(CharClassID, char) -> bool + 'static),
-    ) -> (bool, usize) {
-        if let Some(ma) = self.nfa.find_from(input, char_indices, match_char_class) {
-            (self.is_positive, ma.len())
-        } else {
-            (!self.is_positive, 0)
+        &self,
+        input: Haystack,
+        char_indices: CharIndices,
+    ) -> (bool, usize, usize) {
+        match self.nfa.find_from_partial(input, char_indices) {
+            (Some(ma), _, read_end) => (self.is_positive, ma.len(), read_end),
+            (None, _, read_end) => (!self.is_positive, 0, read_end),
         }
     }
 }
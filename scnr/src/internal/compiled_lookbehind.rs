@@ -0,0 +1,79 @@
+//! Module with the compiled lookbehind type and functions.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "compiler")]
+use crate::{CompileLimits, Lookbehind, Result};
+
+use super::{compiled_dfa::CompiledDfa, Haystack};
+#[cfg(feature = "compiler")]
+use super::{parse_regex_syntax, CharacterClassRegistry, Nfa};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledLookbehind {
+    /// The compiled DFA of the reversed lookbehind pattern.
+    /// It is simulated from the start of a match backwards.
+    /// We need a box to break the cycle between CompiledDfa and CompiledLookbehind.
+    pub(crate) nfa: Box<CompiledDfa>,
+    /// If the lookbehind is positive or negative.
+    pub(crate) is_positive: bool,
+}
+
+impl CompiledLookbehind {
+    /// Create a new compiled lookbehind from a lookbehind.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn try_from_lookbehind(
+        lookbehind: &Lookbehind,
+        character_class_registry: &mut CharacterClassRegistry,
+        limits: &CompileLimits,
+    ) -> Result<Self> {
+        let Lookbehind {
+            is_positive,
+            pattern,
+        } = lookbehind;
+        let ast = parse_regex_syntax(pattern)?;
+        let nfa: Nfa = Nfa::try_from_ast_with_limits(ast, character_class_registry, limits)?;
+        let nfa = Box::new(CompiledDfa::try_from_nfa(
+            &nfa.reverse(),
+            character_class_registry,
+            limits,
+        )?);
+        Ok(Self {
+            nfa,
+            is_positive: *is_positive,
+        })
+    }
+
+    /// Check if the lookbehind constraints are met.
+    ///
+    /// The `start` is the start position of the match in the `haystack`. The lookbehind pattern is
+    /// matched if it matches a suffix of the text before it.
+    ///
+    /// If the lookbehind is positive, the result is true if the haystack matches the lookbehind.
+    /// Otherwise if the lookbehind is negative, the result is true if the haystack does not match
+    /// the lookbehind.
+    ///
+    /// The second value of the result is the start of the input that the lookbehind has read, see
+    /// `CompiledDfa::matches_suffix`.
+    pub(crate) fn satisfies_lookbehind(&self, haystack: Haystack, start: usize) -> (bool, usize) {
+        let (matched, read_start) = self.nfa.matches_suffix(haystack, start);
+        (matched == self.is_positive, read_start)
+    }
+}
+
+impl std::fmt::Display for CompiledLookbehind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} lookbehind: {}",
+            if self.is_positive {
+                "Positive"
+            } else {
+                "Negative"
+            },
+            self.nfa
+        )
+    }
+}
@@ -1,8 +1,18 @@
-use crate::{Result, ScannerMode};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use super::{compiled_dfa::CompiledDfa, CharacterClassRegistry, ScannerModeID, TerminalID};
+use crate::ModeTransition;
+#[cfg(any(feature = "compiler", feature = "serde"))]
+use crate::Result;
+#[cfg(feature = "compiler")]
+use crate::{CompileLimits, ScannerMode};
+
+#[cfg(feature = "compiler")]
+use super::CharacterClassRegistry;
+use super::{compiled_dfa::CompiledDfa, TerminalID, TerminalIDBase};
 
 /// A compiled scanner mode that can be used to scan a string.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone)]
 pub(crate) struct CompiledScannerMode {
     /// The name of the scanner mode.
@@ -12,42 +22,92 @@ pub(crate) struct CompiledScannerMode {
     /// The priorities of the patterns are determined by their order in the vector. Lower indices
     /// have higher priority if multiple patterns match the input and have the same length.
     pub(crate) dfa: CompiledDfa,
-    pub(crate) transitions: Vec<(TerminalID, ScannerModeID)>,
+    pub(crate) transitions: Vec<(TerminalID, ModeTransition)>,
+    /// The token types of the patterns that are marked as skipped, sorted by token type number.
+    pub(crate) skipped: Vec<TerminalID>,
 }
 
 impl CompiledScannerMode {
     /// Create a new compiled scanner mode.
+    #[cfg(feature = "compiler")]
     pub(crate) fn try_from_scanner_mode(
         scanner_mode: ScannerMode,
         character_class_registry: &mut CharacterClassRegistry,
+        limits: &CompileLimits,
     ) -> Result<Self> {
         let ScannerMode {
             name,
             patterns,
             transitions,
         } = scanner_mode;
-        let dfa = CompiledDfa::try_from_patterns(&patterns, character_class_registry)?;
+        let dfa = CompiledDfa::try_from_patterns(&patterns, character_class_registry, limits)
+            .map_err(|e| e.with_mode(&name))?;
+        let mut skipped = patterns
+            .iter()
+            .filter(|pattern| pattern.is_skip())
+            .map(|pattern| TerminalID::new(pattern.terminal_id() as TerminalIDBase))
+            .collect::<Vec<_>>();
+        skipped.sort();
+        skipped.dedup();
         Ok(Self {
             name,
             dfa,
             transitions,
+            skipped,
         })
     }
 
     /// Check if the scanner configuration has a transition on the given terminal index
-    pub(crate) fn has_transition(&self, token_type: usize) -> Option<usize> {
-        for (tok_type, scanner) in &self.transitions {
+    pub(crate) fn has_transition(&self, token_type: usize) -> Option<ModeTransition> {
+        for (tok_type, transition) in &self.transitions {
             match token_type.cmp(&tok_type.as_usize()) {
                 std::cmp::Ordering::Less => return None,
-                std::cmp::Ordering::Equal => return Some(scanner.as_usize()),
+                std::cmp::Ordering::Equal => return Some(*transition),
                 std::cmp::Ordering::Greater => continue,
             }
         }
         None
     }
+
+    /// Check if the token type is marked as skipped in this scanner mode.
+    pub(crate) fn is_skipped(&self, token_type: usize) -> bool {
+        self.skipped
+            .binary_search(&TerminalID::new(token_type as TerminalIDBase))
+            .is_ok()
+    }
+
+    /// Checks the consistency of a deserialized scanner mode. The targets of the transitions must
+    /// be less than `mode_count`, the number of scanner modes of the scanner.
+    #[cfg(feature = "serde")]
+    pub(crate) fn validate(&self, mode_count: usize) -> Result<()> {
+        let invalid = |reason: &str| {
+            Err(super::serialization::invalid_scanner(format!(
+                "{} in scanner mode '{}'",
+                reason, self.name
+            )))
+        };
+        if !self.transitions.windows(2).all(|w| w[0].0 < w[1].0) {
+            return invalid("the transitions are not sorted by token type");
+        }
+        if !self
+            .transitions
+            .iter()
+            .all(|(_, transition)| match transition {
+                ModeTransition::Set(mode) | ModeTransition::Push(mode) => *mode < mode_count,
+                ModeTransition::Pop => true,
+            })
+        {
+            return invalid("a transition leads to a missing scanner mode");
+        }
+        if !self.skipped.windows(2).all(|w| w[0] < w[1]) {
+            return invalid("the skipped token types are not sorted");
+        }
+        self.dfa.validate()
+    }
 }
 
 #[cfg(test)]
+#[cfg(feature = "compiler")]
 mod tests {
     use std::{fs, sync::Once};
 
@@ -74,12 +134,17 @@ mod tests {
     /// A macro that simplifies the rendering of a dot file for a DFA.
     #[cfg(feature = "dot_writer")]
     macro_rules! compiled_dfa_render_to {
-        ($nfa:expr, $label:expr, $reg:ident) => {
+        ($nfa:expr, $label:expr) => {
             let label = format!("{}Dfa", $label);
             let mut f =
                 std::fs::File::create(format!("{}/{}CompiledDfa.dot", TARGET_FOLDER, $label))
                     .unwrap();
-            $crate::internal::dot::compiled_dfa_render($nfa, &label, &$reg, &mut f);
+            $crate::internal::dot::compiled_dfa_render(
+                $nfa,
+                &label,
+                &$crate::internal::TokenNames::default(),
+                &mut f,
+            );
         };
     }
 
@@ -91,7 +156,7 @@ mod tests {
         let pattern = Pattern::new("(//.*(\r\n|\r|\n))".to_string(), 0);
         let compiled_dfa =
             CompiledDfa::try_from_pattern(&pattern, &mut character_class_registry).unwrap();
-        compiled_dfa_render_to!(&compiled_dfa, "LineComment_", character_class_registry);
+        compiled_dfa_render_to!(&compiled_dfa, "LineComment_");
         // assert_eq!(compiled_dfa.accepting_states.len(), 1);
     }
 
@@ -102,11 +167,14 @@ mod tests {
         let scanner_mode = ScannerMode {
             name: "test".to_string(),
             patterns: vec![Pattern::new("a".to_string(), 0)],
-            transitions: vec![(0.into(), 1.into())],
+            transitions: vec![(0.into(), ModeTransition::Set(1))],
         };
-        let compiled_scanner_mode =
-            CompiledScannerMode::try_from_scanner_mode(scanner_mode, &mut character_class_registry)
-                .unwrap();
+        let compiled_scanner_mode = CompiledScannerMode::try_from_scanner_mode(
+            scanner_mode,
+            &mut character_class_registry,
+            &CompileLimits::default(),
+        )
+        .unwrap();
         assert_eq!(compiled_scanner_mode.name, "test");
         assert_eq!(compiled_scanner_mode.transitions.len(), 1);
     }
@@ -118,10 +186,13 @@ mod tests {
         let scanner_mode = ScannerMode {
             name: "test".to_string(),
             patterns: vec![Pattern::new("[".to_string(), 0)],
-            transitions: vec![(0.into(), 1.into())],
+            transitions: vec![(0.into(), ModeTransition::Set(1))],
         };
-        let compiled_scanner_mode =
-            CompiledScannerMode::try_from_scanner_mode(scanner_mode, &mut character_class_registry);
+        let compiled_scanner_mode = CompiledScannerMode::try_from_scanner_mode(
+            scanner_mode,
+            &mut character_class_registry,
+            &CompileLimits::default(),
+        );
         assert!(compiled_scanner_mode.is_err());
     }
 
@@ -132,14 +203,51 @@ mod tests {
         let scanner_mode = ScannerMode {
             name: "test".to_string(),
             patterns: vec![Pattern::new("a".to_string(), 0)],
-            transitions: vec![(0.into(), 1.into()), (1.into(), 2.into())],
+            transitions: vec![
+                (0.into(), ModeTransition::Set(1)),
+                (1.into(), ModeTransition::Push(2)),
+            ],
         };
-        let compiled_scanner_mode =
-            CompiledScannerMode::try_from_scanner_mode(scanner_mode, &mut character_class_registry)
-                .unwrap();
-        assert_eq!(compiled_scanner_mode.has_transition(0), Some(1));
-        assert_eq!(compiled_scanner_mode.has_transition(1), Some(2));
+        let compiled_scanner_mode = CompiledScannerMode::try_from_scanner_mode(
+            scanner_mode,
+            &mut character_class_registry,
+            &CompileLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            compiled_scanner_mode.has_transition(0),
+            Some(ModeTransition::Set(1))
+        );
+        assert_eq!(
+            compiled_scanner_mode.has_transition(1),
+            Some(ModeTransition::Push(2))
+        );
         assert_eq!(compiled_scanner_mode.has_transition(2), None);
         assert_eq!(compiled_scanner_mode.has_transition(3), None);
     }
+
+    #[test]
+    fn test_compiled_dfa_scanner_mode_skipped() {
+        init();
+        let mut character_class_registry = CharacterClassRegistry::new();
+        let scanner_mode = ScannerMode::new(
+            "test",
+            vec![
+                Pattern::new(r"\s+".to_string(), 2).with_skip(true),
+                Pattern::new("a".to_string(), 0),
+                Pattern::new("//.*".to_string(), 1).with_skip(true),
+            ],
+            vec![],
+        );
+        let compiled_scanner_mode = CompiledScannerMode::try_from_scanner_mode(
+            scanner_mode,
+            &mut character_class_registry,
+            &CompileLimits::default(),
+        )
+        .unwrap();
+        assert!(!compiled_scanner_mode.is_skipped(0));
+        assert!(compiled_scanner_mode.is_skipped(1));
+        assert!(compiled_scanner_mode.is_skipped(2));
+        assert!(!compiled_scanner_mode.is_skipped(3));
+    }
 }
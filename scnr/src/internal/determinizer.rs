@@ -0,0 +1,435 @@
+//! Module with the subset construction that turns the NFAs into a [CompiledDfa].
+//!
+//! The DFA states are sets of NFA states. Its transitions are taken on the symbols of an
+//! [Alphabet], i.e. on disjoint sets of characters, so each state has at most one target state per
+//! character.
+//!
+//! Zero-width assertions can't be resolved by reading a character because they depend on the
+//! characters around a position. Therefore a state that contains look transitions gets a table of
+//! target states, one for each combination of the classes of the previous and the next character,
+//! see [look_context]. The target is the closure of the state over the assertions that hold.
+//!
+//! Patterns with non-greedy repetitions follow the priorities of their paths through the NFA, like
+//! backtracking regex engines do. Their NFA states are kept in priority order in a DFA state, and
+//! the states behind an accepting state of such a pattern are removed, because a match on these
+//! paths would lose against the match that has been found. If the pattern has a lookahead the
+//! removal depends on the result of the lookahead, which is only known during the search. In this
+//! case the accepting state names the state to continue with if the match is taken.
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{CompileLimits, Pattern, Result};
+
+use super::{
+    compiled_dfa::{CompiledDfa, StateData, DEAD_STATE},
+    ids::StateSetID,
+    look_context, Alphabet, CharClassID, CharacterClassRegistry, Look, MultiPatternNfa, Nfa,
+    StateID, StateIDBase, TerminalID, TerminalIDBase, LOOK_CONTEXTS, LOOK_CONTEXT_CHARS,
+};
+
+/// The data of a pattern that are relevant for the construction of the DFA.
+#[derive(Debug)]
+struct PatternData {
+    /// The pattern. It is used in error messages.
+    pattern: String,
+    /// The terminal id of the pattern.
+    terminal_id: TerminalID,
+    /// If the pattern contains a non-greedy repetition.
+    non_greedy: bool,
+    /// If the pattern has a lookahead. Its result is only known during the search.
+    has_lookahead: bool,
+    /// If the pattern has a lookbehind. Its result is only known during the search.
+    has_lookbehind: bool,
+}
+
+/// A state of the NFA in a flat representation, independent of the NFA it belongs to.
+#[derive(Debug, Default, Clone)]
+struct NfaStateData {
+    /// The targets of the epsilon transitions.
+    epsilons: Vec<StateID>,
+    /// The transitions that consume a character of the character class.
+    transitions: Vec<(CharClassID, StateID)>,
+    /// The transitions that are taken if the zero-width assertion holds.
+    looks: Vec<(Look, StateID)>,
+    /// The index of the pattern the state belongs to. The start state of a multi-pattern NFA
+    /// belongs to no pattern.
+    pattern: Option<usize>,
+    /// If the state is the end state of its pattern.
+    accepting: bool,
+}
+
+/// A DFA state during the construction: the set of NFA states and whether the state is reached
+/// without consuming any character. The NFA states are sorted by their patterns. Within a
+/// non-greedy pattern they are in priority order, otherwise they are sorted by their ids.
+type StateKey = (Vec<StateID>, bool);
+
+/// The subset construction of a [CompiledDfa].
+pub(crate) struct Determinizer<'a> {
+    /// The states of the NFA, indexed by their ids.
+    nfa_states: Vec<NfaStateData>,
+    /// The start state of the NFA.
+    start_state: StateID,
+    /// The patterns in priority order.
+    patterns: Vec<PatternData>,
+    /// The alphabet of the character classes of the NFA.
+    alphabet: Alphabet,
+    /// The limits of the compilation.
+    limits: &'a CompileLimits,
+    /// The DFA states created so far. The index is the id of the DFA state.
+    keys: Vec<StateKey>,
+    /// The ids of the DFA states.
+    state_map: FxHashMap<StateKey, StateSetID>,
+}
+
+impl<'a> Determinizer<'a> {
+    /// Creates the subset construction for a single NFA.
+    ///
+    /// The pattern of the NFA must carry the lookahead and the lookbehind if there are any,
+    /// because they influence the handling of the accepting states.
+    pub(crate) fn from_nfa(
+        nfa: &Nfa,
+        character_class_registry: &CharacterClassRegistry,
+        limits: &'a CompileLimits,
+    ) -> Self {
+        let mut determinizer = Self::new(
+            nfa.start_state,
+            Self::char_classes_of(std::iter::once(nfa)),
+            character_class_registry,
+            limits,
+        );
+        determinizer.add_nfa(nfa, 0, &nfa.pattern);
+        determinizer
+    }
+
+    /// Creates the subset construction for a multi-pattern NFA.
+    /// The start state of the multi-pattern NFA is always 0.
+    pub(crate) fn from_multi_pattern_nfa(
+        mp_nfa: &MultiPatternNfa,
+        character_class_registry: &CharacterClassRegistry,
+        limits: &'a CompileLimits,
+    ) -> Self {
+        let mut determinizer = Self::new(
+            StateID::new(0),
+            Self::char_classes_of(mp_nfa.nfas.iter()),
+            character_class_registry,
+            limits,
+        );
+        for (index, (nfa, pattern)) in mp_nfa.nfas.iter().zip(&mp_nfa.patterns).enumerate() {
+            determinizer.add_nfa(nfa, index, pattern);
+        }
+        let start_state = determinizer.nfa_state_mut(StateID::new(0));
+        start_state.epsilons = mp_nfa
+            .start_transitions
+            .iter()
+            .map(|t| t.target_state())
+            .collect();
+        determinizer
+    }
+
+    fn new(
+        start_state: StateID,
+        char_classes: Vec<CharClassID>,
+        character_class_registry: &CharacterClassRegistry,
+        limits: &'a CompileLimits,
+    ) -> Self {
+        Self {
+            nfa_states: Vec::new(),
+            start_state,
+            patterns: Vec::new(),
+            alphabet: character_class_registry.create_alphabet(char_classes),
+            limits,
+            keys: Vec::new(),
+            state_map: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the character classes that are used in the transitions of the NFAs.
+    fn char_classes_of<'n>(nfas: impl Iterator<Item = &'n Nfa>) -> Vec<CharClassID> {
+        nfas.flat_map(|nfa| nfa.states())
+            .flat_map(|state| state.transitions())
+            .map(|t| t.char_class())
+            .collect()
+    }
+
+    /// Adds the states of the NFA of the pattern with the given index.
+    fn add_nfa(&mut self, nfa: &Nfa, index: usize, pattern: &Pattern) {
+        self.patterns.push(PatternData {
+            pattern: pattern.pattern().to_string(),
+            terminal_id: TerminalID::new(pattern.terminal_id() as TerminalIDBase),
+            non_greedy: nfa.non_greedy,
+            has_lookahead: pattern.lookahead().is_some(),
+            has_lookbehind: pattern.lookbehind().is_some(),
+        });
+        for state in nfa.states() {
+            *self.nfa_state_mut(state.id()) = NfaStateData {
+                epsilons: state
+                    .epsilon_transitions()
+                    .iter()
+                    .map(|t| t.target_state())
+                    .collect(),
+                transitions: state
+                    .transitions()
+                    .iter()
+                    .map(|t| (t.char_class(), t.target_state()))
+                    .collect(),
+                looks: state
+                    .look_transitions()
+                    .iter()
+                    .map(|t| (t.look(), t.target_state()))
+                    .collect(),
+                pattern: Some(index),
+                accepting: state.id() == nfa.end_state(),
+            };
+        }
+    }
+
+    fn nfa_state_mut(&mut self, state: StateID) -> &mut NfaStateData {
+        if self.nfa_states.len() <= state.as_usize() {
+            self.nfa_states
+                .resize(state.as_usize() + 1, NfaStateData::default());
+        }
+        &mut self.nfa_states[state]
+    }
+
+    /// Runs the subset construction.
+    /// An error is returned if the number of DFA states exceeds the limit. In this case the
+    /// pattern that contributes the most NFA states to the new DFA state is named in the error.
+    ///
+    /// The lookaheads and the lookbehinds are not set in the resulting CompiledDfa. This must be
+    /// done separately.
+    pub(crate) fn determinize(mut self) -> Result<CompiledDfa> {
+        let start = self.closure([self.start_state], None);
+        self.add_state(start, true)?;
+
+        let symbol_count = self.alphabet.len();
+        let mut states = Vec::new();
+        let mut transitions = Vec::new();
+        let mut targets: Vec<Vec<StateID>> = vec![Vec::new(); symbol_count];
+        // The keys grow while we process them.
+        let mut index = 0;
+        while index < self.keys.len() {
+            let id = StateSetID::new(index as StateIDBase);
+            let (set, at_start) = self.keys[index].clone();
+            let (accepting, effective) = self.accepting(&set, at_start)?;
+            let looks = self.look_targets(id, &effective, at_start)?;
+
+            for symbol_targets in &mut targets {
+                symbol_targets.clear();
+            }
+            for state in &effective {
+                for (char_class, target) in &self.nfa_states[*state].transitions {
+                    for symbol in self.alphabet.symbols_of_class(*char_class) {
+                        targets[*symbol].push(*target);
+                    }
+                }
+            }
+            for symbol_targets in &targets {
+                if symbol_targets.is_empty() {
+                    transitions.push(DEAD_STATE);
+                } else {
+                    let closure = self.closure(symbol_targets.iter().copied(), None);
+                    transitions.push(self.add_state(closure, false)?);
+                }
+            }
+            states.push(StateData { accepting, looks });
+            index += 1;
+        }
+
+        Ok(CompiledDfa {
+            patterns: self.patterns.iter().map(|p| p.pattern.clone()).collect(),
+            terminal_ids: self.patterns.iter().map(|p| p.terminal_id).collect(),
+            alphabet: self.alphabet,
+            states,
+            transitions,
+            lookaheads: FxHashMap::default(),
+            lookbehinds: FxHashMap::default(),
+        })
+    }
+
+    /// Calculates the accepting terminals of the DFA state in priority order and the NFA states
+    /// that take part in the transitions of the state.
+    ///
+    /// A terminal with a lookahead is followed by the state that is left over when its non-greedy
+    /// pattern has matched, if any. Terminals that can't win against a terminal with higher
+    /// priority and without any lookaround are omitted.
+    ///
+    /// The states of non-greedy patterns without lookahead that match here and that have a lower
+    /// priority than the match are removed from the transitions, except in the start state,
+    /// because empty matches are not taken.
+    #[allow(clippy::type_complexity)]
+    fn accepting(
+        &mut self,
+        set: &[StateID],
+        at_start: bool,
+    ) -> Result<(Vec<(TerminalID, Option<StateSetID>)>, Vec<StateID>)> {
+        let mut accepting_patterns = set
+            .iter()
+            .filter(|s| self.nfa_states[**s].accepting)
+            .filter_map(|s| self.nfa_states[*s].pattern)
+            .collect::<Vec<_>>();
+        accepting_patterns.sort_unstable();
+        accepting_patterns.dedup();
+
+        let mut accepting = Vec::new();
+        let mut unconditional = false;
+        for pattern in accepting_patterns.iter().copied() {
+            let PatternData {
+                terminal_id,
+                non_greedy,
+                has_lookahead,
+                has_lookbehind,
+                ..
+            } = self.patterns[pattern];
+            if unconditional && !has_lookahead {
+                // A lookahead can make a match longer, all other matches of this length lose
+                // against the unconditional one.
+                continue;
+            }
+            let drop_target = if !at_start && non_greedy && has_lookahead {
+                let rest = self.truncated(set, |p| p == pattern);
+                Some(self.add_state(rest, false)?)
+            } else {
+                None
+            };
+            accepting.push((terminal_id, drop_target));
+            unconditional |= !has_lookahead && !has_lookbehind;
+        }
+
+        let effective = if at_start {
+            set.to_vec()
+        } else {
+            self.truncated(set, |p| {
+                accepting_patterns.contains(&p)
+                    && self.patterns[p].non_greedy
+                    && !self.patterns[p].has_lookahead
+            })
+        };
+        Ok((accepting, effective))
+    }
+
+    /// Returns the NFA states of the set without the states of the patterns that fulfill the
+    /// predicate, starting with the accepting state of the pattern. The remaining states of such a
+    /// pattern are on the paths that are preferred over the match.
+    fn truncated(&self, set: &[StateID], predicate: impl Fn(usize) -> bool) -> Vec<StateID> {
+        let mut matched = Vec::new();
+        set.iter()
+            .copied()
+            .filter(|s| {
+                let data = &self.nfa_states[*s];
+                let Some(pattern) = data.pattern.filter(|p| predicate(*p)) else {
+                    return true;
+                };
+                if matched.contains(&pattern) {
+                    return false;
+                }
+                if data.accepting {
+                    matched.push(pattern);
+                    return false;
+                }
+                true
+            })
+            .collect()
+    }
+
+    /// Calculates the look table of the DFA state `id` with the NFA states `set`.
+    /// The result is empty if no assertion leads to a new state.
+    fn look_targets(
+        &mut self,
+        id: StateSetID,
+        set: &[StateID],
+        at_start: bool,
+    ) -> Result<Vec<StateSetID>> {
+        if set.iter().all(|s| self.nfa_states[*s].looks.is_empty()) {
+            return Ok(Vec::new());
+        }
+        let mut looks = Vec::with_capacity(LOOK_CONTEXTS * LOOK_CONTEXTS);
+        for prev in LOOK_CONTEXT_CHARS {
+            for next in LOOK_CONTEXT_CHARS {
+                debug_assert_eq!(
+                    looks.len(),
+                    look_context(prev) * LOOK_CONTEXTS + look_context(next)
+                );
+                let closure = self.closure(set.iter().copied(), Some((prev, next)));
+                // The closure contains the set, so the same length means the same states.
+                if closure.len() == set.len() {
+                    looks.push(id);
+                } else {
+                    looks.push(self.add_state(closure, at_start)?);
+                }
+            }
+        }
+        if looks.iter().all(|target| *target == id) {
+            looks.clear();
+        }
+        Ok(looks)
+    }
+
+    /// Calculates the closure of the given NFA states over the epsilon transitions.
+    /// If the characters around the position are given, the look transitions whose assertions
+    /// hold are followed as well.
+    ///
+    /// The given states are expected in priority order. The closure is sorted like a [StateKey].
+    fn closure(
+        &self,
+        states: impl IntoIterator<Item = StateID>,
+        around: Option<(Option<char>, Option<char>)>,
+    ) -> Vec<StateID> {
+        // A depth-first search that visits the states in priority order.
+        let mut stack = states.into_iter().collect::<Vec<_>>();
+        stack.reverse();
+        let mut seen = FxHashSet::default();
+        let mut closure = Vec::new();
+        while let Some(state) = stack.pop() {
+            if !seen.insert(state) {
+                continue;
+            }
+            closure.push(state);
+            let data = &self.nfa_states[state];
+            if let Some((prev, next)) = around {
+                stack.extend(
+                    data.looks
+                        .iter()
+                        .rev()
+                        .filter(|(look, _)| look.is_satisfied(prev, next))
+                        .map(|(_, target)| *target),
+                );
+            }
+            stack.extend(data.epsilons.iter().rev().copied());
+        }
+        closure.sort_by_key(|state| {
+            let pattern = self.nfa_states[*state].pattern;
+            let ordered = pattern.is_some_and(|p| self.patterns[p].non_greedy);
+            (pattern, if ordered { StateID::default() } else { *state })
+        });
+        closure
+    }
+
+    /// Returns the id of the DFA state with the given NFA states. The state is created if it
+    /// doesn't exist yet.
+    fn add_state(&mut self, set: Vec<StateID>, at_start: bool) -> Result<StateSetID> {
+        let key = (set, at_start);
+        if let Some(id) = self.state_map.get(&key) {
+            return Ok(*id);
+        }
+        self.limits
+            .check_dfa_states(self.keys.len() + 1)
+            .map_err(|e| e.with_pattern(self.main_pattern_of(&key.0)))?;
+        let id = StateSetID::new(self.keys.len() as StateIDBase);
+        self.keys.push(key.clone());
+        self.state_map.insert(key, id);
+        Ok(id)
+    }
+
+    /// Returns the pattern that contributes the most NFA states to the given set.
+    fn main_pattern_of(&self, set: &[StateID]) -> &str {
+        let mut counts = vec![0; self.patterns.len()];
+        for pattern in set.iter().filter_map(|s| self.nfa_states[*s].pattern) {
+            counts[pattern] += 1;
+        }
+        counts
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, count)| (**count, std::cmp::Reverse(*index)))
+            .map_or("", |(index, _)| self.patterns[index].pattern.as_str())
+    }
+}
@@ -1,15 +1,18 @@
 //! The `dot` module contains the conversion from an finite automata to a graphviz dot format.
 //! The functions in this module are used for testing and debugging purposes.
 
-use std::io::Write;
+use std::{collections::BTreeMap, io::Write};
 
 use dot_writer::{Attributes, DotWriter, RankDirection, Scope};
 
 use crate::internal::compiled_dfa::CompiledDfa;
 
+use super::{ids::StateSetID, StateIDBase, SymbolID, TerminalID, TokenNames};
+#[cfg(feature = "compiler")]
 use super::{nfa::Nfa, CharClassID, CharacterClassRegistry, MultiPatternNfa, StateID};
 
 /// Render the NFA to a graphviz dot format.
+#[cfg(feature = "compiler")]
 #[allow(dead_code)]
 pub(crate) fn nfa_render<W: Write>(nfa: &Nfa, label: &str, output: &mut W) {
     let mut writer = DotWriter::from(output);
@@ -60,54 +63,102 @@ pub(crate) fn nfa_render<W: Write>(nfa: &Nfa, label: &str, output: &mut W) {
                 .attributes()
                 .set_label("ε");
         }
+        for look_transition in state.look_transitions() {
+            let target_state = look_transition.target_state();
+            digraph
+                .edge(
+                    source_id.clone(),
+                    format!("node_{}", target_state.as_usize()),
+                )
+                .attributes()
+                .set_label(
+                    &look_transition
+                        .look()
+                        .to_string()
+                        .escape_default()
+                        .to_string(),
+                );
+        }
+    }
+}
+
+/// Returns the label of a terminal, which is the token name if the token type has one.
+fn terminal_label(terminal_id: TerminalID, token_names: &TokenNames) -> String {
+    match token_names.name(terminal_id.as_usize()) {
+        Some(name) => name.to_string(),
+        None => format!("T{}", terminal_id),
     }
 }
 
 fn render_compiled_dfa(
     compiled_dfa: &CompiledDfa,
     node_prefix: &str,
-    character_class_registry: &CharacterClassRegistry,
+    token_names: &TokenNames,
     graph: &mut Scope,
 ) {
-    // Render the states of the NFA
-    for id in 0..compiled_dfa.states.len() {
+    // Render the states of the DFA
+    for (id, state) in compiled_dfa.states.iter().enumerate() {
         let node_name = format!("\"{}{}\"", node_prefix, id);
         let mut source_node = graph.node_named(&node_name);
         if id == 0 {
-            // Start state of the compiled NFA
+            // Start state of the compiled DFA
             source_node
                 .set_shape(dot_writer::Shape::Circle)
                 .set_color(dot_writer::Color::Blue)
                 .set_pen_width(3.0);
             source_node.set_label(&id.to_string());
-        } else if compiled_dfa.end_states[id].0 {
+        } else if !state.accepting.is_empty() {
             source_node
                 .set_shape(dot_writer::Shape::Circle)
                 .set_color(dot_writer::Color::Red)
                 .set_pen_width(3.0);
-            source_node.set_label(&format!("{} T{}", id, compiled_dfa.end_states[id].1));
+            let terminals = state
+                .accepting
+                .iter()
+                .map(|(terminal_id, _)| terminal_label(*terminal_id, token_names))
+                .collect::<Vec<_>>();
+            source_node.set_label(&format!("{} {}", id, terminals.join(",")));
         } else {
             source_node.set_label(&id.to_string());
         }
     }
-    // Render the transitions of the NFA
-    for (id, state) in compiled_dfa.states.iter().enumerate() {
-        for (cc, next) in state.transitions.iter() {
-            // Label the edge with the character class used to transition to the target state.
+    // Render the transitions of the DFA
+    for id in 0..compiled_dfa.states.len() {
+        // Group the symbols by their target state to get one edge per target state.
+        let mut targets: BTreeMap<StateSetID, Vec<SymbolID>> = BTreeMap::new();
+        for (symbol, next) in compiled_dfa.transitions_of(StateSetID::new(id as StateIDBase)) {
+            targets.entry(next).or_default().push(symbol);
+        }
+        for (next, symbols) in targets {
+            // Label the edge with the characters used to transition to the target state.
+            let label = symbols
+                .iter()
+                .map(|symbol| compiled_dfa.alphabet.label_of_symbol(*symbol))
+                .collect::<String>();
             graph
                 .edge(
                     format!("\"{}{}\"", node_prefix, id),
                     format!("\"{}{}\"", node_prefix, next.as_usize()),
                 )
                 .attributes()
-                .set_label(&format!(
-                    "{} (C#{})",
-                    character_class_registry.get_character_class(*cc).map_or(
-                        "-".to_string(),
-                        |cc| cc.ast().to_string().escape_debug().to_string()
-                    ),
-                    cc.id()
-                ));
+                .set_label(&format!("[{}]", label).escape_debug().to_string());
+        }
+        // Render the zero-width assertions as edges to the distinct targets of the look table.
+        let mut look_targets = compiled_dfa.states[id].looks.clone();
+        look_targets.sort();
+        look_targets.dedup();
+        for next in look_targets
+            .into_iter()
+            .filter(|next| next.as_usize() != id)
+        {
+            graph
+                .edge(
+                    format!("\"{}{}\"", node_prefix, id),
+                    format!("\"{}{}\"", node_prefix, next.as_usize()),
+                )
+                .attributes()
+                .set_label("look")
+                .set_style(dot_writer::Style::Dashed);
         }
     }
 }
@@ -116,7 +167,7 @@ fn render_compiled_dfa(
 pub(crate) fn compiled_dfa_render<W: Write>(
     compiled_dfa: &CompiledDfa,
     label: &str,
-    character_class_registry: &CharacterClassRegistry,
+    token_names: &TokenNames,
     output: &mut W,
 ) {
     let mut writer = DotWriter::from(output);
@@ -127,33 +178,51 @@ pub(crate) fn compiled_dfa_render<W: Write>(
             format!(
                 "{}: {}...",
                 label,
-                compiled_dfa.pattern(0.into()).escape_default()
+                compiled_dfa.patterns[0].escape_default()
             )
             .as_str(),
         )
         .set_rank_direction(RankDirection::LeftRight);
 
-    render_compiled_dfa(compiled_dfa, "", character_class_registry, &mut digraph);
+    render_compiled_dfa(compiled_dfa, "", token_names, &mut digraph);
 
     // Render the lookaheads of the DFA each into a separate cluster
     for (terminal_id, lookahead) in compiled_dfa.lookaheads.iter() {
         let mut cluster = digraph.cluster();
         cluster.set_label(&format!(
-            "LA for T{}({})",
-            terminal_id,
+            "LA for {}({})",
+            terminal_label(*terminal_id, token_names),
             if lookahead.is_positive { "Pos" } else { "Neg" }
         ));
         let node_prefix = format!("{}_", terminal_id);
         render_compiled_dfa(
             &lookahead.nfa,
             &node_prefix,
-            character_class_registry,
+            &TokenNames::default(),
+            &mut cluster,
+        );
+    }
+
+    // Render the lookbehinds of the DFA each into a separate cluster
+    for (terminal_id, lookbehind) in compiled_dfa.lookbehinds.iter() {
+        let mut cluster = digraph.cluster();
+        cluster.set_label(&format!(
+            "LB for {}({}, reversed)",
+            terminal_label(*terminal_id, token_names),
+            if lookbehind.is_positive { "Pos" } else { "Neg" }
+        ));
+        let node_prefix = format!("{}_lb_", terminal_id);
+        render_compiled_dfa(
+            &lookbehind.nfa,
+            &node_prefix,
+            &TokenNames::default(),
             &mut cluster,
         );
     }
 }
 
 /// Render a MultiPatternNfa to a graphviz dot format.
+#[cfg(feature = "compiler")]
 #[allow(dead_code)]
 pub(crate) fn multi_pattern_nfa_render<W: Write>(
     multi_pattern_nfa: &MultiPatternNfa,
@@ -192,6 +261,20 @@ pub(crate) fn multi_pattern_nfa_render<W: Write>(
         });
     });
 
+    // Add the look transitions of the NFAs to the look transitions
+    let mut look_transitions = Vec::new();
+    multi_pattern_nfa.nfas.iter().for_each(|nfa| {
+        nfa.states().iter().for_each(|state| {
+            state.look_transitions().iter().for_each(|t| {
+                look_transitions.push((
+                    state.id().as_usize(),
+                    t.target_state().as_usize(),
+                    t.look(),
+                ));
+            });
+        });
+    });
+
     // Add the transitions of the NFAs to the transitions
     let transitions = multi_pattern_nfa.nfas.iter().fold(
         Vec::<(StateID, StateID, CharClassID)>::new(),
@@ -229,6 +312,13 @@ pub(crate) fn multi_pattern_nfa_render<W: Write>(
             .set_label("ε");
     }
 
+    for (source_id, target_id, look) in look_transitions {
+        digraph
+            .edge(format!("node_{}", source_id), format!("node_{}", target_id))
+            .attributes()
+            .set_label(&look.to_string().escape_default().to_string());
+    }
+
     for (source_id, target_id, char_class_id) in transitions {
         digraph
             .edge(
@@ -1,17 +1,48 @@
+use std::sync::Arc;
+
 use log::trace;
 
-use crate::{Match, PeekResult, Position, ScannerModeSwitcher};
+use crate::{
+    Checkpoint, ColumnUnit, LineIndex, Match, PeekResult, Position, Result, ScannerModeSwitcher,
+    ScnrError, ScnrErrorKind, Span, UnmatchedInput,
+};
+
+use super::{CharIndices, Haystack, ScannerImpl};
+
+/// A match that is found by [FindMatchesImpl::try_next_step].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScanStep {
+    /// The match, which already has the offset of the iterator applied.
+    pub(crate) matched: Match,
+    /// True if the token type of the match is marked as skipped.
+    pub(crate) skipped: bool,
+    /// The scanner mode that is active after the match if it triggered a mode transition.
+    pub(crate) mode_switch: Option<usize>,
+    /// True if unmatched input was skipped before the match, see [UnmatchedInput::Skip].
+    pub(crate) skipped_input: bool,
+}
 
-use super::ScannerImpl;
+impl ScanStep {
+    /// Creates the step of a match of unmatched input, which is neither skipped nor triggers a
+    /// mode transition.
+    fn unmatched(matched: Match) -> Self {
+        Self {
+            matched,
+            skipped: false,
+            mode_switch: None,
+            skipped_input: false,
+        }
+    }
+}
 
 /// An iterator over all non-overlapping matches.
 pub(crate) struct FindMatchesImpl<'h> {
     // The scanner used to find matches.
     scanner_impl: ScannerImpl,
     // The input haystack.
-    input: &'h str,
+    input: Haystack<'h>,
     // The char_indices iterator of the input haystack.
-    char_indices: std::str::CharIndices<'h>,
+    char_indices: CharIndices<'h>,
     // The last position of the char_indices iterator.
     last_position: usize,
     // The last iterated character.
@@ -23,19 +54,29 @@ pub(crate) struct FindMatchesImpl<'h> {
     // The offset of the char_indices iterator in bytes.
     // It is used to calculate the start position of each match.
     offset: usize,
+    // The unit in which the column numbers of positions are counted.
+    column_unit: ColumnUnit,
+    // The complete index of the lines of the haystack. If it is set, it is used instead of the
+    // recorded line offsets.
+    line_index: Option<Arc<LineIndex>>,
+    // The error at which the last call of `next_match` stopped.
+    last_error: Option<ScnrError>,
 }
 
 impl<'h> FindMatchesImpl<'h> {
     /// Creates a new `FindMatches` iterator.
-    pub(crate) fn new(scanner_impl: ScannerImpl, input: &'h str) -> Self {
+    pub(crate) fn new(scanner_impl: ScannerImpl, input: Haystack<'h>) -> Self {
         let mut me = Self {
             scanner_impl,
             input,
-            char_indices: input.char_indices(),
+            char_indices: input.char_indices(0),
             last_position: 0,
             last_char: '\0',
             line_offsets: vec![0],
             offset: 0,
+            column_unit: ColumnUnit::Byte,
+            line_index: None,
+            last_error: None,
         };
         me.scanner_impl.reset();
         me
@@ -52,48 +93,163 @@ impl<'h> FindMatchesImpl<'h> {
     /// The function is used to set the position of the char_indices iterator to the given position.
     pub(crate) fn set_offset(&mut self, offset: usize) {
         trace!("Set offset to {}", offset);
-        if offset <= self.input.len() {
-            // Split the input a byte position `offset` and create a new char_indices iterator.
-            self.char_indices = self.input[offset..].char_indices();
-        } else {
-            // The position is greater than the length of the haystack.
-            // Take an empty slice after the haystack to create an empty char_indices iterator.
-            self.char_indices = self.input[self.input.len()..self.input.len()].char_indices();
-        }
+        // Split the input a byte position `offset` and create a new char_indices iterator.
+        // If the position is greater than the length of the haystack, the iterator is empty.
+        self.char_indices = self.input.char_indices(offset);
         self.last_position = 0;
         self.offset = offset;
     }
 
+    /// Captures the state of the iterator that determines the following matches.
+    pub(crate) fn checkpoint(&self) -> Checkpoint<'h> {
+        Checkpoint {
+            char_indices: self.char_indices.clone(),
+            offset: self.offset,
+            last_position: self.last_position,
+            last_char: self.last_char,
+            mode: self.scanner_impl.current_mode(),
+            mode_stack: self.scanner_impl.mode_stack().clone(),
+        }
+    }
+
+    /// Restores the state of the iterator from the checkpoint.
+    /// The recorded line offsets are kept, because they don't depend on the state of the iterator
+    /// but only on the haystack. The last error is dropped, because it belongs to input behind the
+    /// checkpoint.
+    pub(crate) fn restore(&mut self, checkpoint: &Checkpoint<'h>) {
+        trace!(
+            "Restore checkpoint at offset {} in mode {}",
+            checkpoint.offset + checkpoint.last_position,
+            checkpoint.mode
+        );
+        self.char_indices = checkpoint.char_indices.clone();
+        self.offset = checkpoint.offset;
+        self.last_position = checkpoint.last_position;
+        self.last_char = checkpoint.last_char;
+        self.scanner_impl.set_mode(checkpoint.mode);
+        self.scanner_impl.set_mode_stack(&checkpoint.mode_stack);
+        self.last_error = None;
+    }
+
     /// Returns the next match in the haystack.
     ///
-    /// If no match is found, `None` is returned.
-    ///
-    /// The function calls the `find_from` method of the scanner to find the next match.
-    /// If a match is found, the function advances the char_indices iterator to the end of the match.
-    /// If no match is found, the function repeatedly advances the char_indices iterator by one
-    /// and tries again until a match is found or the iterator is exhausted.
+    /// If no match is found, `None` is returned. This is also the case if the scanner stops at
+    /// unmatched input, see [Self::try_next_match]. The error is kept until the next call and is
+    /// returned by [Self::last_error].
     #[inline]
     pub(crate) fn next_match(&mut self) -> Option<Match> {
-        let mut result;
+        match self.try_next_match() {
+            Ok(matched) => {
+                self.last_error = None;
+                matched
+            }
+            Err(error) => {
+                self.last_error = Some(error);
+                None
+            }
+        }
+    }
+
+    /// Returns the error at which the last call of [Self::next_match] stopped.
+    #[inline]
+    pub(crate) fn last_error(&self) -> Option<&ScnrError> {
+        self.last_error.as_ref()
+    }
+
+    /// Returns the next match in the haystack or `None` if the haystack is exhausted.
+    ///
+    /// Matches of token types that are marked as skipped are consumed without being returned.
+    /// See [Self::try_next_step] for the search itself.
+    pub(crate) fn try_next_match(&mut self) -> Result<Option<Match>> {
+        while let Some(step) = self.try_next_step()? {
+            if !step.skipped {
+                return Ok(Some(step.matched));
+            }
+            trace!("Skipped match {:?}", step.matched);
+        }
+        Ok(None)
+    }
+
+    /// Returns the next match in the haystack including matches of skipped token types, or `None`
+    /// if the haystack is exhausted.
+    ///
+    /// The function calls the `peek_from` method of the scanner to find the next match.
+    /// If a match is found, the function executes a possible mode switch and advances the
+    /// char_indices iterator to the end of the match.
+    /// If no match is found, the unmatched input is handled as configured by [UnmatchedInput].
+    /// The function either advances the char_indices iterator by one and tries again, or it
+    /// collects the maximal run of unmatched characters into one match, or it returns an error.
+    pub(crate) fn try_next_step(&mut self) -> Result<Option<ScanStep>> {
         trace!("Find next match from offset {}", self.offset);
+        // The start of a run of unmatched characters that is returned as one match.
+        let mut unmatched_start = None;
+        // True if unmatched characters are skipped.
+        let mut skipped_input = false;
         loop {
-            result = self
+            let result = self
                 .scanner_impl
-                .find_from(self.input, self.char_indices.clone());
+                .peek_from(self.input, self.char_indices.clone());
             if let Some(mut matched) = result {
+                if let Some(start) = unmatched_start {
+                    // The match is found again by the next call.
+                    return Ok(Some(ScanStep::unmatched(self.unmatched_match(start))));
+                }
+                let skipped = self.scanner_impl.is_skipped(matched.token_type());
+                let mode_switch = self.scanner_impl.has_transition(matched.token_type());
+                self.scanner_impl.execute_possible_mode_switch(&matched);
                 self.advance_beyond_match(matched);
                 matched.add_offset(self.offset);
-                return Some(matched);
-            } else if let Some((i, c)) = self.char_indices.next() {
-                self.record_line_offset(i + self.offset, c);
-            } else {
+                return Ok(Some(ScanStep {
+                    matched,
+                    skipped,
+                    mode_switch,
+                    skipped_input,
+                }));
+            }
+            let start = self.char_indices.offset();
+            let mut char_indices = self.char_indices.clone();
+            let Some((i, c)) = char_indices.next() else {
                 // The iterator is exhausted.
                 // We should update the line offsets with the last character of the haystack.
                 self.record_line_offset(self.last_position + self.offset, '\0');
-                break;
+                return Ok(
+                    unmatched_start.map(|start| ScanStep::unmatched(self.unmatched_match(start)))
+                );
+            };
+            match self.scanner_impl.unmatched_input() {
+                UnmatchedInput::Skip => skipped_input = true,
+                UnmatchedInput::Token(_) => {
+                    unmatched_start.get_or_insert(start);
+                }
+                UnmatchedInput::Error => {
+                    let mode = self.scanner_impl.current_mode();
+                    return Err(ScnrError::new(ScnrErrorKind::UnmatchedInput {
+                        offset: start + self.offset,
+                        mode: self
+                            .scanner_impl
+                            .mode_name(mode)
+                            .unwrap_or_default()
+                            .to_string(),
+                    }));
+                }
             }
+            self.char_indices = char_indices;
+            self.record_line_offset(i + self.offset, c.as_char());
         }
-        result
+    }
+
+    /// Returns the match of the unmatched characters from the given start to the current position
+    /// of the char_indices iterator. The scanner must be configured with [UnmatchedInput::Token].
+    fn unmatched_match(&self, start: usize) -> Match {
+        let UnmatchedInput::Token(token_type) = self.scanner_impl.unmatched_input() else {
+            unreachable!("Unmatched input is only returned as match with UnmatchedInput::Token");
+        };
+        let end = self.char_indices.offset();
+        trace!("Unmatched input from {} to {}", start, end);
+        Match::new(
+            token_type,
+            Span::new(start + self.offset, end + self.offset),
+        )
     }
 
     /// Peeks n matches ahead without consuming the matches.
@@ -103,12 +259,42 @@ impl<'h> FindMatchesImpl<'h> {
     /// triggered by the last match. The mode switch is not conducted by the peek operation to not
     /// change the state of the scanner as well as to avoid a mix of tokens from different modes
     /// being returned.
+    ///
+    /// Matches of token types that are marked as skipped are not returned and not counted. A mode
+    /// switch that is triggered by a skipped match stops the peek operation, too.
+    ///
+    /// Unmatched input stops the peek operation unless the scanner is configured with
+    /// [UnmatchedInput::Token]. Then the maximal run of unmatched characters is returned as one
+    /// match.
     pub(crate) fn peek_n(&mut self, n: usize) -> PeekResult {
+        let (matches, mode_switch) = self.peek_matches(n, false);
+        Self::peek_result(matches, n, mode_switch).map(|(matched, _)| matched)
+    }
+
+    /// Peeks n matches ahead like [Self::peek_n], but follows the mode switches that are
+    /// triggered by the peeked matches. Each match is returned with the scanner mode it was found
+    /// in. The mode switches are simulated and the current mode and the mode stack are restored
+    /// afterwards, so the peek operation never stops at a mode switch.
+    pub(crate) fn peek_n_across_modes(&mut self, n: usize) -> PeekResult<(Match, usize)> {
+        let (matches, mode_switch) = self.peek_matches(n, true);
+        debug_assert!(mode_switch.is_none());
+        Self::peek_result(matches, n, mode_switch)
+    }
+
+    /// Collects up to n matches ahead together with the scanner mode they were found in.
+    /// Returns the matches and the new mode if the peek operation stopped at a mode switch.
+    /// If `follow_mode_switches` is true, the mode switches are conducted and undone at the end.
+    fn peek_matches(
+        &mut self,
+        n: usize,
+        follow_mode_switches: bool,
+    ) -> (Vec<(Match, usize)>, Option<usize>) {
         let mut char_indices = self.char_indices.clone();
         let mut matches = Vec::with_capacity(n);
-        let mut mode_switch = false;
-        let mut new_mode = 0;
-        for _ in 0..n {
+        let mut mode_switch = None;
+        let mode = self.scanner_impl.current_mode();
+        let mode_stack = self.scanner_impl.mode_stack().clone();
+        while matches.len() < n {
             let result = self
                 .scanner_impl
                 .peek_from(self.input, char_indices.clone());
@@ -116,17 +302,51 @@ impl<'h> FindMatchesImpl<'h> {
                 let token_type = matched.token_type();
                 Self::advance_char_indices_beyond_match(&mut char_indices, matched);
                 matched.add_offset(self.offset);
-                matches.push(matched);
+                if !self.scanner_impl.is_skipped(token_type) {
+                    matches.push((matched, self.scanner_impl.current_mode()));
+                }
                 if let Some(mode) = self.scanner_impl.has_transition(token_type) {
-                    mode_switch = true;
-                    new_mode = mode;
+                    if follow_mode_switches {
+                        self.scanner_impl.execute_possible_mode_switch(&matched);
+                    } else {
+                        mode_switch = Some(mode);
+                        break;
+                    }
+                }
+            } else if let UnmatchedInput::Token(token_type) = self.scanner_impl.unmatched_input() {
+                // Collect the maximal run of unmatched characters like `try_next_match`.
+                let start = char_indices.offset();
+                while char_indices.next().is_some()
+                    && self
+                        .scanner_impl
+                        .peek_from(self.input, char_indices.clone())
+                        .is_none()
+                {}
+                let end = char_indices.offset();
+                if end == start {
                     break;
                 }
+                matches.push((
+                    Match::new(
+                        token_type,
+                        Span::new(start + self.offset, end + self.offset),
+                    ),
+                    self.scanner_impl.current_mode(),
+                ));
             } else {
                 break;
             }
         }
-        if mode_switch {
+        if follow_mode_switches {
+            self.scanner_impl.set_mode(mode);
+            self.scanner_impl.set_mode_stack(&mode_stack);
+        }
+        (matches, mode_switch)
+    }
+
+    /// Creates the result of a peek operation for n requested matches.
+    fn peek_result<T>(matches: Vec<T>, n: usize, mode_switch: Option<usize>) -> PeekResult<T> {
+        if let Some(new_mode) = mode_switch {
             PeekResult::MatchesReachedModeSwitch((matches, new_mode))
         } else if matches.len() == n {
             PeekResult::Matches(matches)
@@ -148,13 +368,13 @@ impl<'h> FindMatchesImpl<'h> {
     }
 
     /// Advances the given char_indices iterator to the end of the given match.
-    fn advance_char_indices_beyond_match(char_indices: &mut std::str::CharIndices, matched: Match) {
+    fn advance_char_indices_beyond_match(char_indices: &mut CharIndices, matched: Match) {
         if matched.is_empty() {
             return;
         }
         let end = matched.span().end;
-        for (i, c) in char_indices {
-            if i + c.len_utf8() >= end {
+        while char_indices.next().is_some() {
+            if char_indices.offset() >= end {
                 // Stop at the end of the match.
                 break;
             }
@@ -179,13 +399,13 @@ impl<'h> FindMatchesImpl<'h> {
         let mut new_position = 0;
         let mut line_start_offsets = vec![];
         let mut last_char = self.last_char;
-        for (i, c) in self.char_indices.by_ref() {
+        while let Some((i, c)) = self.char_indices.next() {
             if last_char == '\n' {
                 line_start_offsets.push(i + self.offset);
             }
-            last_char = c;
+            last_char = c.as_char();
             new_position = i;
-            if i + c.len_utf8() >= position {
+            if self.char_indices.offset() >= position {
                 break;
             }
         }
@@ -207,24 +427,61 @@ impl<'h> FindMatchesImpl<'h> {
 
     /// Returns the line and column numbers of the given offset.
     /// The line number is the index of the line offset in the vector plus one.
-    /// The column number is actually the number of characters found from the line offset to the
-    /// offset plus one. See note below for more about the inaccuracy of the column number.
+    /// The column number is counted from the line offset to the offset in the configured
+    /// [ColumnUnit]. With the default [ColumnUnit::Byte] it is simply the difference of both
+    /// offsets plus one, the other units count the characters of the line up to the offset.
     /// If the offset is greater than the length of the haystack, the function returns the last
     /// recorded line and the column number is calculated from the last recorded position.
-    ///
-    /// *Note:*
-    ///
-    /// This function simply calculates the column number by subtracting the last recorded line
-    /// offset from the given offset. This is not always the correct column number, especially when
-    /// the line contains characters with more then one byte length.
-    /// This inaccuracy is accepted in favor of performance.
     pub(crate) fn position(&self, offset: usize) -> Position {
+        let (line, line_offset) = self.line_of(offset);
+        let column = match self.column_unit {
+            ColumnUnit::Byte => offset.saturating_sub(line_offset) + 1,
+            unit => {
+                // An offset within a character counts up to the start of the character.
+                let end = self.input.floor_char_boundary(offset.min(self.input.len()));
+                unit.column(self.input.slice(line_offset.min(end)..end))
+            }
+        };
+        Position::new(line, column)
+    }
+
+    /// Returns the number of the last recorded line that starts at or before the given offset and
+    /// the offset of its start. With a line index the line is looked up in the index.
+    pub(crate) fn line_of(&self, offset: usize) -> (usize, usize) {
+        if let Some(line_index) = &self.line_index {
+            return line_index.line_of(offset);
+        }
         match self.line_offsets.binary_search_by(|&x| x.cmp(&offset)) {
-            Ok(i) => Position::new(i + 1, offset.saturating_sub(self.line_offsets[i]) + 1),
-            Err(i) => Position::new(i, offset.saturating_sub(self.line_offsets[i - 1]) + 1),
+            Ok(i) => (i + 1, self.line_offsets[i]),
+            Err(i) => (i, self.line_offsets[i - 1]),
         }
     }
 
+    /// Returns the unit in which the column numbers of positions are counted.
+    pub(crate) fn column_unit(&self) -> ColumnUnit {
+        self.column_unit
+    }
+
+    /// Sets the unit in which the column numbers of positions are counted.
+    pub(crate) fn set_column_unit(&mut self, column_unit: ColumnUnit) {
+        self.column_unit = column_unit;
+    }
+
+    /// Returns the line index if one is set.
+    pub(crate) fn line_index(&self) -> Option<&Arc<LineIndex>> {
+        self.line_index.as_ref()
+    }
+
+    /// Sets the line index that is used to calculate the positions.
+    pub(crate) fn set_line_index(&mut self, line_index: Arc<LineIndex>) {
+        debug_assert_eq!(
+            line_index.len(),
+            self.input.len(),
+            "The line index must be built over the haystack"
+        );
+        self.line_index = Some(line_index);
+    }
+
     /// Records the offset of a line in the haystack.
     fn record_line_offset(&mut self, i: usize, c: char) {
         if self.last_char == '\n' {
@@ -248,6 +505,10 @@ impl<'h> FindMatchesImpl<'h> {
         self.scanner_impl.mode_name(index)
     }
 
+    pub(crate) fn mode_stack_depth(&self) -> usize {
+        self.scanner_impl.mode_stack_depth()
+    }
+
     /// Merges the given line start offsets with the current line start offsets.
     /// The function is used to merge the given line start offsets.
     /// Already existing line start offsets are not added to the vector.
@@ -273,7 +534,7 @@ impl std::fmt::Debug for FindMatchesImpl<'_> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "compiler"))]
 mod tests {
     use super::*;
     use crate::{MatchExt, MatchExtIterator, Pattern, ScannerBuilder, ScannerMode};
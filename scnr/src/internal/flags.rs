@@ -0,0 +1,138 @@
+use regex_syntax::ast::{self, Flag, FlagsItemKind};
+
+/// The regex flags that are in effect while a regex AST is converted into a NFA.
+///
+/// The flags can be set with inline flag groups like `(?i)` or `(?i:...)` in the pattern.
+/// A flag group without a sub-expression modifies the flags until the end of the enclosing group,
+/// a flag group with a sub-expression modifies the flags only for the sub-expression.
+/// This is the same behavior as in the `regex` crate.
+///
+/// The flag `x` (ignore whitespace) is not stored here because it is handled by the regex parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Flags {
+    /// `i`: Letters match both upper and lower case (Unicode simple case folding).
+    pub(crate) case_insensitive: bool,
+    /// `m`: `^` and `$` match at the begin and end of a line.
+    pub(crate) multi_line: bool,
+    /// `s`: `.` also matches `\n`.
+    pub(crate) dot_matches_new_line: bool,
+    /// `U`: Swaps the meaning of `x*` and `x*?`.
+    pub(crate) swap_greed: bool,
+    /// `u`: Unicode support, enabled by default.
+    pub(crate) unicode: bool,
+    /// `R`: Enables CRLF mode, i.e. `\r\n` is used as line terminator.
+    pub(crate) crlf: bool,
+}
+
+impl Flags {
+    /// Applies the flags of an inline flag group to the current flags.
+    pub(crate) fn apply(&mut self, flags: &ast::Flags) {
+        let mut enable = true;
+        for item in &flags.items {
+            match item.kind {
+                FlagsItemKind::Negation => enable = false,
+                FlagsItemKind::Flag(flag) => match flag {
+                    Flag::CaseInsensitive => self.case_insensitive = enable,
+                    Flag::MultiLine => self.multi_line = enable,
+                    Flag::DotMatchesNewLine => self.dot_matches_new_line = enable,
+                    Flag::SwapGreed => self.swap_greed = enable,
+                    Flag::Unicode => self.unicode = enable,
+                    Flag::CRLF => self.crlf = enable,
+                    // Whitespace is already stripped by the regex parser.
+                    Flag::IgnoreWhitespace => (),
+                },
+            }
+        }
+    }
+
+    /// Returns the flags that are relevant for the matching of a single character.
+    /// Character classes that only differ in other flags can share the same match function.
+    pub(crate) fn char_class_flags(self) -> Self {
+        Self {
+            case_insensitive: self.case_insensitive,
+            dot_matches_new_line: self.dot_matches_new_line,
+            unicode: self.unicode,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Self {
+            case_insensitive: false,
+            multi_line: false,
+            dot_matches_new_line: false,
+            swap_greed: false,
+            unicode: true,
+            crlf: false,
+        }
+    }
+}
+
+impl std::fmt::Display for Flags {
+    /// Writes the flags that differ from the default flags in inline flag syntax, e.g. `(?i-u)`.
+    /// Nothing is written if all flags have their default values.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == Self::default() {
+            return Ok(());
+        }
+        let mut flags = String::new();
+        for (set, c) in [
+            (self.case_insensitive, 'i'),
+            (self.multi_line, 'm'),
+            (self.dot_matches_new_line, 's'),
+            (self.swap_greed, 'U'),
+            (self.crlf, 'R'),
+        ] {
+            if set {
+                flags.push(c);
+            }
+        }
+        if !self.unicode {
+            flags.push_str("-u");
+        }
+        write!(f, "(?{})", flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex_syntax::ast::Ast;
+
+    use super::*;
+    use crate::internal::parse_regex_syntax;
+
+    fn flags_of(pattern: &str) -> Flags {
+        let mut flags = Flags::default();
+        match parse_regex_syntax(pattern).unwrap() {
+            Ast::Flags(ref f) => flags.apply(&f.flags),
+            ast => panic!("Expected flags, got {:?}", ast),
+        }
+        flags
+    }
+
+    #[test]
+    fn test_apply_flags() {
+        let flags = flags_of("(?is)");
+        assert!(flags.case_insensitive);
+        assert!(flags.dot_matches_new_line);
+        assert!(flags.unicode);
+        assert_eq!(flags.to_string(), "(?is)");
+
+        let flags = flags_of("(?i-u)");
+        assert!(flags.case_insensitive);
+        assert!(!flags.unicode);
+        assert_eq!(flags.to_string(), "(?i-u)");
+
+        let flags = flags_of("(?-i)");
+        assert_eq!(flags, Flags::default());
+        assert_eq!(flags.to_string(), "");
+    }
+
+    #[test]
+    fn test_char_class_flags() {
+        let flags = flags_of("(?imsUR)");
+        assert_eq!(flags.char_class_flags().to_string(), "(?is)");
+    }
+}
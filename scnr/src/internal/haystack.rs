@@ -0,0 +1,257 @@
+//! Module with the haystack types that abstract over string and byte inputs.
+//!
+//! The scanners match characters. A byte haystack is therefore decoded as UTF-8 on the fly. Each
+//! byte that is not part of a valid UTF-8 sequence is handed to the automata as a raw byte of its
+//! own, see [Unit]. This way all positions remain byte offsets into the haystack.
+
+/// A unit of a haystack that the automata read.
+///
+/// Raw bytes are the bytes of a byte haystack that are not part of a valid UTF-8 sequence. They
+/// are always in the range 0x80..=0xFF and distinct from all characters. Only byte escapes like
+/// `(?-u:\xFF)` match them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Unit {
+    /// A character.
+    Char(char),
+    /// A raw byte.
+    Byte(u8),
+}
+
+impl Unit {
+    /// Returns the character that stands for the unit in the zero-width assertions and when lines
+    /// and columns are counted. A raw byte stands for the replacement character U+FFFD, like in
+    /// [String::from_utf8_lossy].
+    #[inline]
+    pub(crate) fn as_char(self) -> char {
+        match self {
+            Unit::Char(c) => c,
+            Unit::Byte(_) => char::REPLACEMENT_CHARACTER,
+        }
+    }
+}
+
+impl From<char> for Unit {
+    #[inline]
+    fn from(c: char) -> Self {
+        Unit::Char(c)
+    }
+}
+
+/// The haystack a scanner runs on.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Haystack<'h> {
+    /// A string haystack. It is always valid UTF-8.
+    Str(&'h str),
+    /// A byte haystack that can contain invalid UTF-8.
+    Bytes(&'h [u8]),
+}
+
+impl<'h> Haystack<'h> {
+    /// Returns the length of the haystack in bytes.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Haystack::Str(s) => s.len(),
+            Haystack::Bytes(b) => b.len(),
+        }
+    }
+
+    /// Returns the part of the haystack in the given byte range.
+    /// For a string haystack the range must be at character boundaries.
+    #[inline]
+    pub(crate) fn slice(&self, range: std::ops::Range<usize>) -> Haystack<'h> {
+        match self {
+            Haystack::Str(s) => Haystack::Str(&s[range]),
+            Haystack::Bytes(b) => Haystack::Bytes(&b[range]),
+        }
+    }
+
+    /// Returns the greatest character boundary at or before the offset, which must not exceed the
+    /// length. A byte haystack has no character boundaries, so the offset is returned unchanged.
+    #[inline]
+    pub(crate) fn floor_char_boundary(&self, offset: usize) -> usize {
+        match self {
+            Haystack::Str(s) => (0..=offset)
+                .rev()
+                .find(|&i| s.is_char_boundary(i))
+                .unwrap_or(0),
+            Haystack::Bytes(_) => offset,
+        }
+    }
+
+    /// Returns the haystack as byte slice.
+    #[cfg(feature = "regex_automata")]
+    #[inline]
+    pub(crate) fn as_bytes(&self) -> &'h [u8] {
+        match self {
+            Haystack::Str(s) => s.as_bytes(),
+            Haystack::Bytes(b) => b,
+        }
+    }
+
+    /// Returns an iterator over the characters of the haystack starting at the byte position
+    /// `start`. Like [std::str::CharIndices] the positions are relative to `start`.
+    /// If `start` is greater than the length of the haystack, the iterator is empty.
+    #[inline]
+    pub(crate) fn char_indices(&self, start: usize) -> CharIndices<'h> {
+        let start = start.min(self.len());
+        let inner = match self {
+            Haystack::Str(s) => CharIndicesInner::Str(s[start..].char_indices()),
+            Haystack::Bytes(b) => CharIndicesInner::Bytes {
+                bytes: &b[start..],
+                pos: 0,
+            },
+        };
+        CharIndices { start, inner }
+    }
+
+    /// Returns the start position and the character that ends at the byte position `end`.
+    /// The position `end` must be a character boundary.
+    #[cfg(not(feature = "regex_automata"))]
+    #[inline]
+    pub(crate) fn char_before(&self, end: usize) -> Option<(usize, Unit)> {
+        match self {
+            Haystack::Str(s) => s[..end]
+                .chars()
+                .next_back()
+                .map(|c| (end - c.len_utf8(), Unit::Char(c))),
+            Haystack::Bytes(b) => {
+                let last = *b[..end].last()?;
+                if last < 0x80 {
+                    return Some((end - 1, Unit::Char(last as char)));
+                }
+                // The shortest valid sequence that ends here is the character. A valid UTF-8
+                // sequence starts with a lead byte, so it can't be part of a preceding character.
+                (2..=end.min(4))
+                    .find_map(|len| {
+                        std::str::from_utf8(&b[end - len..end])
+                            .ok()
+                            .and_then(|s| s.chars().next())
+                            .map(|c| (end - len, Unit::Char(c)))
+                    })
+                    .or(Some((end - 1, Unit::Byte(last))))
+            }
+        }
+    }
+}
+
+/// Decodes the first unit of the given non-empty byte slice.
+/// Returns the unit and its length in bytes.
+#[inline]
+fn decode_char(bytes: &[u8]) -> (Unit, usize) {
+    let b = bytes[0];
+    if b < 0x80 {
+        return (Unit::Char(b as char), 1);
+    }
+    let prefix = &bytes[..bytes.len().min(4)];
+    let valid = match std::str::from_utf8(prefix) {
+        Ok(s) => s,
+        Err(e) => std::str::from_utf8(&prefix[..e.valid_up_to()]).unwrap(),
+    };
+    match valid.chars().next() {
+        Some(c) => (Unit::Char(c), c.len_utf8()),
+        None => (Unit::Byte(b), 1),
+    }
+}
+
+/// An iterator over the characters of a haystack and their byte positions.
+/// It is the counterpart of [std::str::CharIndices] for [Haystack].
+#[derive(Debug, Clone)]
+pub(crate) struct CharIndices<'h> {
+    /// The position in the haystack where the iteration started.
+    start: usize,
+    inner: CharIndicesInner<'h>,
+}
+
+#[derive(Debug, Clone)]
+enum CharIndicesInner<'h> {
+    Str(std::str::CharIndices<'h>),
+    Bytes {
+        /// The bytes of the haystack from the start position on.
+        bytes: &'h [u8],
+        /// The position of the next character relative to the start position.
+        pos: usize,
+    },
+}
+
+impl CharIndices<'_> {
+    /// Returns the position of the next character relative to the start position.
+    #[inline]
+    pub(crate) fn offset(&self) -> usize {
+        match &self.inner {
+            CharIndicesInner::Str(chars) => chars.offset(),
+            CharIndicesInner::Bytes { pos, .. } => *pos,
+        }
+    }
+
+    /// Returns the position in the haystack where the iteration started.
+    #[inline]
+    pub(crate) fn slice_start(&self) -> usize {
+        self.start
+    }
+}
+
+impl Iterator for CharIndices<'_> {
+    type Item = (usize, Unit);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            CharIndicesInner::Str(chars) => chars.next().map(|(i, c)| (i, Unit::Char(c))),
+            CharIndicesInner::Bytes { bytes, pos } => {
+                if *pos >= bytes.len() {
+                    return None;
+                }
+                let (c, len) = decode_char(&bytes[*pos..]);
+                let index = *pos;
+                *pos += len;
+                Some((index, c))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_char_indices() {
+        let haystack = Haystack::Bytes(b"a\xC3\xA9\xFFb\xE2\x82");
+        let chars: Vec<_> = haystack.char_indices(0).collect();
+        assert_eq!(
+            chars,
+            vec![
+                (0, Unit::Char('a')),
+                (1, Unit::Char('é')),
+                (3, Unit::Byte(0xFF)),
+                (4, Unit::Char('b')),
+                (5, Unit::Byte(0xE2)),
+                (6, Unit::Byte(0x82)),
+            ]
+        );
+        let mut char_indices = haystack.char_indices(3);
+        assert_eq!(char_indices.next(), Some((0, Unit::Byte(0xFF))));
+        assert_eq!(char_indices.offset(), 1);
+        assert_eq!(char_indices.slice_start(), 3);
+    }
+
+    #[cfg(not(feature = "regex_automata"))]
+    #[test]
+    fn test_char_before() {
+        let haystack = Haystack::Bytes(b"a\xC3\xA9\xFFb\xE2\x82");
+        let mut end = haystack.len();
+        let mut chars = Vec::new();
+        while let Some((start, c)) = haystack.char_before(end) {
+            chars.push((start, c));
+            end = start;
+        }
+        chars.reverse();
+        assert_eq!(chars, haystack.char_indices(0).collect::<Vec<_>>());
+
+        let haystack = Haystack::Str("aé€");
+        assert_eq!(haystack.char_before(6), Some((3, Unit::Char('€'))));
+        assert_eq!(haystack.char_before(3), Some((1, Unit::Char('é'))));
+        assert_eq!(haystack.char_before(0), None);
+    }
+}
@@ -99,6 +99,11 @@ impl_id!(StateSetID, StateIDBase);
 pub(crate) type CharClassIDBase = u32;
 impl_id!(CharClassID, CharClassIDBase);
 
+/// The ID type for the symbols of the alphabet of a DFA. A symbol is a set of characters that
+/// belong to exactly the same character classes.
+pub(crate) type SymbolIDBase = u32;
+impl_id!(SymbolID, SymbolIDBase);
+
 /// The ID type for patterns. Actually the index of the pattern in the pattern vector of a scanner
 /// mode. It determines the priority of the pattern, i.e. lower indices have higher priority.
 pub(crate) type PatternIDBase = usize;
@@ -116,5 +121,5 @@ impl_id!(ScannerModeID, ScannerModeIDBase);
 
 /// The ID type for groups in Partitions. This is the index of the group in the partition vector of
 /// the minimizer.
-pub(crate) type StateGroupIDBase = u16;
+pub(crate) type StateGroupIDBase = u32;
 impl_id!(StateGroupID, StateGroupIDBase);
@@ -0,0 +1,297 @@
+#[cfg(feature = "compiler")]
+use regex_syntax::ast::AssertionKind;
+
+use super::perl_word::PERL_WORD;
+#[cfg(feature = "compiler")]
+use super::Flags;
+
+/// The number of classes of characters that the zero-width assertions can distinguish.
+/// The end of the haystack is a class of its own, see [look_context].
+pub(crate) const LOOK_CONTEXTS: usize = 6;
+
+/// A representative character for each class of characters returned by [look_context].
+/// All characters of a class satisfy the same assertions.
+#[cfg(feature = "compiler")]
+pub(crate) const LOOK_CONTEXT_CHARS: [Option<char>; LOOK_CONTEXTS] = [
+    None,
+    Some('\n'),
+    Some('\r'),
+    Some('a'),
+    Some('é'),
+    Some(' '),
+];
+
+/// Returns the class of the character with respect to the zero-width assertions.
+/// `None` stands for the start or the end of the haystack.
+#[inline(always)]
+pub(crate) fn look_context(c: Option<char>) -> usize {
+    match c {
+        None => 0,
+        Some('\n') => 1,
+        Some('\r') => 2,
+        Some(c) if c.is_ascii() => {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                3
+            } else {
+                5
+            }
+        }
+        Some(c) if is_word_character(c) => 4,
+        Some(_) => 5,
+    }
+}
+
+/// Checks if the character is a Unicode word character, i.e. if it is matched by `\w`.
+/// The table is the one of `regex-syntax`, so the compiled scanners don't depend on it.
+pub(crate) fn is_word_character(c: char) -> bool {
+    PERL_WORD
+        .binary_search_by(|(start, end)| {
+            if *end < c {
+                std::cmp::Ordering::Less
+            } else if *start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// A zero-width assertion that is checked against the characters around the current position in
+/// the haystack.
+///
+/// The variants are created from the assertions in a regex AST and the flags that are in effect.
+/// The semantics are the same as in the `regex` crate. Especially `^` and `$` match only at the
+/// start and end of the haystack unless the multi-line flag `m` is set.
+#[cfg(feature = "compiler")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum Look {
+    /// `\A` or `^` without multi-line mode: The start of the haystack.
+    Start,
+    /// `\z` or `$` without multi-line mode: The end of the haystack.
+    End,
+    /// `(?m:^)`: The start of the haystack or the position after a `\n`.
+    StartLF,
+    /// `(?m:$)`: The end of the haystack or the position before a `\n`.
+    EndLF,
+    /// `(?Rm:^)`: Like [Look::StartLF] but `\r`, `\n` and `\r\n` are line terminators.
+    StartCRLF,
+    /// `(?Rm:$)`: Like [Look::EndLF] but `\r`, `\n` and `\r\n` are line terminators.
+    EndCRLF,
+    /// `(?-u:\b)`: An ASCII word boundary.
+    WordAscii,
+    /// `(?-u:\B)`: Not an ASCII word boundary.
+    WordAsciiNegate,
+    /// `\b`: A Unicode word boundary.
+    WordUnicode,
+    /// `\B`: Not a Unicode word boundary.
+    WordUnicodeNegate,
+    /// `(?-u:\b{start})`: The start of an ASCII word.
+    WordStartAscii,
+    /// `(?-u:\b{end})`: The end of an ASCII word.
+    WordEndAscii,
+    /// `\b{start}`: The start of a Unicode word.
+    WordStartUnicode,
+    /// `\b{end}`: The end of a Unicode word.
+    WordEndUnicode,
+    /// `(?-u:\b{start-half})`: The previous character is not an ASCII word character.
+    WordStartHalfAscii,
+    /// `(?-u:\b{end-half})`: The next character is not an ASCII word character.
+    WordEndHalfAscii,
+    /// `\b{start-half}`: The previous character is not a Unicode word character.
+    WordStartHalfUnicode,
+    /// `\b{end-half}`: The next character is not a Unicode word character.
+    WordEndHalfUnicode,
+}
+
+#[cfg(feature = "compiler")]
+impl Look {
+    /// Creates a look from an assertion in the regex AST with respect to the given flags.
+    pub(crate) fn new(kind: &AssertionKind, flags: Flags) -> Self {
+        match (kind, flags.unicode) {
+            (AssertionKind::StartText, _) => Look::Start,
+            (AssertionKind::EndText, _) => Look::End,
+            (AssertionKind::StartLine, _) if !flags.multi_line => Look::Start,
+            (AssertionKind::EndLine, _) if !flags.multi_line => Look::End,
+            (AssertionKind::StartLine, _) if flags.crlf => Look::StartCRLF,
+            (AssertionKind::EndLine, _) if flags.crlf => Look::EndCRLF,
+            (AssertionKind::StartLine, _) => Look::StartLF,
+            (AssertionKind::EndLine, _) => Look::EndLF,
+            (AssertionKind::WordBoundary, true) => Look::WordUnicode,
+            (AssertionKind::WordBoundary, false) => Look::WordAscii,
+            (AssertionKind::NotWordBoundary, true) => Look::WordUnicodeNegate,
+            (AssertionKind::NotWordBoundary, false) => Look::WordAsciiNegate,
+            (AssertionKind::WordBoundaryStart | AssertionKind::WordBoundaryStartAngle, true) => {
+                Look::WordStartUnicode
+            }
+            (AssertionKind::WordBoundaryStart | AssertionKind::WordBoundaryStartAngle, false) => {
+                Look::WordStartAscii
+            }
+            (AssertionKind::WordBoundaryEnd | AssertionKind::WordBoundaryEndAngle, true) => {
+                Look::WordEndUnicode
+            }
+            (AssertionKind::WordBoundaryEnd | AssertionKind::WordBoundaryEndAngle, false) => {
+                Look::WordEndAscii
+            }
+            (AssertionKind::WordBoundaryStartHalf, true) => Look::WordStartHalfUnicode,
+            (AssertionKind::WordBoundaryStartHalf, false) => Look::WordStartHalfAscii,
+            (AssertionKind::WordBoundaryEndHalf, true) => Look::WordEndHalfUnicode,
+            (AssertionKind::WordBoundaryEndHalf, false) => Look::WordEndHalfAscii,
+        }
+    }
+
+    /// Checks if the assertion holds at a position in the haystack.
+    /// `prev` is the character before the position and `next` the character at the position.
+    /// They are `None` at the start and at the end of the haystack respectively.
+    pub(crate) fn is_satisfied(self, prev: Option<char>, next: Option<char>) -> bool {
+        let is_word_ascii =
+            |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        let is_word_unicode = |c: Option<char>| c.is_some_and(is_word_character);
+        match self {
+            Look::Start => prev.is_none(),
+            Look::End => next.is_none(),
+            Look::StartLF => prev.is_none() || prev == Some('\n'),
+            Look::EndLF => next.is_none() || next == Some('\n'),
+            Look::StartCRLF => {
+                prev.is_none() || prev == Some('\n') || (prev == Some('\r') && next != Some('\n'))
+            }
+            Look::EndCRLF => {
+                next.is_none() || next == Some('\r') || (next == Some('\n') && prev != Some('\r'))
+            }
+            Look::WordAscii => is_word_ascii(prev) != is_word_ascii(next),
+            Look::WordAsciiNegate => is_word_ascii(prev) == is_word_ascii(next),
+            Look::WordUnicode => is_word_unicode(prev) != is_word_unicode(next),
+            Look::WordUnicodeNegate => is_word_unicode(prev) == is_word_unicode(next),
+            Look::WordStartAscii => !is_word_ascii(prev) && is_word_ascii(next),
+            Look::WordEndAscii => is_word_ascii(prev) && !is_word_ascii(next),
+            Look::WordStartUnicode => !is_word_unicode(prev) && is_word_unicode(next),
+            Look::WordEndUnicode => is_word_unicode(prev) && !is_word_unicode(next),
+            Look::WordStartHalfAscii => !is_word_ascii(prev),
+            Look::WordEndHalfAscii => !is_word_ascii(next),
+            Look::WordStartHalfUnicode => !is_word_unicode(prev),
+            Look::WordEndHalfUnicode => !is_word_unicode(next),
+        }
+    }
+}
+
+#[cfg(feature = "compiler")]
+impl std::fmt::Display for Look {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Look::Start => r"\A",
+            Look::End => r"\z",
+            Look::StartLF => "(?m:^)",
+            Look::EndLF => "(?m:$)",
+            Look::StartCRLF => "(?mR:^)",
+            Look::EndCRLF => "(?mR:$)",
+            Look::WordAscii => r"(?-u:\b)",
+            Look::WordAsciiNegate => r"(?-u:\B)",
+            Look::WordUnicode => r"\b",
+            Look::WordUnicodeNegate => r"\B",
+            Look::WordStartAscii => r"(?-u:\b{start})",
+            Look::WordEndAscii => r"(?-u:\b{end})",
+            Look::WordStartUnicode => r"\b{start}",
+            Look::WordEndUnicode => r"\b{end}",
+            Look::WordStartHalfAscii => r"(?-u:\b{start-half})",
+            Look::WordEndHalfAscii => r"(?-u:\b{end-half})",
+            Look::WordStartHalfUnicode => r"\b{start-half}",
+            Look::WordEndHalfUnicode => r"\b{end-half}",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "compiler")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_anchors() {
+        assert!(Look::Start.is_satisfied(None, Some('a')));
+        assert!(!Look::Start.is_satisfied(Some('\n'), Some('a')));
+        assert!(Look::StartLF.is_satisfied(Some('\n'), Some('a')));
+        assert!(Look::EndLF.is_satisfied(Some('a'), Some('\n')));
+        assert!(!Look::EndLF.is_satisfied(Some('a'), Some('\r')));
+        assert!(Look::EndCRLF.is_satisfied(Some('a'), Some('\r')));
+        assert!(!Look::EndCRLF.is_satisfied(Some('\r'), Some('\n')));
+        assert!(!Look::StartCRLF.is_satisfied(Some('\r'), Some('\n')));
+        assert!(Look::End.is_satisfied(Some('a'), None));
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        assert!(Look::WordUnicode.is_satisfied(None, Some('a')));
+        assert!(Look::WordUnicode.is_satisfied(Some('ä'), Some(' ')));
+        assert!(!Look::WordAscii.is_satisfied(Some('ä'), Some(' ')));
+        assert!(Look::WordUnicodeNegate.is_satisfied(Some('a'), Some('b')));
+        assert!(Look::WordStartUnicode.is_satisfied(Some(' '), Some('b')));
+        assert!(!Look::WordStartUnicode.is_satisfied(Some('a'), Some(' ')));
+        assert!(Look::WordEndUnicode.is_satisfied(Some('a'), None));
+    }
+
+    #[test]
+    fn test_word_characters_agree_with_regex_syntax() {
+        for c in ('\0'..=char::MAX)
+            .step_by(7)
+            .chain(['é', 'ß', '\u{200C}', '\u{E01EF}'])
+        {
+            assert_eq!(
+                is_word_character(c),
+                regex_syntax::is_word_character(c),
+                "{:?}",
+                c
+            );
+        }
+    }
+
+    #[test]
+    fn test_look_contexts() {
+        const LOOKS: [Look; 18] = [
+            Look::Start,
+            Look::End,
+            Look::StartLF,
+            Look::EndLF,
+            Look::StartCRLF,
+            Look::EndCRLF,
+            Look::WordAscii,
+            Look::WordAsciiNegate,
+            Look::WordUnicode,
+            Look::WordUnicodeNegate,
+            Look::WordStartAscii,
+            Look::WordEndAscii,
+            Look::WordStartUnicode,
+            Look::WordEndUnicode,
+            Look::WordStartHalfAscii,
+            Look::WordEndHalfAscii,
+            Look::WordStartHalfUnicode,
+            Look::WordEndHalfUnicode,
+        ];
+        for (i, c) in LOOK_CONTEXT_CHARS.iter().enumerate() {
+            assert_eq!(look_context(*c), i);
+        }
+        let chars = [
+            None,
+            Some('\n'),
+            Some('\r'),
+            Some('Z'),
+            Some('_'),
+            Some('ß'),
+        ]
+        .into_iter()
+        .chain([Some('-'), Some('\t'), Some('€'), Some('\u{10FFFF}')]);
+        for prev in chars.clone() {
+            for next in chars.clone() {
+                let prev_repr = LOOK_CONTEXT_CHARS[look_context(prev)];
+                let next_repr = LOOK_CONTEXT_CHARS[look_context(next)];
+                for look in LOOKS {
+                    assert_eq!(
+                        look.is_satisfied(prev, next),
+                        look.is_satisfied(prev_repr, next_repr),
+                        "{look} between {prev:?} and {next:?}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,14 +1,15 @@
 use regex_syntax::ast::{
-    Ast, ClassAscii, ClassAsciiKind, ClassBracketed, ClassPerl, ClassPerlKind, ClassSet,
-    ClassSetBinaryOp, ClassSetBinaryOpKind, ClassSetItem, ClassSetRange, ClassSetUnion,
-    ClassUnicode,
-    ClassUnicodeKind::{Named, NamedValue, OneLetter},
-    Literal,
+    self, Ast, ClassAscii, ClassAsciiKind, ClassBracketed, ClassPerl, ClassPerlKind, ClassSet,
+    ClassSetBinaryOp, ClassSetBinaryOpKind, ClassSetItem, ClassSetRange, ClassUnicode, Literal,
 };
+use regex_syntax::hir;
 use seshat::unicode::{props::Gc, Ucd};
+use std::{ops::RangeInclusive, sync::LazyLock};
 
 use crate::{Result, ScnrError};
 
+use super::{Flags, Unit};
+
 macro_rules! unsupported {
     ($feature:expr) => {
         ScnrError::new($crate::ScnrErrorKind::UnsupportedFeature(
@@ -17,344 +18,442 @@ macro_rules! unsupported {
     };
 }
 
-pub(crate) struct MatchFn(Box<dyn Fn(char) -> bool + 'static + Send + Sync>);
+/// A function that decides whether a character or a raw byte belongs to a character class.
+/// The characters of the class are held as sorted and disjoint ranges. The ASCII characters are
+/// additionally stored in a bitmap, so the common case needs no search.
+#[derive(Debug, Clone)]
+pub(crate) struct MatchFunction {
+    /// Bit `c` is set if the ASCII character `c` belongs to the class.
+    ascii: u128,
+    /// The characters of the class.
+    class: hir::ClassUnicode,
+    /// The raw bytes of the class, see [byte_ranges].
+    bytes: hir::ClassBytes,
+}
 
-impl MatchFn {
-    pub(crate) fn new<F>(f: F) -> Self
-    where
-        F: Fn(char) -> bool + 'static + Send + Sync,
-    {
-        MatchFn(Box::new(f))
+impl MatchFunction {
+    /// Create a new match function from the characters and the raw bytes of a class.
+    pub(crate) fn new(class: hir::ClassUnicode, bytes: hir::ClassBytes) -> Self {
+        let ascii = class
+            .iter()
+            .take_while(|r| r.start().is_ascii())
+            .fold(0, |ascii, r| {
+                let start = r.start() as u32;
+                let end = (r.end() as u32).min(0x7F);
+                ascii | (u128::MAX >> (127 - (end - start))) << start
+            });
+        MatchFunction {
+            ascii,
+            class,
+            bytes,
+        }
     }
 
-    /// Flatten the MatchFn to a function pointer for performance.
-    /// This is safe because MatchFn is a thin wrapper around a function pointer.
+    /// Call the match function with a character or a raw byte.
     #[inline]
-    pub(crate) fn inner(&self) -> &(dyn Fn(char) -> bool + 'static + Send + Sync) {
-        &*self.0
+    pub(crate) fn call(&self, unit: impl Into<Unit>) -> bool {
+        match unit.into() {
+            Unit::Char(c) if c.is_ascii() => self.ascii & (1 << c as u32) != 0,
+            Unit::Char(c) => contains(&self.class, c),
+            Unit::Byte(b) => self.bytes.iter().any(|r| r.start() <= b && b <= r.end()),
+        }
     }
-}
 
-/// A function that takes a character and returns a boolean.
-pub(crate) struct MatchFunction {
-    pub(crate) match_fn: MatchFn,
+    /// Returns the characters of the class as sorted and disjoint ranges.
+    #[inline]
+    pub(crate) fn class(&self) -> &hir::ClassUnicode {
+        &self.class
+    }
+
+    /// Returns the raw bytes of the class as sorted and disjoint ranges.
+    #[inline]
+    pub(crate) fn bytes(&self) -> &hir::ClassBytes {
+        &self.bytes
+    }
 }
 
-impl MatchFunction {
-    /// Create a new match function from a closure.
-    pub(crate) fn new<F>(f: F) -> Self
-    where
-        F: Fn(char) -> bool + 'static + Send + Sync,
-    {
-        MatchFunction {
-            match_fn: MatchFn::new(f),
-        }
+/// Returns the case variants of the given character.
+/// If Unicode is disabled only ASCII letters have case variants.
+fn case_variants(c: char, unicode: bool) -> Vec<char> {
+    if unicode {
+        let mut class = char_ranges([c]);
+        class.case_fold_simple();
+        class
+            .iter()
+            .flat_map(|r| r.start()..=r.end())
+            .filter(|v| *v != c)
+            .collect()
+    } else if c.is_ascii_alphabetic() {
+        vec![(c as u8 ^ 0x20) as char]
+    } else {
+        Vec::new()
     }
+}
 
-    /// Call the match function with a character.
-    #[inline]
-    pub(crate) fn call(&self, c: char) -> bool {
-        self.match_fn.inner()(c)
+/// Returns true if the literal is an escape like `\xFF` that denotes a byte. This is the case if
+/// Unicode is disabled and the escaped value is not ASCII.
+/// Such bytes are matched against the raw bytes of a byte haystack, i.e. the bytes that are not
+/// part of valid UTF-8, see [byte_ranges].
+fn is_byte_escape(l: &Literal, flags: Flags) -> bool {
+    !flags.unicode
+        && matches!(
+            l.kind,
+            ast::LiteralKind::Octal | ast::LiteralKind::HexFixed(_) | ast::LiteralKind::HexBrace(_)
+        )
+        && ('\u{80}'..='\u{FF}').contains(&l.c)
+}
+
+/// Unicode classes are resolved with the Unicode tables of `regex-syntax`. This way all general
+/// categories, scripts, script extensions and boolean properties behave exactly as in the `regex`
+/// crate.
+fn unicode_class(unicode: &ClassUnicode, flags: Flags) -> Result<hir::ClassUnicode> {
+    let class = Ast::ClassUnicode(Box::new(unicode.clone())).to_string();
+    if !flags.unicode {
+        return Err(unsupported!(format!(
+            "Unicode class {} in non-Unicode mode",
+            class
+        )));
+    }
+    // The class is parsed again from its string representation to get an AST whose spans match
+    // the pattern that is handed to the translator.
+    let ast = ast::parse::Parser::new().parse(&class)?;
+    let hir = hir::translate::TranslatorBuilder::new()
+        .unicode(true)
+        .case_insensitive(flags.case_insensitive)
+        .build()
+        .translate(&class, &ast)
+        .map_err(|e| unsupported!(format!("Unicode named class {}: {}", class, e.kind())))?;
+    match hir.into_kind() {
+        hir::HirKind::Class(hir::Class::Unicode(class)) => Ok(class),
+        _ => Err(unsupported!(format!("Unicode class {}", class))),
     }
 }
 
-impl TryFrom<&Literal> for MatchFn {
+impl TryFrom<(&Ast, Flags)> for MatchFunction {
     type Error = ScnrError;
 
-    #[inline(always)]
-    fn try_from(l: &Literal) -> Result<Self> {
-        let Literal {
-            ref c, ref kind, ..
-        } = *l;
-        let c = *c;
-        if c == '.' && *kind == regex_syntax::ast::LiteralKind::Verbatim {
-            Ok(MatchFn::new(|ch| ch != '\n' && ch != '\r'))
-        } else {
-            Ok(MatchFn::new(move |ch| ch == c))
-        }
+    fn try_from((ast, flags): (&Ast, Flags)) -> Result<Self> {
+        Ok(Self::new(
+            char_class_ranges(ast, flags)?,
+            byte_ranges(ast, flags),
+        ))
     }
 }
 
-impl TryFrom<&ClassSetUnion> for MatchFn {
+impl TryFrom<&Ast> for MatchFunction {
     type Error = ScnrError;
 
     #[inline(always)]
-    fn try_from(union: &ClassSetUnion) -> Result<Self> {
-        union
-            .items
-            .iter()
-            .try_fold(MatchFn::new(|_| false), |acc, s| {
-                (s, false)
-                    .try_into()
-                    .map(|f: MatchFn| MatchFn::new(move |ch| acc.inner()(ch) || f.inner()(ch)))
-            })
+    fn try_from(ast: &Ast) -> Result<Self> {
+        (ast, Flags::default()).try_into()
     }
 }
 
-impl TryFrom<&ClassBracketed> for MatchFn {
-    type Error = ScnrError;
+/// Returns the characters that are matched by a character class as a set of sorted and disjoint
+/// ranges. The ranges are the base of the [MatchFunction] of the class and they are used to
+/// partition the characters into the alphabet of a DFA. Byte escapes contribute no characters.
+pub(crate) fn char_class_ranges(ast: &Ast, flags: Flags) -> Result<hir::ClassUnicode> {
+    match ast {
+        Ast::Empty(_) => Ok(any_char()),
+        Ast::Dot(_) if flags.dot_matches_new_line => Ok(any_char()),
+        Ast::Dot(_) => Ok(any_char_except_newline()),
+        Ast::Literal(l) => literal_ranges(l, flags),
+        Ast::ClassUnicode(c) => unicode_class(c, flags),
+        Ast::ClassPerl(c) => perl_ranges(c, flags),
+        Ast::ClassBracketed(c) => bracketed_ranges(c, flags),
+        _ => Err(unsupported!(format!("{:#?}", ast))),
+    }
+}
 
-    #[inline(always)]
-    fn try_from(bracketed: &ClassBracketed) -> Result<Self> {
-        let negated = bracketed.negated;
-        match &bracketed.kind {
-            ClassSet::Item(item) => (item, negated).try_into(),
-            ClassSet::BinaryOp(bin_op) => (bin_op, negated).try_into(),
-        }
+/// Returns the raw bytes that are matched by a character class. Raw bytes are the bytes of a byte
+/// haystack that are not part of a valid UTF-8 sequence. They are distinct from all characters and
+/// only the byte escapes like `(?-u:\xFF)` match them. Negated classes never match raw bytes.
+fn byte_ranges(ast: &Ast, flags: Flags) -> hir::ClassBytes {
+    match ast {
+        Ast::Literal(l) => literal_bytes(l, flags),
+        Ast::ClassBracketed(c) if !c.negated => set_bytes(&c.kind, flags),
+        _ => hir::ClassBytes::empty(),
     }
 }
 
-impl TryFrom<&ClassUnicode> for MatchFn {
-    type Error = ScnrError;
+fn literal_bytes(l: &Literal, flags: Flags) -> hir::ClassBytes {
+    if is_byte_escape(l, flags) {
+        let b = l.c as u8;
+        hir::ClassBytes::new([hir::ClassBytesRange::new(b, b)])
+    } else {
+        hir::ClassBytes::empty()
+    }
+}
 
-    #[inline(always)]
-    fn try_from(unicode: &ClassUnicode) -> Result<Self> {
-        let kind = unicode.kind.clone();
-        let match_function = match kind {
-            OneLetter(ch) => {
-                match ch {
-                    // Unicode class for Letters
-                    'L' => MatchFn::new(|ch| ch.alpha()),
-                    // Unicode class for Numbers
-                    'N' => MatchFn::new(|ch| ch.is_numeric()),
-                    // Unicode class for Whitespace
-                    'Z' => MatchFn::new(|ch| ch.is_whitespace()),
-                    // Unicode class Terminal_Punctuation
-                    'P' => MatchFn::new(|ch| ch.term()),
-                    // Unicode class for Control characters
-                    'C' => MatchFn::new(|ch| ch.is_control()),
-                    _ => return Err(unsupported!(format!("Unicode class {}", ch))),
-                }
+fn set_bytes(set: &ClassSet, flags: Flags) -> hir::ClassBytes {
+    match set {
+        ClassSet::Item(item) => item_bytes(item, flags),
+        ClassSet::BinaryOp(ClassSetBinaryOp { kind, lhs, rhs, .. }) => {
+            let mut class = set_bytes(lhs, flags);
+            let rhs = set_bytes(rhs, flags);
+            match kind {
+                ClassSetBinaryOpKind::Intersection => class.intersect(&rhs),
+                ClassSetBinaryOpKind::Difference => class.difference(&rhs),
+                ClassSetBinaryOpKind::SymmetricDifference => class.symmetric_difference(&rhs),
             }
-            Named(name) => match name.as_str() {
-                "Alphabetic" => MatchFn::new(|ch| ch.alpha()),
-                "ASCII_Hex_Digit" => MatchFn::new(|ch| ch.ahex()),
-                "Bidi_Control" => MatchFn::new(|ch| ch.bidi_c()),
-                "Case_Ignorable" => MatchFn::new(|ch| ch.ci()),
-                "Cased" => MatchFn::new(|ch| ch.cased()),
-                "Composition_Exclusion" => MatchFn::new(|ch| ch.ce()),
-                "Dash" => MatchFn::new(|ch| ch.dash()),
-                "Default_Ignorable_Code_Point" => MatchFn::new(|ch| ch.di()),
-                "Deprecated" => MatchFn::new(|ch| ch.dep()),
-                "Diacritic" => MatchFn::new(|ch| ch.dia()),
-                "Emoji_Component" => MatchFn::new(|ch| ch.ecomp()),
-                "Emoji_Modifier_Base" => MatchFn::new(|ch| ch.ebase()),
-                "Emoji_Modifier" => MatchFn::new(|ch| ch.emod()),
-                "Emoji_Presentation" => MatchFn::new(|ch| ch.epres()),
-                "Emoji" => MatchFn::new(|ch| ch.emoji()),
-                "Extended_Pictographic" => MatchFn::new(|ch| ch.ext_pict()),
-                "Extender" => MatchFn::new(|ch| ch.ext()),
-                "Full_Composition_Exclusion" => MatchFn::new(|ch| ch.comp_ex()),
-                "Grapheme_Extend" => MatchFn::new(|ch| ch.gr_ext()),
-                "Hex_Digit" => MatchFn::new(|ch| ch.hex()),
-                "Hyphen" => MatchFn::new(|ch| ch.hyphen()),
-                "ID_Continue" => MatchFn::new(|ch| ch.idc()),
-                "ID_Start" => MatchFn::new(|ch| ch.ids()),
-                "Ideographic" => MatchFn::new(|ch| ch.ideo()),
-                "IDS_Binary_Operator" => MatchFn::new(|ch| ch.idsb()),
-                "IDS_Trinary_Operator" => MatchFn::new(|ch| ch.idst()),
-                "Join_Control" => MatchFn::new(|ch| ch.join_c()),
-                "Logical_Order_Exception" => MatchFn::new(|ch| ch.loe()),
-                "Lowercase" => MatchFn::new(|ch| ch.lower()),
-                "Math" => MatchFn::new(|ch| ch.math()),
-                "Noncharacter_Code_Point" => MatchFn::new(|ch| ch.nchar()),
-                "Other_Alphabetic" => MatchFn::new(|ch| ch.oalpha()),
-                "Other_Default_Ignorable_Code_Point" => MatchFn::new(|ch| ch.odi()),
-                "Other_Grapheme_Extend" => MatchFn::new(|ch| ch.ogr_ext()),
-                "Other_ID_Continue" => MatchFn::new(|ch| ch.oidc()),
-                "Other_ID_Start" => MatchFn::new(|ch| ch.oids()),
-                "Other_Lowercase" => MatchFn::new(|ch| ch.olower()),
-                "Other_Math" => MatchFn::new(|ch| ch.omath()),
-                "Other_Uppercase" => MatchFn::new(|ch| ch.oupper()),
-                "Pattern_Syntax" => MatchFn::new(|ch| ch.pat_syn()),
-                "Pattern_White_Space" => MatchFn::new(|ch| ch.pat_ws()),
-                "Prepended_Concatenation_Mark" => MatchFn::new(|ch| ch.pcm()),
-                "Quotation_Mark" => MatchFn::new(|ch| ch.qmark()),
-                "Radical" => MatchFn::new(|ch| ch.radical()),
-                "Regional_Indicator" => MatchFn::new(|ch| ch.ri()),
-                "Sentence_Terminal" => MatchFn::new(|ch| ch.sterm()),
-                "Soft_Dotted" => MatchFn::new(|ch| ch.sd()),
-                "Terminal_Punctuation" => MatchFn::new(|ch| ch.term()),
-                "Unified_Ideograph" => MatchFn::new(|ch| ch.uideo()),
-                "Uppercase" => MatchFn::new(|ch| ch.upper()),
-                "Variation_Selector" => MatchFn::new(|ch| ch.vs()),
-                "White_Space" => MatchFn::new(|ch| ch.wspace()),
-                "XID_Continue" => MatchFn::new(|ch| ch.xidc()),
-                "XID_Start" => MatchFn::new(|ch| ch.xids()),
-                _ => return Err(unsupported!(format!("Unicode named class {}", name))),
-            },
-            NamedValue { name, value, .. } => {
-                return Err(unsupported!(format!("Named value {}={}", name, value)));
-            }
-        };
-        Ok(if unicode.is_negated() {
-            MatchFn::new(move |ch| !match_function.inner()(ch))
-        } else {
-            match_function
-        })
+            class
+        }
     }
 }
 
-impl TryFrom<&ClassPerl> for MatchFn {
-    type Error = ScnrError;
-
-    #[inline(always)]
-    fn try_from(perl: &ClassPerl) -> Result<Self> {
-        let ClassPerl { negated, kind, .. } = perl;
-        let match_function = match kind {
-            ClassPerlKind::Digit => MatchFn::new(|ch| ch.is_numeric()),
-            ClassPerlKind::Space => MatchFn::new(|ch| ch.is_whitespace()),
-            ClassPerlKind::Word => MatchFn::new(|ch| {
-                ch.is_alphanumeric() || ch.join_c() || ch.gc() == Gc::Pc || ch.gc() == Gc::Mn
-            }),
-        };
-        Ok(if *negated {
-            MatchFn::new(move |ch| !match_function.inner()(ch))
-        } else {
-            match_function
-        })
+fn item_bytes(item: &ClassSetItem, flags: Flags) -> hir::ClassBytes {
+    match item {
+        ClassSetItem::Literal(l) => literal_bytes(l, flags),
+        ClassSetItem::Range(ClassSetRange { start, end, .. }) if is_byte_escape(end, flags) => {
+            // The ASCII part of the range matches characters, the rest matches raw bytes.
+            hir::ClassBytes::new([hir::ClassBytesRange::new(
+                start.c.max('\u{80}') as u8,
+                end.c as u8,
+            )])
+        }
+        ClassSetItem::Bracketed(c) if !c.negated => set_bytes(&c.kind, flags),
+        ClassSetItem::Union(union) => {
+            union
+                .items
+                .iter()
+                .fold(hir::ClassBytes::empty(), |mut acc, item| {
+                    acc.union(&item_bytes(item, flags));
+                    acc
+                })
+        }
+        _ => hir::ClassBytes::empty(),
     }
 }
 
-impl TryFrom<&ClassSet> for MatchFn {
-    type Error = ScnrError;
+/// All characters.
+fn any_char() -> hir::ClassUnicode {
+    hir::ClassUnicode::new([hir::ClassUnicodeRange::new('\0', char::MAX)])
+}
 
-    #[inline(always)]
-    fn try_from(set: &ClassSet) -> Result<Self> {
-        let negated = false;
-        match set {
-            ClassSet::Item(item) => (item, negated).try_into(),
-            ClassSet::BinaryOp(bin_op) => (bin_op, negated).try_into(),
+/// All characters except `\n` and `\r`.
+fn any_char_except_newline() -> hir::ClassUnicode {
+    negated(char_ranges(['\n', '\r']), true)
+}
+
+/// The set of the given characters.
+fn char_ranges(chars: impl IntoIterator<Item = char>) -> hir::ClassUnicode {
+    hir::ClassUnicode::new(chars.into_iter().map(|c| hir::ClassUnicodeRange::new(c, c)))
+}
+
+/// Collects the characters of the range for which the predicate holds.
+fn tabulate(predicate: impl Fn(char) -> bool, chars: RangeInclusive<char>) -> hir::ClassUnicode {
+    let mut ranges = Vec::new();
+    let mut current: Option<(char, char)> = None;
+    for c in chars {
+        if predicate(c) {
+            current = Some(current.map_or((c, c), |(start, _)| (start, c)));
+        } else if let Some((start, end)) = current.take() {
+            ranges.push(hir::ClassUnicodeRange::new(start, end));
         }
     }
+    if let Some((start, end)) = current {
+        ranges.push(hir::ClassUnicodeRange::new(start, end));
+    }
+    hir::ClassUnicode::new(ranges)
 }
 
-impl TryFrom<(&ClassSetItem, bool)> for MatchFn {
-    type Error = ScnrError;
+/// Collects the ASCII characters for which the predicate holds.
+fn tabulate_ascii(predicate: impl Fn(char) -> bool) -> hir::ClassUnicode {
+    tabulate(predicate, '\0'..='\x7F')
+}
 
-    #[inline(always)]
-    fn try_from((item, negated): (&ClassSetItem, bool)) -> Result<Self> {
-        let match_function = match item {
-            ClassSetItem::Empty(_) => MatchFn::new(|_| false),
-            ClassSetItem::Literal(ref l) => l.try_into()?,
-            ClassSetItem::Range(ref r) => {
-                let ClassSetRange {
-                    ref start, ref end, ..
-                } = *r;
-                let start = start.c;
-                let end = end.c;
-                MatchFn::new(move |ch| start <= ch && ch <= end)
-            }
-            ClassSetItem::Ascii(ref a) => {
-                let ClassAscii {
-                    ref kind, negated, ..
-                } = *a;
-                let match_function = match kind {
-                    ClassAsciiKind::Alnum => MatchFn::new(|ch| ch.is_alphanumeric()),
-                    ClassAsciiKind::Alpha => MatchFn::new(|ch| ch.is_alphabetic()),
-                    ClassAsciiKind::Ascii => MatchFn::new(|ch| ch.is_ascii()),
-                    ClassAsciiKind::Blank => MatchFn::new(|ch| ch.is_ascii_whitespace()),
-                    ClassAsciiKind::Cntrl => MatchFn::new(|ch| ch.is_ascii_control()),
-                    ClassAsciiKind::Digit => MatchFn::new(|ch| ch.is_numeric()),
-                    ClassAsciiKind::Graph => MatchFn::new(|ch| ch.is_ascii_graphic()),
-                    ClassAsciiKind::Lower => MatchFn::new(|ch| ch.is_lowercase()),
-                    ClassAsciiKind::Print => MatchFn::new(|ch| ch.is_ascii_graphic()),
-                    ClassAsciiKind::Punct => MatchFn::new(|ch| ch.is_ascii_punctuation()),
-                    ClassAsciiKind::Space => MatchFn::new(|ch| ch.is_whitespace()),
-                    ClassAsciiKind::Upper => MatchFn::new(|ch| ch.is_uppercase()),
-                    ClassAsciiKind::Word => MatchFn::new(|ch| {
-                        ch.is_alphanumeric()
-                            || ch.join_c()
-                            || ch.gc() == Gc::Pc
-                            || ch.gc() == Gc::Mn
-                    }),
-                    ClassAsciiKind::Xdigit => MatchFn::new(|ch| ch.is_ascii_hexdigit()),
-                };
-                if negated {
-                    MatchFn::new(move |ch| !match_function.inner()(ch))
-                } else {
-                    match_function
-                }
+/// The planes that contain characters with the properties of the Unicode character classes.
+/// The planes 4 to 13 are unassigned, the planes 15 and 16 hold private use characters only.
+const PROPERTY_PLANES: [RangeInclusive<char>; 2] = ['\0'..='\u{3FFFF}', '\u{E0000}'..='\u{E0FFF}'];
+
+/// Collects the characters for which the Unicode predicate holds.
+fn tabulate_unicode(predicate: impl Fn(char) -> bool) -> hir::ClassUnicode {
+    let mut class = hir::ClassUnicode::empty();
+    for plane in PROPERTY_PLANES {
+        class.union(&tabulate(&predicate, plane));
+    }
+    class
+}
+
+// The Unicode predicates of the character classes, tabulated once over the planes that contain
+// characters with these properties.
+static NUMERIC: LazyLock<hir::ClassUnicode> = LazyLock::new(|| tabulate_unicode(char::is_numeric));
+static WHITESPACE: LazyLock<hir::ClassUnicode> =
+    LazyLock::new(|| tabulate_unicode(char::is_whitespace));
+static ALPHABETIC: LazyLock<hir::ClassUnicode> =
+    LazyLock::new(|| tabulate_unicode(char::is_alphabetic));
+static ALPHANUMERIC: LazyLock<hir::ClassUnicode> =
+    LazyLock::new(|| tabulate_unicode(char::is_alphanumeric));
+static LOWERCASE: LazyLock<hir::ClassUnicode> =
+    LazyLock::new(|| tabulate_unicode(char::is_lowercase));
+static UPPERCASE: LazyLock<hir::ClassUnicode> =
+    LazyLock::new(|| tabulate_unicode(char::is_uppercase));
+static WORD: LazyLock<hir::ClassUnicode> = LazyLock::new(|| {
+    tabulate_unicode(|ch| {
+        ch.is_alphanumeric() || ch.join_c() || ch.gc() == Gc::Pc || ch.gc() == Gc::Mn
+    })
+});
+
+/// Checks if the character is contained in the set.
+fn contains(class: &hir::ClassUnicode, ch: char) -> bool {
+    class
+        .ranges()
+        .binary_search_by(|r| {
+            if r.end() < ch {
+                std::cmp::Ordering::Less
+            } else if r.start() > ch {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
             }
-            ClassSetItem::Unicode(ref c) => c.try_into()?,
-            ClassSetItem::Perl(ref c) => c.try_into()?,
-            ClassSetItem::Bracketed(ref c) => c.as_ref().try_into()?,
-            ClassSetItem::Union(ref c) => c.try_into()?,
-        };
-        Ok(if negated {
-            MatchFn::new(move |ch| !match_function.inner()(ch))
-        } else {
-            match_function
         })
+        .is_ok()
+}
+
+/// Extends the set by all characters that have a case variant in the set if the case insensitive
+/// flag is set.
+fn fold_case_ranges(mut class: hir::ClassUnicode, flags: Flags) -> hir::ClassUnicode {
+    if !flags.case_insensitive {
+        return class;
+    }
+    if flags.unicode {
+        // Simple case folding is symmetric, so the folded class contains exactly the characters
+        // that have a case variant in the class.
+        class.case_fold_simple();
+        return class;
+    }
+    let variants = char_ranges(
+        ('A'..='Z')
+            .chain('a'..='z')
+            .filter(|ch| contains(&class, (*ch as u8 ^ 0x20) as char)),
+    );
+    class.union(&variants);
+    class
+}
+
+/// Negates the set if `negated` is true.
+fn negated(mut class: hir::ClassUnicode, negated: bool) -> hir::ClassUnicode {
+    if negated {
+        class.negate();
     }
+    class
 }
 
-impl TryFrom<(&ClassSetBinaryOp, bool)> for MatchFn {
-    type Error = ScnrError;
+fn literal_ranges(l: &Literal, flags: Flags) -> Result<hir::ClassUnicode> {
+    if l.c == '.' && l.kind == ast::LiteralKind::Verbatim {
+        Ok(any_char_except_newline())
+    } else if is_byte_escape(l, flags) {
+        Ok(hir::ClassUnicode::empty())
+    } else if flags.case_insensitive {
+        let mut chars = case_variants(l.c, flags.unicode);
+        chars.push(l.c);
+        Ok(char_ranges(chars))
+    } else {
+        Ok(char_ranges([l.c]))
+    }
+}
 
-    #[inline(always)]
-    fn try_from((bin_op, negated): (&ClassSetBinaryOp, bool)) -> Result<Self> {
-        let ClassSetBinaryOp { kind, lhs, rhs, .. } = bin_op;
-        let lhs: MatchFn = lhs.as_ref().try_into()?;
-        let rhs: MatchFn = rhs.as_ref().try_into()?;
-        let match_function = match kind {
-            ClassSetBinaryOpKind::Intersection => {
-                MatchFn::new(move |ch| lhs.inner()(ch) && rhs.inner()(ch))
-            }
-            ClassSetBinaryOpKind::Difference => {
-                MatchFn::new(move |ch| lhs.inner()(ch) && !rhs.inner()(ch))
-            }
-            ClassSetBinaryOpKind::SymmetricDifference => {
-                MatchFn::new(move |ch| lhs.inner()(ch) != rhs.inner()(ch))
-            }
-        };
-        Ok(if negated {
-            MatchFn::new(move |ch| !match_function.inner()(ch))
-        } else {
-            match_function
-        })
+fn perl_ranges(perl: &ClassPerl, flags: Flags) -> Result<hir::ClassUnicode> {
+    let ClassPerl {
+        negated: neg, kind, ..
+    } = perl;
+    let class = match (kind, flags.unicode) {
+        (ClassPerlKind::Digit, true) => NUMERIC.clone(),
+        (ClassPerlKind::Space, true) => WHITESPACE.clone(),
+        (ClassPerlKind::Word, true) => WORD.clone(),
+        (ClassPerlKind::Digit, false) => tabulate_ascii(|ch| ch.is_ascii_digit()),
+        (ClassPerlKind::Space, false) => {
+            tabulate_ascii(|ch| ch.is_ascii_whitespace() || ch == '\x0B')
+        }
+        (ClassPerlKind::Word, false) => {
+            tabulate_ascii(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        }
+    };
+    Ok(negated(class, *neg))
+}
+
+fn bracketed_ranges(bracketed: &ClassBracketed, flags: Flags) -> Result<hir::ClassUnicode> {
+    match &bracketed.kind {
+        ClassSet::Item(item) => item_ranges(item, bracketed.negated, flags),
+        ClassSet::BinaryOp(bin_op) => bin_op_ranges(bin_op, bracketed.negated, flags),
     }
 }
 
-impl TryFrom<&Ast> for MatchFunction {
-    type Error = ScnrError;
+fn set_ranges(set: &ClassSet, flags: Flags) -> Result<hir::ClassUnicode> {
+    match set {
+        ClassSet::Item(item) => item_ranges(item, false, flags),
+        ClassSet::BinaryOp(bin_op) => bin_op_ranges(bin_op, false, flags),
+    }
+}
 
-    #[inline(always)]
-    fn try_from(ast: &Ast) -> Result<Self> {
-        let match_function = match ast {
-            Ast::Empty(_) => {
-                // An empty AST matches everything.
-                Self::new(|_| true)
-            }
-            Ast::Dot(_) => {
-                // A dot AST matches any character except newline.
-                Self::new(|ch| ch != '\n' && ch != '\r')
-            }
-            Ast::Literal(ref l) => {
-                // A literal AST matches a single character.
-                Self {
-                    match_fn: l.as_ref().try_into()?,
+fn item_ranges(item: &ClassSetItem, neg: bool, flags: Flags) -> Result<hir::ClassUnicode> {
+    let class = match item {
+        ClassSetItem::Empty(_) => hir::ClassUnicode::empty(),
+        ClassSetItem::Literal(l) => literal_ranges(l, flags)?,
+        ClassSetItem::Range(r) => {
+            let ClassSetRange { start, end, .. } = r;
+            let class = if is_byte_escape(end, flags) {
+                // The ASCII part of the range matches characters, the rest matches raw bytes.
+                if start.c < '\u{80}' {
+                    hir::ClassUnicode::new([hir::ClassUnicodeRange::new(start.c, '\x7F')])
+                } else {
+                    hir::ClassUnicode::empty()
                 }
-            }
-            Ast::ClassUnicode(ref c) => Self {
-                match_fn: c.as_ref().try_into()?,
-            },
-            Ast::ClassPerl(ref c) => Self {
-                match_fn: c.as_ref().try_into()?,
-            },
-            Ast::ClassBracketed(ref c) => Self {
-                match_fn: c.as_ref().try_into()?,
-            },
-            _ => return Err(unsupported!(format!("{:#?}", ast))),
-        };
-        Ok(match_function)
-    }
+            } else if start.c <= end.c {
+                hir::ClassUnicode::new([hir::ClassUnicodeRange::new(start.c, end.c)])
+            } else {
+                hir::ClassUnicode::empty()
+            };
+            fold_case_ranges(class, flags)
+        }
+        ClassSetItem::Ascii(a) => {
+            let ClassAscii {
+                kind, negated: neg, ..
+            } = a;
+            let class = match kind {
+                ClassAsciiKind::Alnum => ALPHANUMERIC.clone(),
+                ClassAsciiKind::Alpha => ALPHABETIC.clone(),
+                ClassAsciiKind::Ascii => tabulate_ascii(|ch| ch.is_ascii()),
+                ClassAsciiKind::Blank => tabulate_ascii(|ch| ch.is_ascii_whitespace()),
+                ClassAsciiKind::Cntrl => tabulate_ascii(|ch| ch.is_ascii_control()),
+                ClassAsciiKind::Digit => NUMERIC.clone(),
+                ClassAsciiKind::Graph => tabulate_ascii(|ch| ch.is_ascii_graphic()),
+                ClassAsciiKind::Lower => LOWERCASE.clone(),
+                ClassAsciiKind::Print => tabulate_ascii(|ch| ch.is_ascii_graphic()),
+                ClassAsciiKind::Punct => tabulate_ascii(|ch| ch.is_ascii_punctuation()),
+                ClassAsciiKind::Space => WHITESPACE.clone(),
+                ClassAsciiKind::Upper => UPPERCASE.clone(),
+                ClassAsciiKind::Word => WORD.clone(),
+                ClassAsciiKind::Xdigit => tabulate_ascii(|ch| ch.is_ascii_hexdigit()),
+            };
+            negated(fold_case_ranges(class, flags), *neg)
+        }
+        ClassSetItem::Unicode(c) => unicode_class(c, flags)?,
+        ClassSetItem::Perl(c) => perl_ranges(c, flags)?,
+        ClassSetItem::Bracketed(c) => bracketed_ranges(c, flags)?,
+        ClassSetItem::Union(union) => {
+            union
+                .items
+                .iter()
+                .try_fold(hir::ClassUnicode::empty(), |mut acc, item| {
+                    acc.union(&item_ranges(item, false, flags)?);
+                    Ok::<_, ScnrError>(acc)
+                })?
+        }
+    };
+    Ok(negated(class, neg))
 }
 
-impl std::fmt::Debug for MatchFunction {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "MatchFunction")
+fn bin_op_ranges(bin_op: &ClassSetBinaryOp, neg: bool, flags: Flags) -> Result<hir::ClassUnicode> {
+    let ClassSetBinaryOp { kind, lhs, rhs, .. } = bin_op;
+    let mut class = set_ranges(lhs, flags)?;
+    let rhs = set_ranges(rhs, flags)?;
+    match kind {
+        ClassSetBinaryOpKind::Intersection => class.intersect(&rhs),
+        ClassSetBinaryOpKind::Difference => class.difference(&rhs),
+        ClassSetBinaryOpKind::SymmetricDifference => class.symmetric_difference(&rhs),
     }
+    Ok(negated(class, neg))
 }
 
 #[cfg(test)]
@@ -564,6 +663,61 @@ mod tests {
         assert!(!match_function.call(' '));
     }
 
+    #[test]
+    fn test_unicode_properties_like_regex_crate() {
+        for class in [
+            r"\pL",
+            r"\p{Lu}",
+            r"\pM",
+            r"\pN",
+            r"\pP",
+            r"\pS",
+            r"\pZ",
+            r"\pC",
+            r"\p{Greek}",
+            r"\p{sc=Han}",
+            r"\p{scx=Hira}",
+            r"\p{Script_Extensions=Greek}",
+            r"\P{Greek}",
+            r"\P{Lu}",
+            r"\p{gc!=Nd}",
+            r"\p{Emoji}",
+            r"[\p{Greek}&&\pL]",
+        ] {
+            for case_insensitive in [false, true] {
+                let ast = Parser::new().parse(class).unwrap();
+                let flags = Flags {
+                    case_insensitive,
+                    ..Default::default()
+                };
+                let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+                let regex = regex::RegexBuilder::new(&format!("^{}$", class))
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .unwrap();
+                for ch in (0..=char::MAX as u32)
+                    .step_by(11)
+                    .filter_map(char::from_u32)
+                {
+                    assert_eq!(
+                        match_function.call(ch),
+                        regex.is_match(ch.encode_utf8(&mut [0; 4])),
+                        "{} {:?}",
+                        class,
+                        ch
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_unicode_property() {
+        let ast = Parser::new().parse(r"\p{Foo}").unwrap();
+        let err = MatchFunction::try_from(&ast).unwrap_err();
+        assert!(err.to_string().contains("named class"));
+    }
+
     #[test]
     fn test_evaluate_general_category() {
         assert_eq!('_'.gc(), Gc::Pc);
@@ -571,4 +725,236 @@ mod tests {
         let match_function = MatchFunction::try_from(&ast).unwrap();
         assert!(match_function.call('_'));
     }
+
+    #[test]
+    fn test_case_insensitive_literal() {
+        let flags = Flags {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        let ast = Parser::new().parse(r"k").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('k'));
+        assert!(match_function.call('K'));
+        // KELVIN SIGN
+        assert!(match_function.call('\u{212A}'));
+        assert!(!match_function.call('l'));
+
+        let flags = Flags {
+            unicode: false,
+            ..flags
+        };
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('k'));
+        assert!(match_function.call('K'));
+        assert!(!match_function.call('\u{212A}'));
+    }
+
+    #[test]
+    fn test_case_insensitive_classes() {
+        let flags = Flags {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        let ast = Parser::new().parse(r"[a-c]").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('B'));
+        assert!(!match_function.call('D'));
+
+        // Case folding is applied before negation
+        let ast = Parser::new().parse(r"[^a-c]").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(!match_function.call('b'));
+        assert!(!match_function.call('B'));
+        assert!(match_function.call('D'));
+
+        let ast = Parser::new().parse(r"[a-z&&[^x]]").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('Y'));
+        assert!(!match_function.call('X'));
+    }
+
+    #[test]
+    fn test_non_unicode_classes() {
+        let flags = Flags {
+            unicode: false,
+            ..Default::default()
+        };
+        let ast = Parser::new().parse(r"\w").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('_'));
+        assert!(match_function.call('a'));
+        assert!(!match_function.call('ä'));
+
+        let ast = Parser::new().parse(r"\d").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('1'));
+        assert!(!match_function.call('١'));
+
+        let ast = Parser::new().parse(r"\pL").unwrap();
+        assert!(MatchFunction::try_from((&ast, flags)).is_err());
+    }
+
+    #[test]
+    fn test_dot_matches_new_line() {
+        let flags = Flags {
+            dot_matches_new_line: true,
+            ..Default::default()
+        };
+        let ast = Parser::new().parse(r".").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('\n'));
+        let match_function = MatchFunction::try_from(&ast).unwrap();
+        assert!(!match_function.call('\n'));
+    }
+
+    #[test]
+    fn test_match_function_byte_escapes() {
+        let flags = Flags {
+            unicode: false,
+            ..Default::default()
+        };
+        let ast = Parser::new().parse(r"[a-\xFF]").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call('a'));
+        assert!(match_function.call('\u{7F}'));
+        assert!(match_function.call(Unit::Byte(0x80)));
+        assert!(match_function.call(Unit::Byte(0xFF)));
+        assert!(!match_function.call('A'));
+        // The escapes denote bytes, not the characters U+0080 to U+00FF, and the raw bytes are no
+        // characters at all.
+        assert!(!match_function.call('\u{FF}'));
+        assert!(!match_function.call('\u{10FFFF}'));
+
+        let ast = Parser::new().parse(r"\xE9").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(match_function.call(Unit::Byte(0xE9)));
+        assert!(!match_function.call('é'));
+        // With Unicode enabled the escape denotes the character.
+        let match_function = MatchFunction::try_from(&ast).unwrap();
+        assert!(match_function.call('é'));
+        assert!(!match_function.call(Unit::Byte(0xE9)));
+
+        // Negated classes, the dot and Unicode classes don't match raw bytes.
+        for pattern in [r"[^a]", r".", r"\p{Co}", r"\W"] {
+            let ast = Parser::new().parse(pattern).unwrap();
+            let match_function = MatchFunction::try_from(&ast).unwrap();
+            assert!(!match_function.call(Unit::Byte(0xFF)), "{}", pattern);
+        }
+        let ast = Parser::new().parse(r"[^\x80]").unwrap();
+        let match_function = MatchFunction::try_from((&ast, flags)).unwrap();
+        assert!(!match_function.call(Unit::Byte(0xFF)));
+    }
+
+    /// The ranges of a character class must contain exactly the characters that are accepted by
+    /// its match function.
+    #[test]
+    fn test_match_functions_agree_with_predicates() {
+        let default = Flags::default();
+        let case_insensitive = Flags {
+            case_insensitive: true,
+            ..default
+        };
+        let ascii = Flags {
+            unicode: false,
+            ..default
+        };
+        let ascii_case_insensitive = Flags {
+            case_insensitive: true,
+            ..ascii
+        };
+        let dot_all = Flags {
+            dot_matches_new_line: true,
+            ..default
+        };
+        let word = |ch: char| {
+            ch.is_alphanumeric() || ch.join_c() || ch.gc() == Gc::Pc || ch.gc() == Gc::Mn
+        };
+        type Predicate<'a> = &'a dyn Fn(char) -> bool;
+        let cases: &[(&str, Flags, Predicate)] = &[
+            (r"a", default, &|ch| ch == 'a'),
+            (r"k", case_insensitive, &|ch| {
+                matches!(ch, 'k' | 'K' | '\u{212A}')
+            }),
+            (r"ß", case_insensitive, &|ch| {
+                matches!(ch, 'ß' | '\u{1E9E}')
+            }),
+            (r"k", ascii_case_insensitive, &|ch| matches!(ch, 'k' | 'K')),
+            (r".", default, &|ch| ch != '\n' && ch != '\r'),
+            (r".", dot_all, &|_| true),
+            (r"\d", default, &char::is_numeric),
+            (r"\D", default, &|ch| !ch.is_numeric()),
+            (r"\s", default, &char::is_whitespace),
+            (r"\w", default, &word),
+            (r"[[:alpha:]]", default, &char::is_alphabetic),
+            (r"[[:alnum:]]", default, &char::is_alphanumeric),
+            (r"[[:lower:]]", default, &char::is_lowercase),
+            (r"[[:upper:]]", default, &char::is_uppercase),
+            (r"\W", default, &|ch| !word(ch)),
+            (r"\d", ascii, &|ch| ch.is_ascii_digit()),
+            (r"\s", ascii, &|ch| ch.is_ascii_whitespace() || ch == '\x0B'),
+            (r"\w", ascii, &|ch| ch.is_ascii_alphanumeric() || ch == '_'),
+            (r"\PN", default, &|ch| {
+                !matches!(ch.gc(), Gc::Nd | Gc::Nl | Gc::No)
+            }),
+            (r"[a-zA-Z_]", default, &|ch| {
+                ch.is_ascii_alphabetic() || ch == '_'
+            }),
+            (r"[^\u{0022}\\\u0000-\u001F]", default, &|ch| {
+                ch != '"' && ch != '\\' && ch > '\u{1F}'
+            }),
+            (r"[a-f]", case_insensitive, &|ch| {
+                ('a'..='f').contains(&ch.to_ascii_lowercase())
+            }),
+            (r"[^a-z]", case_insensitive, &|ch| {
+                !ch.is_ascii_alphabetic() && ch != '\u{17F}' && ch != '\u{212A}'
+            }),
+            (r"[[:alpha:][:digit:]]", default, &|ch| {
+                ch.is_alphabetic() || ch.is_numeric()
+            }),
+            (r"[[:^space:]]", default, &|ch| !ch.is_whitespace()),
+            (r"[[:word:][:punct:]]", default, &|ch| {
+                word(ch) || ch.is_ascii_punctuation()
+            }),
+            (r"[a-y&&xyz]", default, &|ch| ch == 'x' || ch == 'y'),
+            (r"[a-z--aeiou]", default, &|ch| {
+                ch.is_ascii_lowercase() && !"aeiou".contains(ch)
+            }),
+            (r"[\w~~\d]", default, &|ch| word(ch) != ch.is_numeric()),
+            (r"[a-\xFF]", ascii, &|ch| ('a'..='\x7F').contains(&ch)),
+            (r"(?-u:\xE9)", ascii, &|_| false),
+        ];
+        let chars = ('\0'..='\u{3000}').chain(('\u{3001}'..=char::MAX).step_by(97));
+        for (pattern, flags, predicate) in cases {
+            let ast = Parser::new().parse(pattern).unwrap();
+            // Inline flags are handled by the NFA, here only the class itself is of interest.
+            let ast = match &ast {
+                Ast::Group(group) => group.ast.as_ref().clone(),
+                ast => ast.clone(),
+            };
+            let match_function = MatchFunction::try_from((&ast, *flags)).unwrap();
+            for c in chars.clone() {
+                assert_eq!(
+                    predicate(c),
+                    match_function.call(c),
+                    "Pattern '{}' with {:?}, character {:?}",
+                    pattern,
+                    flags,
+                    c
+                );
+            }
+            // Only byte escapes match raw bytes.
+            let byte_escapes = pattern.contains(r"\x");
+            for b in 0x80..=0xFF {
+                assert_eq!(
+                    byte_escapes && (b == 0xE9 || pattern.starts_with('[')),
+                    match_function.call(Unit::Byte(b)),
+                    "Pattern '{}' with {:?}, raw byte {:#X}",
+                    pattern,
+                    flags,
+                    b
+                );
+            }
+        }
+    }
 }
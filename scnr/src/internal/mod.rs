@@ -1,13 +1,23 @@
+/// Module that provides the alphabet of a DFA, i.e. the disjoint sets of characters.
+#[cfg(not(feature = "regex_automata"))]
+mod alphabet;
+#[cfg(not(feature = "regex_automata"))]
+pub(crate) use alphabet::Alphabet;
+
 /// Module that provides functions and types related to character classes.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 mod character_class;
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) use character_class::CharacterClass;
 
 /// Module that provides the type CharacterClassRegistry.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 mod character_class_registry;
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) use character_class_registry::CharacterClassRegistry;
 
 /// Module that provides functions and types related to compiled Lookahead.
@@ -17,6 +27,12 @@ pub(crate) mod compiled_lookahead;
 #[cfg(not(feature = "regex_automata"))]
 pub(crate) use compiled_lookahead::CompiledLookahead;
 
+/// Module that provides functions and types related to compiled Lookbehind.
+#[cfg(not(feature = "regex_automata"))]
+pub(crate) mod compiled_lookbehind;
+#[cfg(not(feature = "regex_automata"))]
+pub(crate) use compiled_lookbehind::CompiledLookbehind;
+
 /// Module that provides functions and types related to compiled NFA.
 #[cfg(not(feature = "regex_automata"))]
 pub(crate) mod compiled_dfa;
@@ -28,10 +44,17 @@ pub(crate) mod compiled_scanner_mode;
 
 /// Module that provides functions and types related to comparable ASTs.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) mod comparable_ast;
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) use comparable_ast::ComparableAst;
 
+/// Module that provides the subset construction of the compiled DFA.
+#[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
+mod determinizer;
+
 /// Module with conversion to graphviz dot format
 #[cfg(feature = "dot_writer")]
 pub(crate) mod dot;
@@ -39,44 +62,93 @@ pub(crate) mod dot;
 /// Module that provides functions and types related to the `find_matches` function.
 pub(crate) mod find_matches_impl;
 
+/// Module that provides the type Flags for inline regex flags.
+#[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
+mod flags;
+#[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
+pub(crate) use flags::Flags;
+
+/// Module that provides the haystack types for string and byte inputs.
+mod haystack;
+#[cfg(not(feature = "regex_automata"))]
+pub(crate) use haystack::Unit;
+pub(crate) use haystack::{CharIndices, Haystack};
+
 /// Module for several ID types.
 mod ids;
-pub(crate) use ids::{ScannerModeID, TerminalID, TerminalIDBase};
+pub(crate) use ids::{TerminalID, TerminalIDBase};
+
+#[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
+pub(crate) use ids::{CharClassID, StateID};
+#[cfg(not(feature = "regex_automata"))]
+pub(crate) use ids::{StateIDBase, StateSetID, SymbolID};
 
+/// Module that provides the type Look for zero-width assertions.
+#[cfg(not(feature = "regex_automata"))]
+mod look;
+#[cfg(not(feature = "regex_automata"))]
+pub(crate) use look::{look_context, LOOK_CONTEXTS};
 #[cfg(not(feature = "regex_automata"))]
-pub(crate) use ids::{CharClassID, StateID, StateIDBase};
+#[cfg(feature = "compiler")]
+pub(crate) use look::{Look, LOOK_CONTEXT_CHARS};
+
+/// Module with the table of the Unicode word characters.
+#[cfg(not(feature = "regex_automata"))]
+mod perl_word;
 
 /// Module that provides functions and types related to match functions.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) mod match_function;
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) use match_function::MatchFunction;
 
 /// Module that provides functions and types related to DFA minimization.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) mod minimizer;
 
+/// Module that provides the persistent stack of scanner modes.
+mod mode_stack;
+pub(crate) use mode_stack::ModeStack;
+
 /// Module that provides functions and types related to the multi pattern NFA.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) mod multi_pattern_nfa;
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) use multi_pattern_nfa::MultiPatternNfa;
 
 /// The nfa module contains the NFA implementation.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 mod nfa;
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) use nfa::Nfa;
 
 /// The parser module contains the regex syntax parser.
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 mod parser;
 #[cfg(not(feature = "regex_automata"))]
+#[cfg(feature = "compiler")]
 pub(crate) use parser::parse_regex_syntax;
 
+#[cfg(feature = "compiler")]
 mod scanner_cache;
+#[cfg(feature = "compiler")]
 pub(crate) use scanner_cache::SCANNER_CACHE;
 
+/// Module that provides the versioned serialization of compiled scanners.
+#[cfg(feature = "serde")]
+pub(crate) mod serialization;
+
 /// Module that provides functions and types related to NFA scanner implementations.
 #[cfg(not(feature = "regex_automata"))]
 pub(crate) mod scanner_impl;
@@ -87,3 +159,7 @@ pub(crate) use scanner_impl::ScannerImpl;
 pub(crate) mod scanner_impl_rx;
 #[cfg(feature = "regex_automata")]
 pub(crate) use scanner_impl_rx::ScannerImpl;
+
+/// Module that provides the registry of the token names.
+mod token_names;
+pub(crate) use token_names::TokenNames;
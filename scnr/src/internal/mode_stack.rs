@@ -0,0 +1,95 @@
+//! Module with the stack of scanner modes of push and pop transitions.
+
+use std::sync::Arc;
+
+/// The scanner modes that were active before the push transitions that are not yet popped.
+///
+/// The stack is persistent: its entries are shared and never changed, so a clone is a pointer
+/// copy. This makes checkpoints of the scanner state cheap, independent of the depth of the stack.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct ModeStack {
+    top: Option<Arc<Entry>>,
+}
+
+/// An entry of the mode stack with the entries below it.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Entry {
+    mode: usize,
+    // The number of entries up to and including this one.
+    depth: usize,
+    below: ModeStack,
+}
+
+impl ModeStack {
+    /// Pushes the scanner mode onto the stack.
+    pub(crate) fn push(&mut self, mode: usize) {
+        let below = std::mem::take(self);
+        self.top = Some(Arc::new(Entry {
+            mode,
+            depth: below.len() + 1,
+            below,
+        }));
+    }
+
+    /// Removes the scanner mode on top of the stack and returns it.
+    pub(crate) fn pop(&mut self) -> Option<usize> {
+        let top = self.top.take()?;
+        *self = top.below.clone();
+        Some(top.mode)
+    }
+
+    /// Returns the scanner mode on top of the stack.
+    pub(crate) fn last(&self) -> Option<usize> {
+        self.top.as_ref().map(|top| top.mode)
+    }
+
+    /// Returns the number of scanner modes on the stack.
+    pub(crate) fn len(&self) -> usize {
+        self.top.as_ref().map_or(0, |top| top.depth)
+    }
+
+    /// Returns the scanner modes from the bottom to the top of the stack.
+    #[cfg(not(feature = "regex_automata"))]
+    pub(crate) fn to_vec(&self) -> Vec<usize> {
+        let mut modes = Vec::with_capacity(self.len());
+        let mut current = self;
+        while let Some(top) = &current.top {
+            modes.push(top.mode);
+            current = &top.below;
+        }
+        modes.reverse();
+        modes
+    }
+}
+
+impl From<&[usize]> for ModeStack {
+    /// Creates the stack from the scanner modes from the bottom to the top.
+    fn from(modes: &[usize]) -> Self {
+        let mut mode_stack = Self::default();
+        modes.iter().for_each(|mode| mode_stack.push(*mode));
+        mode_stack
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_stack() {
+        let mut mode_stack = ModeStack::from([1, 2].as_slice());
+        let saved = mode_stack.clone();
+        mode_stack.push(3);
+        assert_eq!(mode_stack.len(), 3);
+        assert_eq!(mode_stack.last(), Some(3));
+        assert_eq!(mode_stack.pop(), Some(3));
+        assert_eq!(mode_stack, saved);
+        assert_eq!(mode_stack.pop(), Some(2));
+        assert_eq!(mode_stack.last(), Some(1));
+        // The saved stack is not changed by the operations on the clone.
+        assert_eq!(saved, ModeStack::from([1, 2].as_slice()));
+        assert_eq!(mode_stack.pop(), Some(1));
+        assert_eq!(mode_stack.pop(), None);
+        assert_eq!(mode_stack, ModeStack::default());
+    }
+}
@@ -4,7 +4,7 @@
 //! multi-pattern NFA has one end state for each pattern.
 
 use super::{nfa::EpsilonTransition, CharClassID, Nfa, StateID};
-use crate::{Pattern, Result, ScnrError, ScnrErrorKind};
+use crate::{CompileLimits, Pattern, Result, ScnrError, ScnrErrorKind};
 
 macro_rules! unsupported {
     ($feature:expr) => {
@@ -39,12 +39,13 @@ impl MultiPatternNfa {
     pub(crate) fn try_from_patterns(
         patterns: &[Pattern],
         character_class_registry: &mut super::CharacterClassRegistry,
+        limits: &CompileLimits,
     ) -> Result<Self> {
         let mut multi_pattern_nfa = Self::new();
         let mut next_state = 1;
         for (index, pattern) in patterns.iter().enumerate() {
             let ast = super::parse_regex_syntax(pattern.pattern())?;
-            let result = Nfa::try_from_ast(ast, character_class_registry);
+            let result = Nfa::try_from_ast_with_limits(ast, character_class_registry, limits);
             match result {
                 Err(ScnrError { ref source }) => match source.as_ref() {
                     ScnrErrorKind::RegexSyntaxError(r, _) => {
@@ -57,15 +58,19 @@ impl MultiPatternNfa {
                         "Error in pattern #{} '{}': {}",
                         index, pattern, s
                     )))?,
-                    ScnrErrorKind::IoError(_) | ScnrErrorKind::EmptyToken => {
-                        Err(result.unwrap_err())?
+                    ScnrErrorKind::CompileLimitExceeded { .. } => {
+                        Err(result.unwrap_err().with_pattern(pattern.pattern()))?
                     }
+                    _ => Err(result.unwrap_err())?,
                 },
                 Ok(mut nfa) => {
                     nfa.set_terminal_id(pattern.terminal_id());
                     let (s, _) = nfa.shift_ids(next_state);
 
                     next_state = nfa.highest_state_number() as usize + 1;
+                    limits
+                        .check_nfa_states(next_state)
+                        .map_err(|e| e.with_pattern(pattern.pattern()))?;
 
                     multi_pattern_nfa
                         .start_transitions
@@ -93,6 +98,8 @@ impl MultiPatternNfa {
     }
 
     /// Checks if the given state is an accepting state of one of the NFAs.
+    /// It is used for debugging purposes in the [crate::internal::dot] module.
+    #[allow(dead_code)]
     pub(crate) fn is_accepting_state(&self, state: StateID) -> bool {
         self.nfas.iter().any(|nfa| nfa.end_state() == state)
     }
@@ -193,48 +200,6 @@ impl MultiPatternNfa {
         result.sort_unstable();
         result
     }
-
-    pub(crate) fn get_match_transitions(
-        &self,
-        start_states: impl Iterator<Item = StateID>,
-    ) -> Vec<(CharClassID, StateID)> {
-        let mut target_states = Vec::new();
-        for state in start_states {
-            if state.id() == 0 {
-                for transition in &self.start_transitions {
-                    if let Some(nfa) = self.find_nfa(transition.target_state()) {
-                        if let Some(state) = nfa.find_state(transition.target_state()) {
-                            for state in state.transitions() {
-                                target_states.push((state.char_class(), state.target_state()));
-                            }
-                        } else {
-                            panic!("State {} not found", transition.target_state());
-                        }
-                    } else {
-                        panic!("NFA for target state not found");
-                    };
-                }
-            } else if let Some(nfa) = self.find_nfa(state) {
-                if let Some(state) = nfa.find_state(state) {
-                    for state in state.transitions() {
-                        target_states.push((state.char_class(), state.target_state()));
-                    }
-                } else {
-                    panic!("State {} not found", state);
-                }
-            }
-        }
-        // Sort and dedup the target states by target state.
-        // Constraint is neseccary be able to hold the priority of the patterns.
-        target_states.sort_by_key(|t| t.1);
-        target_states.dedup();
-        target_states
-    }
-
-    /// Find the NFA that contains the state and return the state.
-    pub(crate) fn find_nfa(&self, state: StateID) -> Option<&Nfa> {
-        self.nfas.iter().find(|nfa| nfa.contains_state(state))
-    }
 }
 
 #[cfg(test)]
@@ -346,6 +311,7 @@ mod tests {
         let multi_pattern_nfa = MultiPatternNfa::try_from_patterns(
             &[Pattern::new("a+".to_string(), 0)],
             &mut character_class_registry,
+            &CompileLimits::default(),
         )
         .unwrap();
         assert_eq!(multi_pattern_nfa.patterns().len(), 1);
@@ -361,6 +327,7 @@ mod tests {
         let multi_pattern_nfa = MultiPatternNfa::try_from_patterns(
             &SCANNER_MODES.iter().next().unwrap().patterns,
             &mut character_class_registry,
+            &CompileLimits::default(),
         )
         .unwrap();
         assert_eq!(multi_pattern_nfa.patterns().len(), 115);
@@ -3,19 +3,13 @@
 
 use std::vec;
 
-use regex_syntax::ast::{Ast, FlagsItemKind, GroupKind, RepetitionKind, RepetitionRange};
+use regex_syntax::ast::{Ast, GroupKind, RepetitionKind, RepetitionRange};
 
-use crate::{Pattern, Result, ScnrError};
+use crate::{CompileLimits, Pattern, Result};
 
-use super::{ids::StateIDBase, CharClassID, CharacterClassRegistry, ComparableAst, StateID};
-
-macro_rules! unsupported {
-    ($feature:expr) => {
-        ScnrError::new($crate::ScnrErrorKind::UnsupportedFeature(
-            $feature.to_string(),
-        ))
-    };
-}
+use super::{
+    ids::StateIDBase, CharClassID, CharacterClassRegistry, ComparableAst, Flags, Look, StateID,
+};
 
 #[derive(Debug, Clone, Default)]
 pub(crate) struct Nfa {
@@ -26,6 +20,10 @@ pub(crate) struct Nfa {
     pub(crate) start_state: StateID,
     // Used during NFA construction
     pub(crate) end_state: StateID,
+    /// True if the NFA contains a non-greedy repetition.
+    /// Such a NFA follows the priorities of its epsilon transitions, i.e. a match is only extended
+    /// by paths that are preferred over the path of the match, like in backtracking regex engines.
+    pub(crate) non_greedy: bool,
 }
 
 impl Nfa {
@@ -35,6 +33,7 @@ impl Nfa {
             states: vec![NfaState::default()],
             start_state: StateID::default(),
             end_state: StateID::default(),
+            non_greedy: false,
         }
     }
 
@@ -70,10 +69,6 @@ impl Nfa {
         self.pattern = Pattern::new(pattern.to_string(), self.pattern.terminal_id());
     }
 
-    pub(crate) fn terminal_id(&self) -> usize {
-        self.pattern.terminal_id()
-    }
-
     pub(crate) fn set_terminal_id(&mut self, terminal_id: usize) {
         self.pattern.set_token_type(terminal_id);
     }
@@ -94,27 +89,34 @@ impl Nfa {
         &mut self,
         from: StateID,
         chars: Ast,
+        flags: Flags,
         target_state: StateID,
         char_class_registry: &mut CharacterClassRegistry,
-    ) {
-        let char_class = char_class_registry.add_character_class(&chars);
+    ) -> Result<()> {
+        let char_class = char_class_registry.add_character_class(&chars, flags)?;
         self.states[from].transitions.push(NfaTransition {
             ast: ComparableAst(chars),
             char_class,
             target_state,
         });
-        debug_assert!(
-            self.states[from].epsilon_transitions.len() + self.states[from].transitions.len() <= 2
-        );
+        debug_assert!(self.states[from].number_of_transitions() <= 2);
+        Ok(())
     }
 
     pub(crate) fn add_epsilon_transition(&mut self, from: StateID, target_state: StateID) {
         self.states[from]
             .epsilon_transitions
             .push(EpsilonTransition { target_state });
-        debug_assert!(
-            self.states[from].epsilon_transitions.len() + self.states[from].transitions.len() <= 2
-        );
+        debug_assert!(self.states[from].number_of_transitions() <= 2);
+    }
+
+    /// Adds a transition that doesn't consume a character but is only taken if the zero-width
+    /// assertion holds at the current position.
+    pub(crate) fn add_look_transition(&mut self, from: StateID, look: Look, target_state: StateID) {
+        self.states[from]
+            .look_transitions
+            .push(LookTransition { look, target_state });
+        debug_assert!(self.states[from].number_of_transitions() <= 2);
     }
 
     pub(crate) fn new_state(&mut self) -> StateID {
@@ -145,6 +147,7 @@ impl Nfa {
 
     /// Concatenates the current NFA with another NFA.
     pub(crate) fn concat(&mut self, mut nfa: Nfa) {
+        self.non_greedy |= nfa.non_greedy;
         if self.is_empty() {
             // If the current NFA is empty, set the start and end states of the current NFA to the
             // start and end states of the new NFA
@@ -167,6 +170,7 @@ impl Nfa {
     }
 
     pub(crate) fn alternation(&mut self, mut nfa: Nfa) {
+        self.non_greedy |= nfa.non_greedy;
         if self.is_empty() {
             // If the current NFA is empty, set the start and end states of the current NFA to the
             // start and end states of the new NFA
@@ -201,19 +205,27 @@ impl Nfa {
         self.set_end_state(end_state);
     }
 
-    pub(crate) fn zero_or_one(&mut self) {
+    /// Makes the NFA optional.
+    /// The epsilon transitions of a state are ordered by priority. A greedy repetition prefers to
+    /// enter the NFA, a non-greedy one prefers to skip it.
+    pub(crate) fn zero_or_one(&mut self, greedy: bool) {
         // Create a new start state
         let start_state = self.new_state();
-        // Connect the new start state to the start state of the current NFA
-        self.add_epsilon_transition(start_state, self.start_state);
-        // Connect the new start state to the end state of the current NFA
-        self.add_epsilon_transition(start_state, self.end_state);
+        // Connect the new start state to the start state and to the end state of the current NFA
+        self.add_prioritized_epsilon_transitions(
+            start_state,
+            self.start_state,
+            self.end_state,
+            greedy,
+        );
 
         // Update the start and end states of the current NFA
         self.set_start_state(start_state);
     }
 
-    pub(crate) fn one_or_more(&mut self) {
+    /// Repeats the NFA at least once.
+    /// A greedy repetition prefers to loop, a non-greedy one prefers to exit.
+    pub(crate) fn one_or_more(&mut self, greedy: bool) {
         // Create a new start state
         let start_state = self.new_state();
         // Connect the new start state to the start state of the current NFA
@@ -221,36 +233,86 @@ impl Nfa {
 
         // Create a new end state
         let end_state = self.new_state();
-        // Connect the end state of the current NFA to the new end state
-        self.add_epsilon_transition(self.end_state, end_state);
-        // Connect the end state of the current NFA to the start state of the current NFA
-        self.add_epsilon_transition(self.end_state, self.start_state);
+        // Connect the end state of the current NFA to the start state of the current NFA and to
+        // the new end state
+        self.add_prioritized_epsilon_transitions(
+            self.end_state,
+            self.start_state,
+            end_state,
+            greedy,
+        );
 
         // Update the start and end states of the current NFA
         self.set_start_state(start_state);
         self.set_end_state(end_state);
     }
 
-    pub(crate) fn zero_or_more(&mut self) {
+    /// Repeats the NFA any number of times.
+    /// A greedy repetition prefers to loop, a non-greedy one prefers to exit.
+    pub(crate) fn zero_or_more(&mut self, greedy: bool) {
         // Create a new start state
         let start_state = self.new_state();
-        // Connect the new start state to the start state of the current NFA
-        self.add_epsilon_transition(start_state, self.start_state);
-        // Connect the new start state to the end state of the current NFA
-        self.add_epsilon_transition(start_state, self.end_state);
-
         // Create a new end state
         let end_state = self.new_state();
-        // Connect the end state of the current NFA to the new end state
-        self.add_epsilon_transition(self.end_state, end_state);
-        // Connect the end state of the current NFA to the start state of the current NFA
-        self.add_epsilon_transition(self.end_state, self.start_state);
+        // Connect the new start state to the start state of the current NFA and to the new end
+        // state
+        self.add_prioritized_epsilon_transitions(start_state, self.start_state, end_state, greedy);
+        if self.matches_empty() {
+            // Like `(?:a+)?`: a path that loops without consuming a character doesn't end at the
+            // new start state but continues with the alternatives of the loop.
+            self.add_prioritized_epsilon_transitions(
+                self.end_state,
+                self.start_state,
+                end_state,
+                greedy,
+            );
+        } else {
+            // Connect the end state of the current NFA back to the new start state
+            self.add_epsilon_transition(self.end_state, start_state);
+        }
 
         // Update the start and end states of the current NFA
         self.set_start_state(start_state);
         self.set_end_state(end_state);
     }
 
+    /// Returns true if the end state can be reached from the start state without consuming a
+    /// character.
+    fn matches_empty(&self) -> bool {
+        let mut stack = vec![self.start_state];
+        let mut seen = vec![false; self.states.len()];
+        while let Some(state) = stack.pop() {
+            if state == self.end_state {
+                return true;
+            }
+            if std::mem::replace(&mut seen[state.as_usize()], true) {
+                continue;
+            }
+            let state = &self.states[state];
+            stack.extend(state.epsilon_transitions.iter().map(|t| t.target_state));
+            stack.extend(state.look_transitions.iter().map(|t| t.target_state));
+        }
+        false
+    }
+
+    /// Adds the epsilon transitions from `from` to `repeat` and to `skip` in the order of their
+    /// priority. A greedy repetition prefers `repeat`.
+    fn add_prioritized_epsilon_transitions(
+        &mut self,
+        from: StateID,
+        repeat: StateID,
+        skip: StateID,
+        greedy: bool,
+    ) {
+        if greedy {
+            self.add_epsilon_transition(from, repeat);
+            self.add_epsilon_transition(from, skip);
+        } else {
+            self.add_epsilon_transition(from, skip);
+            self.add_epsilon_transition(from, repeat);
+        }
+    }
+
     /// Move the states of the given NFA to the current NFA and thereby consume the NFA.
     pub(crate) fn append(&mut self, mut nfa: Nfa) {
         self.states.append(nfa.states.as_mut());
@@ -265,12 +327,37 @@ impl Nfa {
     pub(crate) fn try_from_ast(
         ast: Ast,
         char_class_registry: &mut CharacterClassRegistry,
+    ) -> Result<Self> {
+        Self::try_from_ast_with_limits(ast, char_class_registry, &CompileLimits::default())
+    }
+
+    /// Converts the AST into a NFA and checks the repetition counts and the number of states
+    /// against the given limits.
+    pub(crate) fn try_from_ast_with_limits(
+        ast: Ast,
+        char_class_registry: &mut CharacterClassRegistry,
+        limits: &CompileLimits,
+    ) -> Result<Self> {
+        Self::try_from_ast_with_flags(ast, &mut Flags::default(), char_class_registry, limits)
+    }
+
+    /// Converts the AST into a NFA.
+    /// The flags are modified by flag items in the AST, e.g. `(?i)`. Such modifications last until
+    /// the end of the enclosing group.
+    fn try_from_ast_with_flags(
+        ast: Ast,
+        flags: &mut Flags,
+        char_class_registry: &mut CharacterClassRegistry,
+        limits: &CompileLimits,
     ) -> Result<Self> {
         let mut nfa = Nfa::new();
         nfa.set_pattern(&ast.to_string());
         match ast {
             Ast::Empty(_) => Ok(nfa),
-            Ast::Flags(ref f) => Err(unsupported!(format!("{:?}", f.flags.items))),
+            Ast::Flags(ref f) => {
+                flags.apply(&f.flags);
+                Ok(nfa)
+            }
             Ast::Literal(ref l) => {
                 let start_state = nfa.end_state();
                 let end_state = nfa.new_state();
@@ -278,9 +365,10 @@ impl Nfa {
                 nfa.add_transition(
                     start_state,
                     Ast::Literal(l.clone()),
+                    *flags,
                     end_state,
                     char_class_registry,
-                );
+                )?;
                 Ok(nfa)
             }
             Ast::Dot(ref d) => {
@@ -290,36 +378,63 @@ impl Nfa {
                 nfa.add_transition(
                     start_state,
                     Ast::Dot(d.clone()),
+                    *flags,
                     end_state,
                     char_class_registry,
-                );
+                )?;
+                Ok(nfa)
+            }
+            Ast::Assertion(ref a) => {
+                let start_state = nfa.end_state();
+                let end_state = nfa.new_state();
+                nfa.set_end_state(end_state);
+                nfa.add_look_transition(start_state, Look::new(&a.kind, *flags), end_state);
                 Ok(nfa)
             }
-            Ast::Assertion(ref a) => Err(unsupported!(format!("Assertion {:?}", a.kind))),
             Ast::ClassUnicode(_) | Ast::ClassPerl(_) | Ast::ClassBracketed(_) => {
                 let start_state = nfa.end_state();
                 let end_state = nfa.new_state();
                 nfa.set_end_state(end_state);
-                nfa.add_transition(start_state, ast.clone(), end_state, char_class_registry);
+                nfa.add_transition(
+                    start_state,
+                    ast.clone(),
+                    *flags,
+                    end_state,
+                    char_class_registry,
+                )?;
                 Ok(nfa)
             }
             Ast::Repetition(ref r) => {
-                let mut nfa2: Nfa = Self::try_from_ast((*r.ast).clone(), char_class_registry)?;
-                if !r.greedy {
-                    Err(unsupported!(
-                        format!("{}: Non-greedy repetionions. Consider using different scanner modes instead.", ast)))?;
+                let mut nfa2: Nfa = Self::try_from_ast_with_flags(
+                    (*r.ast).clone(),
+                    flags,
+                    char_class_registry,
+                    limits,
+                )?;
+                // The flag `U` swaps the meaning of greedy and non-greedy repetitions.
+                let greedy = r.greedy != flags.swap_greed;
+                nfa2.non_greedy |= !greedy;
+                if let RepetitionKind::Range(
+                    RepetitionRange::Exactly(c)
+                    | RepetitionRange::AtLeast(c)
+                    | RepetitionRange::Bounded(_, c),
+                ) = &r.op.kind
+                {
+                    // Check the limits before the sub-NFA is copied.
+                    limits.check_repetition(*c)?;
+                    limits.check_nfa_states(nfa2.states.len().saturating_mul(*c as usize + 1))?;
                 }
                 match &r.op.kind {
                     RepetitionKind::ZeroOrOne => {
-                        nfa2.zero_or_one();
+                        nfa2.zero_or_one(greedy);
                         nfa = nfa2;
                     }
                     RepetitionKind::ZeroOrMore => {
-                        nfa2.zero_or_more();
+                        nfa2.zero_or_more(greedy);
                         nfa = nfa2;
                     }
                     RepetitionKind::OneOrMore => {
-                        nfa2.one_or_more();
+                        nfa2.one_or_more(greedy);
                         nfa = nfa2;
                     }
                     RepetitionKind::Range(r) => match r {
@@ -328,22 +443,34 @@ impl Nfa {
                                 nfa.concat(nfa2.clone());
                             }
                         }
+                        RepetitionRange::AtLeast(0) => {
+                            nfa2.zero_or_more(greedy);
+                            nfa = nfa2;
+                        }
                         RepetitionRange::AtLeast(c) => {
-                            for _ in 0..*c {
+                            for _ in 1..*c {
                                 nfa.concat(nfa2.clone());
                             }
-                            let mut nfa_zero_or_more: Nfa = nfa2.clone();
-                            nfa_zero_or_more.zero_or_more();
-                            nfa.concat(nfa_zero_or_more);
+                            nfa2.one_or_more(greedy);
+                            nfa.concat(nfa2);
                         }
                         RepetitionRange::Bounded(least, most) => {
                             for _ in 0..*least {
                                 nfa.concat(nfa2.clone());
                             }
-                            let mut nfa_zero_or_one: Nfa = nfa2.clone();
-                            nfa_zero_or_one.zero_or_one();
-                            for _ in *least..*most {
-                                nfa.concat(nfa_zero_or_one.clone());
+                            if least < most {
+                                // The optional repetitions are nested, i.e. `a{1,3}` becomes
+                                // `a(?:a(?:a)?)?`, so skipping one of them skips the following
+                                // ones, too.
+                                let mut nfa_optional: Nfa = nfa2.clone();
+                                nfa_optional.zero_or_one(greedy);
+                                for _ in *least + 1..*most {
+                                    let mut nfa_outer = nfa2.clone();
+                                    nfa_outer.concat(nfa_optional);
+                                    nfa_outer.zero_or_one(greedy);
+                                    nfa_optional = nfa_outer;
+                                }
+                                nfa.concat(nfa_optional);
                             }
                         }
                     },
@@ -351,31 +478,39 @@ impl Nfa {
                 Ok(nfa)
             }
             Ast::Group(ref g) => {
-                if let GroupKind::NonCapturing(flags) = &g.kind {
-                    if flags
-                        .items
-                        .iter()
-                        .any(|f| matches!(f.kind, FlagsItemKind::Flag(_)))
-                    {
-                        Err(unsupported!(format!(
-                            "{:?}: Flags in non-capturing group",
-                            flags.items
-                        )))?;
-                    }
+                // Flags set inside a group are only valid until the end of the group.
+                let mut group_flags = *flags;
+                if let GroupKind::NonCapturing(ref f) = g.kind {
+                    group_flags.apply(f);
                 }
-                nfa = Self::try_from_ast((*g.ast).clone(), char_class_registry)?;
+                nfa = Self::try_from_ast_with_flags(
+                    (*g.ast).clone(),
+                    &mut group_flags,
+                    char_class_registry,
+                    limits,
+                )?;
                 Ok(nfa)
             }
             Ast::Alternation(ref a) => {
                 for ast in a.asts.iter() {
-                    let nfa2: Nfa = Self::try_from_ast(ast.clone(), char_class_registry)?;
+                    let nfa2: Nfa = Self::try_from_ast_with_flags(
+                        ast.clone(),
+                        flags,
+                        char_class_registry,
+                        limits,
+                    )?;
                     nfa.alternation(nfa2);
                 }
                 Ok(nfa)
             }
             Ast::Concat(ref c) => {
                 for ast in c.asts.iter() {
-                    let nfa2: Nfa = Self::try_from_ast(ast.clone(), char_class_registry)?;
+                    let nfa2: Nfa = Self::try_from_ast_with_flags(
+                        ast.clone(),
+                        flags,
+                        char_class_registry,
+                        limits,
+                    )?;
                     nfa.concat(nfa2);
                 }
                 Ok(nfa)
@@ -383,6 +518,49 @@ impl Nfa {
         }
     }
 
+    /// Returns the reversed NFA that matches the reversed strings of this NFA.
+    /// All transitions are reversed and the start and end states are swapped.
+    /// Zero-width assertions are kept because they refer to the position between two characters
+    /// regardless of the direction in which the input is read.
+    pub(crate) fn reverse(&self) -> Nfa {
+        let mut states = self
+            .states
+            .iter()
+            .map(|s| NfaState::new(s.id()))
+            .collect::<Vec<_>>();
+        for state in &self.states {
+            for transition in state.transitions() {
+                states[transition.target_state]
+                    .transitions
+                    .push(NfaTransition {
+                        ast: transition.ast.clone(),
+                        char_class: transition.char_class,
+                        target_state: state.id(),
+                    });
+            }
+            for epsilon_transition in state.epsilon_transitions() {
+                states[epsilon_transition.target_state]
+                    .epsilon_transitions
+                    .push(EpsilonTransition::new(state.id()));
+            }
+            for look_transition in state.look_transitions() {
+                states[look_transition.target_state]
+                    .look_transitions
+                    .push(LookTransition {
+                        look: look_transition.look,
+                        target_state: state.id(),
+                    });
+            }
+        }
+        Nfa {
+            pattern: self.pattern.clone(),
+            states,
+            start_state: self.end_state,
+            end_state: self.start_state,
+            non_greedy: false,
+        }
+    }
+
     /// Calculate the epsilon closure of a state.
     pub(crate) fn epsilon_closure(&self, state: StateID) -> Vec<StateID> {
         // The state itself is always part of the ε-closure
@@ -426,21 +604,6 @@ impl Nfa {
         move_set
     }
 
-    pub(crate) fn get_match_transitions(
-        &self,
-        start_states: impl Iterator<Item = StateID>,
-    ) -> Vec<(CharClassID, StateID)> {
-        let mut target_states = Vec::new();
-        for state in start_states {
-            for transition in self.states()[state].transitions() {
-                target_states.push((transition.char_class(), transition.target_state()));
-            }
-        }
-        target_states.sort_unstable();
-        target_states.dedup();
-        target_states
-    }
-
     pub(crate) fn contains_state(&self, state: StateID) -> bool {
         self.states.iter().any(|s| s.id() == state)
     }
@@ -455,6 +618,7 @@ pub(crate) struct NfaState {
     state: StateID,
     epsilon_transitions: Vec<EpsilonTransition>,
     transitions: Vec<NfaTransition>,
+    look_transitions: Vec<LookTransition>,
 }
 
 impl NfaState {
@@ -463,11 +627,18 @@ impl NfaState {
             state,
             epsilon_transitions: Vec::new(),
             transitions: Vec::new(),
+            look_transitions: Vec::new(),
         }
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.transitions.is_empty() && self.epsilon_transitions.is_empty()
+        self.transitions.is_empty()
+            && self.epsilon_transitions.is_empty()
+            && self.look_transitions.is_empty()
+    }
+
+    fn number_of_transitions(&self) -> usize {
+        self.epsilon_transitions.len() + self.transitions.len() + self.look_transitions.len()
     }
 
     pub(crate) fn id(&self) -> StateID {
@@ -482,6 +653,10 @@ impl NfaState {
         &self.epsilon_transitions
     }
 
+    pub(crate) fn look_transitions(&self) -> &[LookTransition] {
+        &self.look_transitions
+    }
+
     /// Apply an offset to every state number.
     pub(crate) fn offset(&mut self, offset: usize) {
         self.state = StateID::new(self.state.id() + offset as StateIDBase);
@@ -493,6 +668,10 @@ impl NfaState {
             epsilon_transition.target_state =
                 StateID::new(epsilon_transition.target_state.id() + offset as StateIDBase);
         }
+        for look_transition in self.look_transitions.iter_mut() {
+            look_transition.target_state =
+                StateID::new(look_transition.target_state.id() + offset as StateIDBase);
+        }
     }
 }
 
@@ -552,6 +731,27 @@ impl From<StateID> for EpsilonTransition {
     }
 }
 
+/// A transition in the NFA that is only taken if a zero-width assertion holds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct LookTransition {
+    /// The assertion that must hold at the current position
+    look: Look,
+    /// The next state to transition to
+    target_state: StateID,
+}
+
+impl LookTransition {
+    #[inline]
+    pub(crate) fn look(&self) -> Look {
+        self.look
+    }
+
+    #[inline]
+    pub(crate) fn target_state(&self) -> StateID {
+        self.target_state
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
@@ -648,7 +848,7 @@ mod tests {
         )
         .unwrap();
 
-        nfa.zero_or_more();
+        nfa.zero_or_more(true);
 
         // Add assertions here to validate the NFA
         assert_eq!(nfa.states.len(), 4);
@@ -666,7 +866,7 @@ mod tests {
             &mut char_class_registry,
         )
         .unwrap();
-        nfa.zero_or_one();
+        nfa.zero_or_one(true);
 
         // Add assertions here to validate the NFA
         assert_eq!(nfa.states.len(), 3);
@@ -684,7 +884,7 @@ mod tests {
             &mut char_class_registry,
         )
         .unwrap();
-        nfa.one_or_more();
+        nfa.one_or_more(true);
 
         // Add assertions here to validate the NFA
         assert_eq!(nfa.states.len(), 4);
@@ -702,7 +902,7 @@ mod tests {
             &mut char_class_registry,
         )
         .unwrap();
-        nfa.zero_or_more();
+        nfa.zero_or_more(true);
 
         // Add assertions here to validate the NFA
         assert_eq!(nfa.states.len(), 4);
@@ -757,9 +957,9 @@ mod tests {
         .unwrap();
 
         // Add assertions here to validate the NFA
-        assert_eq!(nfa.states.len(), 10);
+        assert_eq!(nfa.states.len(), 8);
         assert_eq!(nfa.start_state.as_usize(), 0);
-        assert_eq!(nfa.end_state.as_usize(), 9);
+        assert_eq!(nfa.end_state.as_usize(), 7);
     }
 
     #[test]
@@ -776,7 +976,7 @@ mod tests {
         // Add assertions here to validate the NFA
         assert_eq!(nfa.states.len(), 12);
         assert_eq!(nfa.start_state.as_usize(), 0);
-        assert_eq!(nfa.end_state.as_usize(), 10);
+        assert_eq!(nfa.end_state.as_usize(), 9);
     }
 
     // Ascii character class are not yet implemented
@@ -813,13 +1013,12 @@ mod tests {
         let closure = nfa.epsilon_closure(nfa.start_state);
 
         // Validate the NFA closure
-        assert_eq!(closure.len(), 7);
+        assert_eq!(closure.len(), 6);
         assert_eq!(BTreeSet::<StateID>::from_iter(closure.iter().cloned()), {
             let mut set = BTreeSet::new();
             set.insert(StateID::new(0));
             set.insert(StateID::new(2));
             set.insert(StateID::new(4));
-            set.insert(StateID::new(5));
             set.insert(StateID::new(6));
             set.insert(StateID::new(7));
             set.insert(StateID::new(8));
@@ -867,6 +1066,7 @@ mod tests {
                     StateID::new(2),
                     StateID::new(4),
                     StateID::new(5),
+                    StateID::new(6),
                     StateID::new(7),
                     StateID::new(8),
                 ])
@@ -878,6 +1078,7 @@ mod tests {
                     StateID::new(3),
                     StateID::new(4),
                     StateID::new(5),
+                    StateID::new(6),
                     StateID::new(7),
                     StateID::new(8),
                 ])
@@ -930,30 +1131,56 @@ mod tests {
         );
     }
 
-    // Test error on greedy repetition
     #[test]
-    fn test_nfa_repetition_non_greedy() {
+    fn test_nfa_flags_scope() {
         // Create a character class registry
         let mut char_class_registry = CharacterClassRegistry::new();
-        // Create an example AST and convert the AST to an NFA
-        let result = Nfa::try_from_ast(
-            crate::internal::parse_regex_syntax("a*?").unwrap(),
+        // Flags in a group are only valid until the end of the group
+        let _ = Nfa::try_from_ast(
+            crate::internal::parse_regex_syntax("((?i)a)a(?i:b)b|(?s:.)(?i)c").unwrap(),
             &mut char_class_registry,
-        );
-
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Non-greedy"));
+        )
+        .unwrap();
 
-        // Create a character class registry
-        let mut char_class_registry = CharacterClassRegistry::new();
-        // Create an example AST and convert the AST to an NFA
-        let result = Nfa::try_from_ast(
-            crate::internal::parse_regex_syntax("a+?").unwrap(),
-            &mut char_class_registry,
+        let classes = char_class_registry
+            .character_classes()
+            .iter()
+            .map(|cc| cc.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            classes,
+            vec![
+                "#0 '(?i)a'",
+                "#1 'a'",
+                "#2 '(?i)b'",
+                "#3 'b'",
+                "#4 '(?s).'",
+                "#5 '(?i)c'"
+            ]
         );
+    }
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Non-greedy"));
+    // Test error on greedy repetition
+    #[test]
+    fn test_nfa_repetition_non_greedy() {
+        for (pattern, non_greedy) in [
+            ("a*?", true),
+            ("a+?", true),
+            ("(a|b??)c", true),
+            ("(?U)a*", true),
+            ("(?U)a*?", false),
+            ("a*(?U:b)", false),
+        ] {
+            // Create a character class registry
+            let mut char_class_registry = CharacterClassRegistry::new();
+            // Create an example AST and convert the AST to an NFA
+            let nfa = Nfa::try_from_ast(
+                crate::internal::parse_regex_syntax(pattern).unwrap(),
+                &mut char_class_registry,
+            )
+            .unwrap();
+            assert_eq!(nfa.non_greedy, non_greedy, "Pattern {}", pattern);
+        }
     }
 }
 
@@ -10,7 +10,7 @@
 //! The global scanner cache is implemented as a `RwLock<ScannerCache>`.
 //!
 
-use crate::{scanner_mode::ScannerMode, Result};
+use crate::{scanner_mode::ScannerMode, CompileLimits, Result};
 use rustc_hash::FxHashMap;
 
 use std::sync::Arc;
@@ -18,8 +18,10 @@ use std::sync::Arc;
 use super::ScannerImpl;
 
 /// The cache is a `FxHashMap` that maps a vectors of `ScannerMode` to `Arc<ScannerImpl>`.
+/// The scanners are additionally distinguished by the compile limits they were built with, because
+/// the same scanner modes can fail with stricter limits.
 pub(crate) struct ScannerCache {
-    cache: FxHashMap<Vec<ScannerMode>, Arc<ScannerImpl>>,
+    cache: FxHashMap<CompileLimits, FxHashMap<Vec<ScannerMode>, Arc<ScannerImpl>>>,
 }
 
 impl ScannerCache {
@@ -36,8 +38,13 @@ impl ScannerCache {
     /// This function uses `unsafe` because it dereferences a raw pointer.
     /// The Arc assures that the pointer is valid because it holds a reference to the object as
     /// long as the Arc is in the cache and thus alive.
-    pub(crate) fn get(&mut self, modes: &[ScannerMode]) -> Result<ScannerImpl> {
-        if let Some(scanner) = self.cache.get(modes) {
+    pub(crate) fn get(
+        &mut self,
+        modes: &[ScannerMode],
+        limits: &CompileLimits,
+    ) -> Result<ScannerImpl> {
+        let cache = self.cache.entry(*limits).or_default();
+        if let Some(scanner) = cache.get(modes) {
             // We need to clone the scanner because we need to return a new instance of the scanner.
             // This is because the scanner is mutable and we need to have a unique instance of the
             // scanner.
@@ -46,9 +53,9 @@ impl ScannerCache {
                 unsafe { (*std::sync::Arc::<ScannerImpl>::as_ptr(scanner)).clone() };
             Ok(cloned_scanner)
         } else {
-            self.cache
-                .insert(modes.to_vec(), Arc::new(modes.try_into()?));
-            Ok(self.get(modes).unwrap())
+            let scanner = Arc::new(ScannerImpl::try_from_scanner_modes(modes, limits)?);
+            cache.insert(modes.to_vec(), scanner);
+            Ok(self.get(modes, limits).unwrap())
         }
     }
 }
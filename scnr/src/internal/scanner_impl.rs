@@ -1,43 +1,70 @@
+#[cfg(feature = "compiler")]
 use std::sync::Arc;
 
 use log::trace;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use crate::{Match, Result, ScannerMode, ScannerModeSwitcher};
+#[cfg(any(feature = "compiler", feature = "serde"))]
+use crate::Result;
+#[cfg(feature = "compiler")]
+use crate::{CompileLimits, ScannerMode};
+use crate::{Match, ScannerModeSwitcher, UnmatchedInput};
 
 use super::{
-    compiled_scanner_mode::CompiledScannerMode, CharClassID, CharacterClassRegistry, TerminalIDBase,
+    compiled_scanner_mode::CompiledScannerMode, CharIndices, Haystack, ModeStack, TerminalIDBase,
+    TokenNames,
 };
+#[cfg(feature = "compiler")]
+use super::{CharClassID, CharacterClassRegistry};
 
 /// ScannerImpl instances are always created by the Scanner::try_new method and of course by
 /// the clone method.
+///
+/// The character class registry is only needed to compile the DFAs. It is not serialized, so a
+/// deserialized scanner has an empty registry.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone)]
 pub(crate) struct ScannerImpl {
+    #[cfg(feature = "compiler")]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) character_classes: Arc<CharacterClassRegistry>,
     pub(crate) scanner_modes: Vec<CompiledScannerMode>,
-    // The function used to match characters against character classes.
-    pub(crate) match_char_class: Arc<dyn (Fn(CharClassID, char) -> bool) + 'static + Send + Sync>,
     // The current mode is private and thereby makes the free creation of ScannerImpl instances
     // impossible.
+    #[cfg_attr(feature = "serde", serde(skip))]
     current_mode: usize,
+    // The scanner modes that were active before the push transitions that are not yet popped.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mode_stack: ModeStack,
+    // The handling of input that no pattern of the current scanner mode matches. Scanners that
+    // were serialized without it skip unmatched input.
+    #[cfg_attr(feature = "serde", serde(default))]
+    unmatched_input: UnmatchedInput,
+    // The names of the token types.
+    #[cfg_attr(feature = "serde", serde(default))]
+    token_names: TokenNames,
 }
 impl ScannerImpl {
     /// Executes a possible mode switch if a transition is defined for the token type found.
     #[inline]
-    fn execute_possible_mode_switch(&mut self, current_match: &Match) {
+    pub(crate) fn execute_possible_mode_switch(&mut self, current_match: &Match) {
         let current_mode = &self.scanner_modes[self.current_mode];
         // We perform a scanner mode switch if a transition is defined for the token type found.
-        if let Some(next_mode) = current_mode.has_transition(current_match.token_type()) {
+        if let Some(transition) = current_mode.has_transition(current_match.token_type()) {
             trace!(
-                "Switching from mode {} to mode {}",
+                "Switching from mode {} with transition {:?} on token type {}",
                 self.current_mode,
-                next_mode
+                transition,
+                self.token_names.describe(current_match.token_type())
             );
-            self.current_mode = next_mode;
+            transition.apply(&mut self.current_mode, &mut self.mode_stack);
         }
     }
 
     /// Creates a function that matches a character against a character class.
     /// Used in tests only.
+    #[cfg(feature = "compiler")]
     #[allow(dead_code)]
     pub(crate) fn create_match_char_class(
         &self,
@@ -47,40 +74,41 @@ impl ScannerImpl {
 
     pub(crate) fn reset(&mut self) {
         self.current_mode = 0;
+        self.mode_stack = ModeStack::default();
     }
 
-    /// Executes a leftmost search and returns the first match that is found, if one exists.
-    /// It starts the search at the position of the given CharIndices iterator.
-    pub(crate) fn find_from(
-        &mut self,
-        input: &str,
-        char_indices: std::str::CharIndices,
-    ) -> Option<crate::Match> {
-        if let Some(matched) = self.peek_from(input, char_indices) {
-            self.execute_possible_mode_switch(&matched);
-            return Some(matched);
-        }
-        None
-    }
-
-    /// This function is used by [super::find_matches_impl::FindMatchesImpl::peek_n].
+    /// This function is used by [super::find_matches_impl::FindMatchesImpl].
     ///
     /// Executes a leftmost search and returns the first match that is found, if one exists.
     /// It starts the search at the position of the given CharIndices iterator.
-    /// In contrast to `find_from`, this method does not execute a mode switch if a transition is
-    /// defined for the token type found.
+    /// It doesn't execute a mode switch if a transition is defined for the token type found. The
+    /// caller does this with `execute_possible_mode_switch` when it consumes the match.
     ///
     /// The name `peek_from` is used to indicate that this method is used for peeking ahead.
-    /// It is called by the `peek_n` method of the `FindMatches` iterator on a copy of the
-    /// `CharIndices` iterator. Thus, the original `CharIndices` iterator is not advanced.
+    /// It is called on a copy of the `CharIndices` iterator. Thus, the original `CharIndices`
+    /// iterator is not advanced.
     pub(crate) fn peek_from(
         &mut self,
-        input: &str,
-        char_indices: std::str::CharIndices,
+        input: Haystack,
+        char_indices: CharIndices,
     ) -> Option<crate::Match> {
-        let dfa = &mut self.scanner_modes[self.current_mode].dfa;
+        self.peek_from_partial(input, char_indices).0
+    }
 
-        if let Some(matched) = dfa.find_from(input, char_indices, &*self.match_char_class) {
+    /// Like `peek_from`, but also returns the start of the input that the search and its
+    /// lookbehinds have read, as position in the input, and the end of the input that the search
+    /// has read, relative to the start of the slice, see `CompiledDfa::find_from_partial`.
+    /// This is used by [crate::StreamMatches], whose input is only a part of the stream, and by
+    /// the incremental re-tokenization.
+    pub(crate) fn peek_from_partial(
+        &mut self,
+        input: Haystack,
+        char_indices: CharIndices,
+    ) -> (Option<crate::Match>, usize, usize) {
+        let dfa = &self.scanner_modes[self.current_mode].dfa;
+
+        let (matched, read_start, read_end) = dfa.find_from_partial(input, char_indices);
+        if let Some(matched) = matched {
             debug_assert!(
                 !matched.is_empty(),
                 r#"
@@ -89,16 +117,64 @@ impl ScannerImpl {
     Please, check regex '{}' for token type {} in scanner mode {}"#,
                 dfa.pattern((matched.token_type() as TerminalIDBase).into())
                     .escape_default(),
-                matched.token_type(),
+                self.token_names.describe(matched.token_type()),
                 self.current_mode
             );
-            return Some(matched);
         }
-        None
+        (matched, read_start, read_end)
+    }
+
+    /// Returns the mode stack. It is shared with its clones, so a copy of it is cheap.
+    pub(crate) fn mode_stack(&self) -> &ModeStack {
+        &self.mode_stack
     }
 
+    /// Replaces the mode stack, e.g. to continue a scan at the state of an earlier token.
+    pub(crate) fn set_mode_stack(&mut self, mode_stack: &ModeStack) {
+        self.mode_stack = mode_stack.clone();
+    }
+
+    /// Returns the scanner mode that is active after a match of the token type if the current
+    /// scanner mode has a transition on it.
     pub(crate) fn has_transition(&self, token_type: usize) -> Option<usize> {
-        self.scanner_modes[self.current_mode].has_transition(token_type)
+        self.scanner_modes[self.current_mode]
+            .has_transition(token_type)
+            .map(|transition| transition.target(self.current_mode, &self.mode_stack))
+    }
+
+    /// Returns the handling of input that no pattern of the current scanner mode matches.
+    pub(crate) fn unmatched_input(&self) -> UnmatchedInput {
+        self.unmatched_input
+    }
+
+    /// Sets the handling of input that no pattern of the current scanner mode matches.
+    pub(crate) fn set_unmatched_input(&mut self, unmatched_input: UnmatchedInput) {
+        self.unmatched_input = unmatched_input;
+    }
+
+    /// Returns the names of the token types.
+    pub(crate) fn token_names(&self) -> &TokenNames {
+        &self.token_names
+    }
+
+    /// Checks if the token type is marked as skipped in the current scanner mode.
+    pub(crate) fn is_skipped(&self, token_type: usize) -> bool {
+        self.scanner_modes[self.current_mode].is_skipped(token_type)
+    }
+
+    /// Checks the consistency of a deserialized scanner, i.e. of its scanner modes with their
+    /// DFAs and transitions and of its token names.
+    #[cfg(feature = "serde")]
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.scanner_modes.is_empty() {
+            return Err(super::serialization::invalid_scanner(
+                "the scanner has no scanner mode",
+            ));
+        }
+        self.scanner_modes
+            .iter()
+            .try_for_each(|scanner_mode| scanner_mode.validate(self.scanner_modes.len()))?;
+        self.token_names.validate()
     }
 
     /// Traces the compiled DFAs as dot format.
@@ -116,7 +192,7 @@ impl ScannerImpl {
                 super::dot::compiled_dfa_render(
                     &scanner_mode.dfa,
                     &title,
-                    &self.character_classes,
+                    &self.token_names,
                     &mut cursor,
                 );
                 let mut dot_format = String::new();
@@ -149,7 +225,7 @@ impl ScannerImpl {
             super::dot::compiled_dfa_render(
                 &scanner_mode.dfa,
                 &title,
-                &self.character_classes,
+                &self.token_names,
                 &mut file,
             );
         }
@@ -171,62 +247,90 @@ impl ScannerModeSwitcher for ScannerImpl {
     fn set_mode(&mut self, mode: usize) {
         self.current_mode = mode;
     }
+
+    #[inline]
+    fn mode_stack_depth(&self) -> usize {
+        self.mode_stack.len()
+    }
 }
 
 impl std::fmt::Debug for ScannerImpl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ScannerImpl")
-            .field("character_classes", &self.character_classes)
+        let mut debug = f.debug_struct("ScannerImpl");
+        #[cfg(feature = "compiler")]
+        debug.field("character_classes", &self.character_classes);
+        debug
             .field("scanner_modes", &self.scanner_modes)
+            .field("unmatched_input", &self.unmatched_input)
+            .field("token_names", &self.token_names)
             .finish()
     }
 }
 
-impl TryFrom<Vec<ScannerMode>> for ScannerImpl {
-    type Error = crate::ScnrError;
-    fn try_from(scanner_modes: Vec<ScannerMode>) -> Result<Self> {
+impl ScannerImpl {
+    /// Creates a scanner from already compiled scanner modes.
+    /// The character class registry stays empty because it is only needed for the compilation.
+    pub(crate) fn from_compiled_scanner_modes(
+        scanner_modes: Vec<CompiledScannerMode>,
+        token_names: TokenNames,
+    ) -> Self {
+        Self {
+            #[cfg(feature = "compiler")]
+            character_classes: Arc::new(CharacterClassRegistry::new()),
+            scanner_modes,
+            current_mode: 0,
+            mode_stack: ModeStack::default(),
+            unmatched_input: UnmatchedInput::Skip,
+            token_names,
+        }
+    }
+
+    /// Compiles the given scanner modes while respecting the given compile limits.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn try_from_scanner_modes(
+        scanner_modes: &[ScannerMode],
+        limits: &CompileLimits,
+    ) -> Result<Self> {
+        let token_names = TokenNames::try_from_scanner_modes(scanner_modes)?;
         let mut character_class_registry = CharacterClassRegistry::new();
         let mut compiled_scanner_modes = Vec::with_capacity(scanner_modes.len());
         for scanner_mode in scanner_modes {
             let compiled_scanner_mode = CompiledScannerMode::try_from_scanner_mode(
-                scanner_mode,
+                scanner_mode.clone(),
                 &mut character_class_registry,
+                limits,
             )?;
             compiled_scanner_modes.push(compiled_scanner_mode);
         }
-        let match_char_class = Arc::new(character_class_registry.create_match_char_class()?);
         Ok(Self {
             character_classes: Arc::new(character_class_registry),
             scanner_modes: compiled_scanner_modes,
-            match_char_class,
             current_mode: 0,
+            mode_stack: ModeStack::default(),
+            unmatched_input: UnmatchedInput::Skip,
+            token_names,
         })
     }
 }
 
+#[cfg(feature = "compiler")]
+impl TryFrom<Vec<ScannerMode>> for ScannerImpl {
+    type Error = crate::ScnrError;
+    fn try_from(scanner_modes: Vec<ScannerMode>) -> Result<Self> {
+        Self::try_from_scanner_modes(&scanner_modes, &CompileLimits::default())
+    }
+}
+
+#[cfg(feature = "compiler")]
 impl TryFrom<&[ScannerMode]> for ScannerImpl {
     type Error = crate::ScnrError;
     fn try_from(scanner_modes: &[ScannerMode]) -> Result<Self> {
-        let mut character_class_registry = CharacterClassRegistry::new();
-        let mut compiled_scanner_modes = Vec::with_capacity(scanner_modes.len());
-        for scanner_mode in scanner_modes {
-            let compiled_scanner_mode = CompiledScannerMode::try_from_scanner_mode(
-                scanner_mode.clone(),
-                &mut character_class_registry,
-            )?;
-            compiled_scanner_modes.push(compiled_scanner_mode);
-        }
-        let match_char_class = Arc::new(character_class_registry.create_match_char_class()?);
-        Ok(Self {
-            character_classes: Arc::new(character_class_registry),
-            scanner_modes: compiled_scanner_modes,
-            match_char_class,
-            current_mode: 0,
-        })
+        Self::try_from_scanner_modes(scanner_modes, &CompileLimits::default())
     }
 }
 
 #[cfg(test)]
+#[cfg(feature = "compiler")]
 mod tests {
     use super::*;
     use crate::{Pattern, ScannerMode};
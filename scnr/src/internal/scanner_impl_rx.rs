@@ -1,15 +1,20 @@
-use crate::{Match, Pattern, Result, ScannerMode, ScannerModeSwitcher, ScnrError};
+use crate::{
+    CompileLimits, Match, ModeTransition, Pattern, Result, ScannerMode, ScannerModeSwitcher,
+    ScnrError, UnmatchedInput,
+};
 use log::trace;
-use regex_automata::{dfa::regex::Regex, Input};
+use regex_automata::{meta::Regex, util::syntax, Anchored, Input, PatternID};
 
-use super::{ScannerModeID, TerminalID};
+use super::{CharIndices, Haystack, ModeStack, TerminalID, TerminalIDBase, TokenNames};
 
-type ModeTransitions = Vec<(TerminalID, ScannerModeID)>;
+type ModeTransitions = Vec<(TerminalID, ModeTransition)>;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ScannerModeRx {
     rx: Regex,
     transitions: ModeTransitions,
+    // The token types of the patterns that are marked as skipped, sorted by token type number.
+    skipped: Vec<TerminalID>,
     name: String,
     patterns: Vec<Pattern>,
     // The lookaheads are stored as a vector of options. If a lookahead is defined for a pattern,
@@ -17,6 +22,19 @@ pub(crate) struct ScannerModeRx {
     // The boolean value indicates if the lookahead is positive.
     // The string value of the lookahead pattern is available in the `patterns` member.
     lookaheads: Vec<Option<(bool, Regex)>>,
+    // The lookbehinds are stored like the lookaheads. The regex of a lookbehind is anchored at the
+    // end of the haystack, so that it only matches a suffix of the text before the match start.
+    lookbehinds: Vec<Option<(bool, Regex)>>,
+}
+
+impl ScannerModeRx {
+    /// Checks if the lookbehind of the pattern with the given index is satisfied.
+    /// The haystack is the text before the match start.
+    fn satisfies_lookbehind(&self, pattern_id: usize, haystack: &[u8]) -> bool {
+        self.lookbehinds[pattern_id]
+            .as_ref()
+            .is_none_or(|(is_positive, re)| re.is_match(haystack) == *is_positive)
+    }
 }
 
 /// ScannerImpl instances are always created by the Scanner::try_new method and of course by
@@ -27,62 +45,76 @@ pub(crate) struct ScannerImpl {
     // The current mode is private and thereby makes the free creation of ScannerImpl instances
     // impossible.
     current_mode: usize,
+    // The scanner modes that were active before the push transitions that are not yet popped.
+    mode_stack: ModeStack,
+    // The handling of input that no pattern of the current scanner mode matches.
+    unmatched_input: UnmatchedInput,
+    // The names of the token types. They are also available in the patterns of the scanner modes
+    // and thereby serialized with them.
+    token_names: TokenNames,
 }
 
 impl ScannerImpl {
     /// Executes a possible mode switch if a transition is defined for the token type found.
     #[inline]
-    fn execute_possible_mode_switch(&mut self, current_match: &Match) {
+    pub(crate) fn execute_possible_mode_switch(&mut self, current_match: &Match) {
         // We perform a scanner mode switch if a transition is defined for the token type found.
-        if let Some(next_mode) = self.has_transition(current_match.token_type()) {
+        if let Some(transition) = self.transition(current_match.token_type()) {
             trace!(
-                "Switching from mode {} to mode {}",
+                "Switching from mode {} with transition {:?} on token type {}",
                 self.current_mode,
-                next_mode
+                transition,
+                self.token_names.describe(current_match.token_type())
             );
-            self.current_mode = next_mode;
+            transition.apply(&mut self.current_mode, &mut self.mode_stack);
         }
     }
 
     pub(crate) fn reset(&mut self) {
         self.current_mode = 0;
+        self.mode_stack = ModeStack::default();
     }
 
-    /// Executes a leftmost search and returns the first match that is found, if one exists.
-    /// It starts the search at the position of the given CharIndices iterator.
-    pub(crate) fn find_from(
-        &mut self,
-        input: &str,
-        char_indices: std::str::CharIndices,
-    ) -> Option<crate::Match> {
-        if let Some(matched) = self.peek_from(input, char_indices) {
-            self.execute_possible_mode_switch(&matched);
-            return Some(matched);
-        }
-        None
-    }
-
-    /// This function is used by [super::find_matches_impl::FindMatchesImpl::peek_n].
+    /// This function is used by [super::find_matches_impl::FindMatchesImpl].
     ///
     /// Executes a leftmost search and returns the first match that is found, if one exists.
     /// It starts the search at the position of the given CharIndices iterator.
-    /// In contrast to `find_from`, this method does not execute a mode switch if a transition is
-    /// defined for the token type found.
+    /// It doesn't execute a mode switch if a transition is defined for the token type found. The
+    /// caller does this with `execute_possible_mode_switch` when it consumes the match.
     ///
     /// The name `peek_from` is used to indicate that this method is used for peeking ahead.
-    /// It is called by the `peek_n` method of the `FindMatches` iterator on a copy of the
-    /// `CharIndices` iterator. Thus, the original `CharIndices` iterator is not advanced.
+    /// It is called on a copy of the `CharIndices` iterator. Thus, the original `CharIndices`
+    /// iterator is not advanced.
     pub(crate) fn peek_from(
         &mut self,
-        _input: &str,
-        char_indices: std::str::CharIndices,
+        input: Haystack,
+        char_indices: CharIndices,
     ) -> Option<crate::Match> {
-        let current_mode = &mut self.scanner_modes[self.current_mode];
-        let re = &mut current_mode.rx;
+        let current_mode = &self.scanner_modes[self.current_mode];
+        let re = &current_mode.rx;
 
-        let input: Input = char_indices.as_str().into();
-        let input = input.anchored(regex_automata::Anchored::Yes);
-        if let Some(matched) = re.find(input) {
+        // The search runs on the whole input so that zero-width assertions can see the characters
+        // before the start position.
+        let start = char_indices.slice_start() + char_indices.offset();
+        let input = input.as_bytes();
+        let haystack = Input::new(input).range(start..).anchored(Anchored::Yes);
+        let mut found = re.find(haystack.clone());
+        if let Some(matched) = found {
+            if !current_mode.satisfies_lookbehind(matched.pattern().as_usize(), &input[..start]) {
+                trace!("Lookbehind is not satisfied.");
+                // Try the patterns with lower priority one by one.
+                found = (matched.pattern().as_usize() + 1..current_mode.patterns.len())
+                    .filter(|p| current_mode.satisfies_lookbehind(*p, &input[..start]))
+                    .find_map(|p| {
+                        re.find(
+                            haystack
+                                .clone()
+                                .anchored(Anchored::Pattern(PatternID::must(p))),
+                        )
+                    });
+            }
+        }
+        if let Some(matched) = found {
             debug_assert!(
                 !matched.is_empty(),
                 r#"
@@ -92,17 +124,19 @@ impl ScannerImpl {
                 current_mode.patterns[matched.pattern().as_usize()]
                     .pattern()
                     .escape_default(),
-                matched.pattern().as_u32(),
+                self.token_names
+                    .describe(current_mode.patterns[matched.pattern().as_usize()].terminal_id()),
                 self.current_mode
             );
             let pattern_id = matched.pattern().as_usize();
             let matched_pattern = &current_mode.patterns[pattern_id];
-            let mut matched: Match =
-                Match::new(matched_pattern.terminal_id(), matched.range().into());
+            let mut matched: Match = Match::new(
+                matched_pattern.terminal_id(),
+                (matched.start() - start..matched.end() - start).into(),
+            );
             // Check if the lookahead is satisfied.
             if let Some((is_positive, re)) = current_mode.lookaheads[pattern_id].as_ref() {
-                let next_char = char_indices.clone().nth(matched.len());
-                let end_of_input = next_char.is_none();
+                let end_of_input = start + matched.end() == input.len();
                 if end_of_input {
                     // End of input reached.
                     // If the lookahead is negative, the match is valid.
@@ -115,9 +149,10 @@ impl ScannerImpl {
                     return if *is_positive { None } else { Some(matched) };
                 } else {
                     let lookahead_matched = {
-                        let input: Input = char_indices.as_str()[matched.end()..].into();
-                        let input = input.anchored(regex_automata::Anchored::Yes);
-                        re.find(input).is_some()
+                        let haystack = Input::new(input)
+                            .range(start + matched.end()..)
+                            .anchored(Anchored::Yes);
+                        re.find(haystack).is_some()
                     };
                     matched.add_offset(char_indices.offset());
                     trace!(
@@ -142,17 +177,58 @@ impl ScannerImpl {
         None
     }
 
-    pub(crate) fn has_transition(&self, token_type: usize) -> Option<usize> {
-        for (tok_type, scanner) in &self.scanner_modes[self.current_mode].transitions {
+    /// Returns the transition of the current scanner mode on the token type, if there is one.
+    fn transition(&self, token_type: usize) -> Option<ModeTransition> {
+        for (tok_type, transition) in &self.scanner_modes[self.current_mode].transitions {
             match token_type.cmp(&tok_type.as_usize()) {
                 std::cmp::Ordering::Less => return None,
-                std::cmp::Ordering::Equal => return Some(scanner.as_usize()),
+                std::cmp::Ordering::Equal => return Some(*transition),
                 std::cmp::Ordering::Greater => continue,
             }
         }
         None
     }
 
+    /// Returns the handling of input that no pattern of the current scanner mode matches.
+    pub(crate) fn unmatched_input(&self) -> UnmatchedInput {
+        self.unmatched_input
+    }
+
+    /// Sets the handling of input that no pattern of the current scanner mode matches.
+    pub(crate) fn set_unmatched_input(&mut self, unmatched_input: UnmatchedInput) {
+        self.unmatched_input = unmatched_input;
+    }
+
+    /// Returns the names of the token types.
+    pub(crate) fn token_names(&self) -> &TokenNames {
+        &self.token_names
+    }
+
+    /// Checks if the token type is marked as skipped in the current scanner mode.
+    pub(crate) fn is_skipped(&self, token_type: usize) -> bool {
+        self.scanner_modes[self.current_mode]
+            .skipped
+            .binary_search(&TerminalID::new(token_type as TerminalIDBase))
+            .is_ok()
+    }
+
+    /// Returns the scanner mode that is active after a match of the token type if the current
+    /// scanner mode has a transition on it.
+    pub(crate) fn has_transition(&self, token_type: usize) -> Option<usize> {
+        self.transition(token_type)
+            .map(|transition| transition.target(self.current_mode, &self.mode_stack))
+    }
+
+    /// Returns the mode stack. It is shared with its clones, so a copy of it is cheap.
+    pub(crate) fn mode_stack(&self) -> &ModeStack {
+        &self.mode_stack
+    }
+
+    /// Replaces the mode stack, e.g. to continue a scan at a checkpoint.
+    pub(crate) fn set_mode_stack(&mut self, mode_stack: &ModeStack) {
+        self.mode_stack = mode_stack.clone();
+    }
+
     /// Traces the compiled DFAs as dot format.
     /// The output is written to the log.
     /// This function is used for debugging purposes.
@@ -187,15 +263,28 @@ impl ScannerModeSwitcher for ScannerImpl {
     fn set_mode(&mut self, mode: usize) {
         self.current_mode = mode;
     }
+
+    #[inline]
+    fn mode_stack_depth(&self) -> usize {
+        self.mode_stack.len()
+    }
 }
 
-impl TryFrom<Vec<ScannerMode>> for ScannerImpl {
-    type Error = crate::ScnrError;
-    fn try_from(modes: Vec<ScannerMode>) -> Result<Self> {
+impl ScannerImpl {
+    /// Compiles the given scanner modes while respecting the given compile limits.
+    /// Only the maximum repetition count is checked here. The sizes of the automata are limited
+    /// by the `regex_automata` crate itself.
+    pub(crate) fn try_from_scanner_modes(
+        modes: &[ScannerMode],
+        limits: &CompileLimits,
+    ) -> Result<Self> {
+        let token_names = TokenNames::try_from_scanner_modes(modes)?;
         let mut scanner_modes = Vec::with_capacity(modes.len());
 
         for mode in modes {
+            check_repetitions(mode, limits)?;
             let rx = Regex::builder()
+                .syntax(syntax_config())
                 .build_many(&mode.patterns)
                 .map_err(|e| ScnrError::new(e.into()))?;
             let mut transitions = Vec::with_capacity(mode.transitions.len());
@@ -203,9 +292,18 @@ impl TryFrom<Vec<ScannerMode>> for ScannerImpl {
                 transitions.push((*terminal, *scanner));
             }
             transitions.sort_by_key(|(terminal, _)| *terminal);
+            let mut skipped = mode
+                .patterns
+                .iter()
+                .filter(|pattern| pattern.is_skip())
+                .map(|pattern| TerminalID::new(pattern.terminal_id() as TerminalIDBase))
+                .collect::<Vec<_>>();
+            skipped.sort();
+            skipped.dedup();
             let mode = ScannerModeRx {
                 rx,
                 transitions,
+                skipped,
                 name: mode.name.clone(),
                 patterns: mode.patterns.clone(),
                 lookaheads: mode.patterns.iter().try_fold(
@@ -215,6 +313,7 @@ impl TryFrom<Vec<ScannerMode>> for ScannerImpl {
                             acc.push(Some((
                                 l.is_positive,
                                 Regex::builder()
+                                    .syntax(syntax_config())
                                     .build(&l.pattern)
                                     .map_err(|e| ScnrError::new(e.into()))?,
                             )));
@@ -224,43 +323,15 @@ impl TryFrom<Vec<ScannerMode>> for ScannerImpl {
                         Ok::<_, ScnrError>(acc)
                     },
                 )?,
-            };
-            scanner_modes.push(mode);
-        }
-        Ok(Self {
-            scanner_modes,
-            current_mode: 0,
-        })
-    }
-}
-
-impl TryFrom<&[ScannerMode]> for ScannerImpl {
-    type Error = crate::ScnrError;
-    fn try_from(modes: &[ScannerMode]) -> Result<Self> {
-        let mut scanner_modes = Vec::with_capacity(modes.len());
-
-        for mode in modes {
-            let rx = Regex::builder()
-                .build_many(&mode.patterns)
-                .map_err(|e| ScnrError::new(e.into()))?;
-            let mut transitions = Vec::with_capacity(mode.transitions.len());
-            for (terminal, scanner) in &mode.transitions {
-                transitions.push((*terminal, *scanner));
-            }
-            transitions.sort_by_key(|(terminal, _)| *terminal);
-            let mode = ScannerModeRx {
-                rx,
-                transitions,
-                name: mode.name.clone(),
-                patterns: mode.patterns.clone(),
-                lookaheads: mode.patterns.iter().try_fold(
+                lookbehinds: mode.patterns.iter().try_fold(
                     Vec::with_capacity(mode.patterns.len()),
                     |mut acc, p| {
-                        if let Some(l) = p.lookahead() {
+                        if let Some(l) = p.lookbehind() {
                             acc.push(Some((
                                 l.is_positive,
                                 Regex::builder()
-                                    .build(&l.pattern)
+                                    .syntax(syntax_config())
+                                    .build(&format!(r"(?:{})\z", l.pattern))
                                     .map_err(|e| ScnrError::new(e.into()))?,
                             )));
                         } else {
@@ -275,10 +346,124 @@ impl TryFrom<&[ScannerMode]> for ScannerImpl {
         Ok(Self {
             scanner_modes,
             current_mode: 0,
+            mode_stack: ModeStack::default(),
+            unmatched_input: UnmatchedInput::Skip,
+            token_names,
         })
     }
 }
 
+impl TryFrom<Vec<ScannerMode>> for ScannerImpl {
+    type Error = crate::ScnrError;
+    fn try_from(modes: Vec<ScannerMode>) -> Result<Self> {
+        Self::try_from_scanner_modes(&modes, &CompileLimits::default())
+    }
+}
+
+impl TryFrom<&[ScannerMode]> for ScannerImpl {
+    type Error = crate::ScnrError;
+    fn try_from(modes: &[ScannerMode]) -> Result<Self> {
+        Self::try_from_scanner_modes(modes, &CompileLimits::default())
+    }
+}
+
+/// The serialized form of the scanner.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedScanner {
+    scanner_modes: Vec<ScannerMode>,
+    #[serde(default)]
+    unmatched_input: UnmatchedInput,
+}
+
+/// The regexes of `regex_automata` can't be serialized. The scanner is therefore serialized as its
+/// scanner modes and the regexes are built again when it is deserialized.
+impl serde::Serialize for ScannerImpl {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        SerializedScanner {
+            scanner_modes: self
+                .scanner_modes
+                .iter()
+                .map(|mode| ScannerMode {
+                    name: mode.name.clone(),
+                    patterns: mode.patterns.clone(),
+                    transitions: mode.transitions.clone(),
+                })
+                .collect(),
+            unmatched_input: self.unmatched_input,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ScannerImpl {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let SerializedScanner {
+            scanner_modes,
+            unmatched_input,
+        } = SerializedScanner::deserialize(deserializer)?;
+        let mut scanner = Self::try_from(scanner_modes).map_err(serde::de::Error::custom)?;
+        scanner.set_unmatched_input(unmatched_input);
+        Ok(scanner)
+    }
+}
+
+/// The syntax configuration of the regexes.
+/// Patterns may match invalid UTF-8, so that byte escapes like `(?-u:\xFF)` can be used to match
+/// the bytes of a byte haystack.
+fn syntax_config() -> syntax::Config {
+    syntax::Config::new().utf8(false)
+}
+
+/// Checks the counts of all counted repetitions in the patterns of the scanner mode, including
+/// their lookaheads and lookbehinds.
+/// Patterns that can't be parsed are skipped here. They are reported by the regex builder.
+fn check_repetitions(mode: &ScannerMode, limits: &CompileLimits) -> Result<()> {
+    for pattern in &mode.patterns {
+        let sub_patterns = std::iter::once(pattern.pattern())
+            .chain(pattern.lookahead().map(|l| l.pattern()))
+            .chain(pattern.lookbehind().map(|l| l.pattern()));
+        for sub_pattern in sub_patterns {
+            if let Ok(ast) = regex_syntax::ast::parse::Parser::new().parse(sub_pattern) {
+                regex_syntax::ast::visit(&ast, RepetitionChecker(limits))
+                    .map_err(|e| e.with_pattern(pattern.pattern()).with_mode(&mode.name))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A visitor that checks the counts of counted repetitions against the compile limits.
+struct RepetitionChecker<'a>(&'a CompileLimits);
+
+impl regex_syntax::ast::Visitor for RepetitionChecker<'_> {
+    type Output = ();
+    type Err = ScnrError;
+
+    fn finish(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_pre(&mut self, ast: &regex_syntax::ast::Ast) -> Result<()> {
+        use regex_syntax::ast::{Ast, RepetitionKind, RepetitionRange};
+        if let Ast::Repetition(repetition) = ast {
+            if let RepetitionKind::Range(
+                RepetitionRange::Exactly(c)
+                | RepetitionRange::AtLeast(c)
+                | RepetitionRange::Bounded(_, c),
+            ) = repetition.op.kind
+            {
+                self.0.check_repetition(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,202 @@
+//! Module with the versioned serialization of compiled scanners.
+//!
+//! A serialized scanner starts with a header line that names the version of the crate and the
+//! backend that created it. The scanner itself follows as JSON. The compiled automata depend on the
+//! internals of the crate, so a serialized scanner is only loaded by the same version of the crate
+//! with the same backend.
+
+use std::io::{BufRead, BufReader, Read, Write};
+
+use crate::{Result, ScnrError, ScnrErrorKind};
+
+use super::ScannerImpl;
+
+/// The backend that created the scanner.
+#[cfg(not(feature = "regex_automata"))]
+const BACKEND: &str = "dfa";
+#[cfg(feature = "regex_automata")]
+const BACKEND: &str = "regex_automata";
+
+/// The header lines are short. Longer input is not read in search of the end of the header.
+const MAX_HEADER_LEN: u64 = 128;
+
+/// Returns the header of the scanners that are written by this version of the crate.
+fn header() -> String {
+    format!("scnr {} {}", env!("CARGO_PKG_VERSION"), BACKEND)
+}
+
+/// Creates the error for a serialized scanner that can't be loaded.
+pub(crate) fn invalid_scanner(reason: impl Into<String>) -> ScnrError {
+    ScnrError::new(ScnrErrorKind::InvalidSerializedScanner(reason.into()))
+}
+
+/// Writes the header and the scanner to the writer.
+pub(crate) fn write_scanner(scanner: &ScannerImpl, mut writer: impl Write) -> Result<()> {
+    writeln!(writer, "{}", header())?;
+    serde_json::to_writer(&mut writer, scanner)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a scanner from the reader.
+/// An error is returned if the header doesn't match the one of this version of the crate or if
+/// the scanner is inconsistent.
+pub(crate) fn read_scanner(reader: impl Read) -> Result<ScannerImpl> {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    (&mut reader)
+        .take(MAX_HEADER_LEN)
+        .read_until(b'\n', &mut line)?;
+    let expected = header();
+    let found = String::from_utf8_lossy(line.trim_ascii_end());
+    if found != expected {
+        return Err(if found.starts_with("scnr ") {
+            invalid_scanner(format!(
+                "it was created by '{}' and can't be loaded by '{}'",
+                found, expected
+            ))
+        } else {
+            invalid_scanner("the header is missing")
+        });
+    }
+    let scanner: ScannerImpl = serde_json::from_reader(reader)?;
+    #[cfg(not(feature = "regex_automata"))]
+    scanner.validate()?;
+    Ok(scanner)
+}
+
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+    use crate::{Pattern, Scanner, ScannerMode, UnmatchedInput};
+
+    #[test]
+    fn test_incompatible_header() {
+        let scanner = ScannerImpl::try_from(vec![ScannerMode::new(
+            "INITIAL",
+            vec![Pattern::new("[a-z]+".to_string(), 0)],
+            vec![],
+        )])
+        .unwrap();
+        let mut bytes = Vec::new();
+        write_scanner(&scanner, &mut bytes).unwrap();
+        let payload = &bytes[header().len()..];
+
+        let other_version = [b"scnr 0.0.1 ".as_slice(), BACKEND.as_bytes(), payload].concat();
+        let error = read_scanner(other_version.as_slice()).unwrap_err();
+        assert!(
+            matches!(&*error.source, ScnrErrorKind::InvalidSerializedScanner(reason)
+                if reason.contains("scnr 0.0.1")),
+            "{}",
+            error
+        );
+
+        let other_backend = [
+            format!("scnr {} other", env!("CARGO_PKG_VERSION")).as_bytes(),
+            payload,
+        ]
+        .concat();
+        assert!(read_scanner(other_backend.as_slice()).is_err());
+
+        let error = read_scanner(&payload[1..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid serialized scanner: the header is missing"
+        );
+    }
+
+    #[test]
+    fn test_unmatched_input_is_serialized() {
+        let scanner = ScannerImpl::try_from(vec![ScannerMode::new(
+            "INITIAL",
+            vec![Pattern::new("[a-z]+".to_string(), 0)],
+            vec![],
+        )])
+        .unwrap();
+        let mut erroring = scanner.clone();
+        erroring.set_unmatched_input(UnmatchedInput::Error);
+        let mut bytes = Vec::new();
+        write_scanner(&erroring, &mut bytes).unwrap();
+        let loaded = read_scanner(bytes.as_slice()).unwrap();
+        assert_eq!(loaded.unmatched_input(), UnmatchedInput::Error);
+        let mut find_iter = Scanner { inner: loaded }.find_iter("1a");
+        let error = find_iter.try_next_match().unwrap_err();
+        assert!(
+            matches!(
+                &*error.source,
+                ScnrErrorKind::UnmatchedInput { offset: 0, .. }
+            ),
+            "{}",
+            error
+        );
+
+        // Scanners that were written without the policy skip unmatched input.
+        #[cfg(not(feature = "regex_automata"))]
+        {
+            let mut value = serde_json::to_value(&erroring).unwrap();
+            value.as_object_mut().unwrap().remove("unmatched_input");
+            let bytes = format!("{}\n{}", header(), value);
+            let loaded = read_scanner(bytes.as_bytes()).unwrap();
+            assert_eq!(loaded.unmatched_input(), UnmatchedInput::Skip);
+        }
+    }
+
+    #[cfg(not(feature = "regex_automata"))]
+    #[test]
+    fn test_inconsistent_scanner() {
+        let scanner = ScannerImpl::try_from(vec![ScannerMode::new(
+            "INITIAL",
+            vec![Pattern::new("[a-z]+".to_string(), 0)],
+            vec![],
+        )])
+        .unwrap();
+        let mut value = serde_json::to_value(&scanner).unwrap();
+        value["scanner_modes"][0]["dfa"]["transitions"][0] = 1000.into();
+        let bytes = format!("{}\n{}", header(), value);
+        let error = read_scanner(bytes.as_bytes()).unwrap_err();
+        assert!(
+            matches!(&*error.source, ScnrErrorKind::InvalidSerializedScanner(_)),
+            "{}",
+            error
+        );
+
+        // A transition to a scanner mode that doesn't exist.
+        let scanner = ScannerImpl::try_from(vec![ScannerMode::new(
+            "INITIAL",
+            vec![
+                Pattern::new("a".to_string(), 0),
+                Pattern::new("b".to_string(), 1).with_skip(true),
+            ],
+            vec![(1, 0)],
+        )])
+        .unwrap();
+        let value = serde_json::to_value(&scanner).unwrap();
+        let bytes = format!("{}\n{}", header(), value);
+        assert!(read_scanner(bytes.as_bytes()).is_ok());
+        let mut bad_target = value.clone();
+        bad_target["scanner_modes"][0]["transitions"][0][1] = 99.into();
+        let bytes = format!("{}\n{}", header(), bad_target);
+        let error = read_scanner(bytes.as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid serialized scanner: a transition leads to a missing scanner mode in scanner \
+            mode 'INITIAL'"
+        );
+
+        // Skipped token types that are not sorted.
+        let mut unsorted = value.clone();
+        unsorted["scanner_modes"][0]["skipped"] = serde_json::json!([1, 0]);
+        let bytes = format!("{}\n{}", header(), unsorted);
+        assert!(read_scanner(bytes.as_bytes()).is_err());
+
+        // A name that is used for two token types.
+        let mut names = value;
+        names["token_names"]["names"] = serde_json::json!([[0, "A"], [1, "A"]]);
+        let bytes = format!("{}\n{}", header(), names);
+        let error = read_scanner(bytes.as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid serialized scanner: the token names are inconsistent"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Module with the registry of the token names of a scanner.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "compiler")]
+use crate::ScannerMode;
+use crate::{Result, ScnrError, ScnrErrorKind};
+
+/// The names of the token types of a scanner.
+///
+/// Only token types whose patterns are given a name are registered. The registry is shared by all
+/// scanner modes, because the token types are shared by them, too.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct TokenNames {
+    /// The token types and their names, sorted by token type.
+    names: Vec<(usize, String)>,
+}
+
+impl TokenNames {
+    /// Collects the names of the patterns of all scanner modes.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn try_from_scanner_modes(scanner_modes: &[ScannerMode]) -> Result<Self> {
+        Self::try_from_names(
+            scanner_modes
+                .iter()
+                .flat_map(|scanner_mode| scanner_mode.patterns.iter())
+                .filter_map(|pattern| pattern.name().map(|name| (pattern.terminal_id(), name))),
+        )
+    }
+
+    /// Creates the registry from pairs of token type and name.
+    /// A pair may occur several times, but a token type must not have different names and a name
+    /// must not be used for different token types.
+    pub(crate) fn try_from_names<'a>(
+        names: impl IntoIterator<Item = (usize, &'a str)>,
+    ) -> Result<Self> {
+        let mut token_names = Self::default();
+        for (token_type, name) in names {
+            match token_names
+                .names
+                .binary_search_by_key(&token_type, |(t, _)| *t)
+            {
+                Ok(i) if token_names.names[i].1 == name => {}
+                Err(i) if token_names.token_type(name).is_none() => {
+                    token_names.names.insert(i, (token_type, name.to_string()));
+                }
+                _ => {
+                    return Err(ScnrError::new(ScnrErrorKind::TokenNameConflict {
+                        name: name.to_string(),
+                        token_type,
+                    }))
+                }
+            }
+        }
+        Ok(token_names)
+    }
+
+    /// Returns the name of the token type if it has one.
+    pub(crate) fn name(&self, token_type: usize) -> Option<&str> {
+        self.names
+            .binary_search_by_key(&token_type, |(t, _)| *t)
+            .ok()
+            .map(|i| self.names[i].1.as_str())
+    }
+
+    /// Returns the token type with the given name.
+    pub(crate) fn token_type(&self, name: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .find(|(_, n)| n == name)
+            .map(|(token_type, _)| *token_type)
+    }
+
+    /// Checks the consistency of deserialized token names. The token types must be sorted and
+    /// unique and a name must not be used for different token types.
+    #[cfg(all(feature = "serde", not(feature = "regex_automata")))]
+    pub(crate) fn validate(&self) -> Result<()> {
+        let sorted = self.names.windows(2).all(|w| w[0].0 < w[1].0);
+        let unique = self
+            .names
+            .iter()
+            .enumerate()
+            .all(|(i, (_, name))| self.names[..i].iter().all(|(_, n)| n != name));
+        if sorted && unique {
+            Ok(())
+        } else {
+            Err(super::serialization::invalid_scanner(
+                "the token names are inconsistent",
+            ))
+        }
+    }
+
+    /// Describes the token type for diagnostics, e.g. `17 (LBrace)` or `17` without a name.
+    pub(crate) fn describe(&self, token_type: usize) -> String {
+        match self.name(token_type) {
+            Some(name) => format!("{} ({})", token_type, name),
+            None => token_type.to_string(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+    use crate::Pattern;
+
+    #[test]
+    fn test_token_names() {
+        let scanner_modes = [
+            ScannerMode::new(
+                "INITIAL",
+                vec![
+                    Pattern::new(r"\{".to_string(), 3).with_name("LBrace"),
+                    Pattern::new(r"[a-z]+".to_string(), 1),
+                    Pattern::new("\"".to_string(), 7).with_name("Quote"),
+                ],
+                vec![],
+            ),
+            ScannerMode::new(
+                "STRING",
+                vec![Pattern::new("\"".to_string(), 7).with_name("Quote")],
+                vec![],
+            ),
+        ];
+        let token_names = TokenNames::try_from_scanner_modes(&scanner_modes).unwrap();
+        assert_eq!(
+            token_names.names,
+            [(3, "LBrace".to_string()), (7, "Quote".to_string())]
+        );
+        assert_eq!(token_names.name(3), Some("LBrace"));
+        assert_eq!(token_names.name(1), None);
+        assert_eq!(token_names.token_type("Quote"), Some(7));
+        assert_eq!(token_names.token_type("RBrace"), None);
+        assert_eq!(token_names.describe(3), "3 (LBrace)");
+        assert_eq!(token_names.describe(1), "1");
+    }
+
+    #[test]
+    fn test_token_name_conflicts() {
+        for names in [
+            [(3, "LBrace"), (3, "Brace")],
+            [(3, "LBrace"), (4, "LBrace")],
+        ] {
+            let error = TokenNames::try_from_names(names).unwrap_err();
+            assert!(
+                matches!(*error.source, ScnrErrorKind::TokenNameConflict { .. }),
+                "{}",
+                error
+            );
+        }
+    }
+}
@@ -131,29 +131,67 @@
 //!
 //! Both features are mutually exclusive. You can enable one of them, but not both at the same time.
 //!
+//! - `derive`: This feature is enabled by default. It provides the derive macro for the [Tokens]
+//!   trait.
+//!
+//! - `compiler`: This feature is enabled by default. It contains the regex compiler, i.e. the
+//!   `ScannerBuilder` and the conversion of scanner modes into a [Scanner]. Without it a scanner
+//!   can only be created from the tables of a generated scanner, see [codegen], or by
+//!   deserialization.
+//!
 //! Enabling the default feature usually results in a slower scanner, but it is faster at compiling
 //! the regexes. The `regex_automata` feature is faster at scanning the input, but it is possibly
 //! slower at compiling the regexes. This depends on the size of your scanner modes, i.e. the number
 //! of regexes you use.
 
+/// Module that provides a BufferedMatches type
+mod buffered_matches;
+pub use buffered_matches::BufferedMatches;
+
+/// Module with the generation of Rust source code for compiled scanners
+#[cfg(not(feature = "regex_automata"))]
+pub mod codegen;
+
+/// Module with the units in which the columns of positions are counted
+mod column_unit;
+pub use column_unit::ColumnUnit;
+
+/// Module with the limits that are checked during the compilation of a scanner
+mod compile_limits;
+pub use compile_limits::{CompileLimit, CompileLimits};
+
 /// Module with error definitions
 mod errors;
 pub use errors::{Result, ScnrError, ScnrErrorKind};
 
-/// Module that provides a FindMatches type
+/// Module that provides a FindMatches type and a Checkpoint type
 mod find_matches;
-pub use find_matches::{FindMatches, PeekResult};
+pub use find_matches::{Checkpoint, FindMatches, PeekResult};
+
+/// Module that provides a FindTokens type
+mod find_tokens;
+pub use find_tokens::FindTokens;
+
+/// Module that provides the incremental re-tokenization of edited texts
+#[cfg(not(feature = "regex_automata"))]
+mod incremental;
+#[cfg(not(feature = "regex_automata"))]
+pub use incremental::{TextEdit, TokenWithMode};
 
 /// The module with internal implementation details.
 mod internal;
 
-/// Module that provides a Match type
+/// Module that provides an index of the lines of a haystack
+mod line_index;
+pub use line_index::{LineIndex, LineTerminators};
+
+/// Module that provides a Match type and a Token type
 mod match_type;
-pub use match_type::{Match, MatchExt};
+pub use match_type::{Match, MatchExt, Token};
 
-/// Module that provides a Pattern type and a Lookahead type
+/// Module that provides a Pattern type, a Lookahead type and a Lookbehind type
 mod pattern;
-pub use pattern::{Lookahead, Pattern};
+pub use pattern::{Lookahead, Lookbehind, Pattern};
 
 /// Module that provides a position type
 mod position;
@@ -164,17 +202,41 @@ mod scanner;
 pub use scanner::{Scanner, ScannerModeSwitcher};
 
 /// The module with the scanner builder.
+#[cfg(feature = "compiler")]
 mod scanner_builder;
+#[cfg(feature = "compiler")]
 pub use scanner_builder::ScannerBuilder;
 
 /// The module with the scanner mode.
 mod scanner_mode;
-pub use scanner_mode::ScannerMode;
+pub use scanner_mode::{ModeTransition, ScannerMode};
 
 /// Module that provides a Span type
 mod span;
 pub use span::Span;
 
+/// Module that provides a StreamMatches type
+#[cfg(not(feature = "regex_automata"))]
+mod stream_matches;
+#[cfg(not(feature = "regex_automata"))]
+pub use stream_matches::StreamMatches;
+
+/// Module that provides the Tokens trait and a TypedMatches type
+mod tokens;
+pub use tokens::{Tokens, TypedMatches};
+
+/// The derive macro for the Tokens trait
+#[cfg(feature = "derive")]
+pub use scnr_derive::Tokens;
+
+/// Module with helpers for the unit tests
+#[cfg(all(test, feature = "compiler"))]
+mod test_support;
+
+/// Module with the policy for unmatched input
+mod unmatched_input;
+pub use unmatched_input::UnmatchedInput;
+
 /// Module that provides a WithPositions type
 mod with_positions;
 pub use with_positions::{MatchExtIterator, WithPositions};
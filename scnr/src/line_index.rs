@@ -0,0 +1,187 @@
+//! Module with an index of the line starts of a haystack.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::Position;
+
+/// The sequences that terminate a line.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LineTerminators {
+    /// Only `\n` terminates a line. A line that ends with `\r\n` also ends after the `\n`.
+    #[default]
+    Lf,
+    /// `\n`, `\r\n` and a single `\r` terminate a line.
+    Ascii,
+    /// In addition to the terminators of [LineTerminators::Ascii] the next line character NEL
+    /// (U+0085), the line separator (U+2028) and the paragraph separator (U+2029) terminate a line.
+    Unicode,
+}
+
+impl LineTerminators {
+    /// Returns the length in bytes of the line terminator at the start of the given bytes or zero
+    /// if the bytes don't start with a line terminator.
+    fn terminator_len(self, bytes: &[u8]) -> usize {
+        match (self, bytes) {
+            (_, [b'\n', ..]) => 1,
+            (LineTerminators::Lf, _) => 0,
+            (_, [b'\r', b'\n', ..]) => 2,
+            (_, [b'\r', ..]) => 1,
+            (LineTerminators::Unicode, [0xC2, 0x85, ..]) => 2,
+            (LineTerminators::Unicode, [0xE2, 0x80, 0xA8 | 0xA9, ..]) => 3,
+            _ => 0,
+        }
+    }
+}
+
+/// An index of the start offsets of all lines of a haystack.
+///
+/// The index maps byte offsets to positions and back. In contrast to the line offsets that a
+/// [crate::FindMatches] iterator records while it advances, the index is complete, so it returns
+/// the correct position for every offset of the haystack, also for offsets the scanner hasn't
+/// passed yet. The columns of the positions are counted in bytes.
+///
+/// An iterator can use the index with [crate::FindMatches::with_line_index]. The index can be
+/// shared by several iterators over the same haystack.
+///
+/// ```rust
+/// use scnr::{LineIndex, LineTerminators, Position};
+///
+/// let line_index = LineIndex::new("ab\r\ncd\ref", LineTerminators::Ascii);
+/// assert_eq!(line_index.line_count(), 3);
+/// assert_eq!(line_index.position(5), Position::new(2, 2));
+/// assert_eq!(line_index.offset(Position::new(3, 2)), Some(8));
+/// assert_eq!(line_index.offset(Position::new(4, 1)), None);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// The start offsets of the lines in ascending order. The first line starts at offset zero.
+    line_starts: Vec<usize>,
+    /// The length of the haystack in bytes.
+    len: usize,
+    /// The line terminators the index was built with.
+    terminators: LineTerminators,
+}
+
+impl LineIndex {
+    /// Creates the index of the lines of the given haystack, which can be a string or a byte slice.
+    pub fn new<T: AsRef<[u8]> + ?Sized>(input: &T, terminators: LineTerminators) -> Self {
+        let bytes = input.as_ref();
+        let mut line_starts = vec![0];
+        let mut i = 0;
+        while i < bytes.len() {
+            match terminators.terminator_len(&bytes[i..]) {
+                0 => i += 1,
+                len => {
+                    i += len;
+                    line_starts.push(i);
+                }
+            }
+        }
+        Self {
+            line_starts,
+            len: bytes.len(),
+            terminators,
+        }
+    }
+
+    /// Returns the number of lines. A haystack that ends with a line terminator has an empty last
+    /// line, and an empty haystack has one line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the length of the indexed haystack in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the indexed haystack is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the line terminators the index was built with.
+    pub fn terminators(&self) -> LineTerminators {
+        self.terminators
+    }
+
+    /// Returns the start offset of the line with the given 1-based number.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        line.checked_sub(1)
+            .and_then(|i| self.line_starts.get(i))
+            .copied()
+    }
+
+    /// Returns the position of the given offset.
+    /// An offset greater than the length of the haystack is treated as an offset on the last line.
+    pub fn position(&self, offset: usize) -> Position {
+        let (line, line_start) = self.line_of(offset);
+        Position::new(line, offset - line_start + 1)
+    }
+
+    /// Returns the offset of the given position or `None` if the position lies outside of the
+    /// haystack.
+    ///
+    /// The column may point behind the line terminator, i.e. to the start of the next line, which
+    /// is the end position of a match that ends with a line terminator.
+    pub fn offset(&self, position: Position) -> Option<usize> {
+        let line_start = self.line_start(position.line)?;
+        let line_end = self.line_start(position.line + 1).unwrap_or(self.len);
+        let offset = line_start + position.column.checked_sub(1)?;
+        (offset <= line_end).then_some(offset)
+    }
+
+    /// Returns the 1-based number of the line that contains the given offset and the start offset
+    /// of that line.
+    pub(crate) fn line_of(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        (line, self.line_starts[line - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_terminators() {
+        let input = "a\nb\r\nc\rd\u{85}e\u{2028}f\u{2029}g";
+        let line_starts = |terminators| LineIndex::new(input, terminators).line_starts;
+        assert_eq!(line_starts(LineTerminators::Lf), [0, 2, 5]);
+        assert_eq!(line_starts(LineTerminators::Ascii), [0, 2, 5, 7]);
+        assert_eq!(
+            line_starts(LineTerminators::Unicode),
+            [0, 2, 5, 7, 10, 14, 18]
+        );
+        assert_eq!(LineIndex::new("", LineTerminators::Lf).line_count(), 1);
+        assert_eq!(LineIndex::new("a\n", LineTerminators::Lf).line_count(), 2);
+        // Invalid UTF-8 doesn't disturb the index.
+        assert_eq!(
+            LineIndex::new(b"\xC2\n\xE2\x80\r", LineTerminators::Unicode).line_starts,
+            [0, 2, 5]
+        );
+    }
+
+    #[test]
+    fn test_position_and_offset() {
+        let input = "ab\r\n\r\ncd";
+        let line_index = LineIndex::new(input, LineTerminators::Ascii);
+        for offset in 0..=input.len() {
+            let position = line_index.position(offset);
+            assert_eq!(line_index.offset(position), Some(offset), "{}", position);
+        }
+        assert_eq!(line_index.position(3), Position::new(1, 4));
+        assert_eq!(line_index.position(4), Position::new(2, 1));
+        assert_eq!(line_index.position(20), Position::new(3, 15));
+        // The end of a line terminator is also addressed by the column behind it.
+        assert_eq!(line_index.offset(Position::new(1, 5)), Some(4));
+        assert_eq!(line_index.offset(Position::new(1, 6)), None);
+        assert_eq!(line_index.offset(Position::new(3, 4)), None);
+        assert_eq!(line_index.offset(Position { line: 0, column: 1 }), None);
+        assert_eq!(line_index.line_start(3), Some(6));
+        assert_eq!(line_index.line_start(0), None);
+    }
+}
@@ -6,18 +6,21 @@ use crate::Position;
 use super::Span;
 
 /// A match in the haystack.
+///
+/// The token type is a number by default. Iterators over types that implement [crate::Tokens]
+/// yield matches with the typed token type instead, see [crate::FindMatches::typed].
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct Match {
-    /// The token type number associated with the match.
-    token_type: usize,
+pub struct Match<T = usize> {
+    /// The token type associated with the match.
+    token_type: T,
     /// The underlying match span.
     span: Span,
 }
 
-impl Match {
+impl<T: Copy> Match<T> {
     /// Create a new match.
-    pub fn new(token_type: usize, span: Span) -> Self {
+    pub fn new(token_type: T, span: Span) -> Self {
         Self { token_type, span }
     }
 
@@ -59,7 +62,7 @@ impl Match {
 
     /// Get the token type of the match.
     #[inline]
-    pub fn token_type(&self) -> usize {
+    pub fn token_type(&self) -> T {
         self.token_type
     }
 
@@ -172,3 +175,120 @@ impl MatchExt {
         self.end_position
     }
 }
+
+/// A match together with the matched text and the line and column information of its start and
+/// end positions.
+///
+/// Tokens are yielded by the iterator that is created with [crate::Scanner::find_tokens]. The text
+/// is borrowed from the haystack, so the haystack doesn't need to be kept around separately to
+/// access the text of a match.
+///
+/// With the feature `serde` tokens can be serialized and deserialized. The text is borrowed from
+/// the serialized data on deserialization, so formats that need to unescape strings can only
+/// deserialize texts without escape sequences.
+///
+/// * `'h` represents the lifetime of the haystack being searched.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Token<'h> {
+    /// The token type number associated with the match.
+    token_type: usize,
+    /// The underlying match span.
+    span: Span,
+    /// The position of the start of the match.
+    start_position: Position,
+    /// The position of the end of the match.
+    /// The end position is exclusive.
+    end_position: Position,
+    /// The matched text.
+    text: &'h str,
+}
+
+impl<'h> Token<'h> {
+    pub(crate) fn new(
+        token_type: usize,
+        span: Span,
+        start_position: Position,
+        end_position: Position,
+        text: &'h str,
+    ) -> Self {
+        Self {
+            token_type,
+            span,
+            start_position,
+            end_position,
+            text,
+        }
+    }
+
+    /// Get the start of the match.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.span.start
+    }
+
+    /// Get the end of the match.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.span.end
+    }
+
+    /// Get the span of the match.
+    #[inline]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Get the span as range
+    #[inline]
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.span.range()
+    }
+
+    /// Get the length of the match.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.span.len()
+    }
+
+    /// Check if the match is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.span.is_empty()
+    }
+
+    /// Get the token type of the match.
+    #[inline]
+    pub fn token_type(&self) -> usize {
+        self.token_type
+    }
+
+    /// Get the start position of the match.
+    #[inline]
+    pub fn start_position(&self) -> Position {
+        self.start_position
+    }
+
+    /// Get the end position of the match.
+    #[inline]
+    pub fn end_position(&self) -> Position {
+        self.end_position
+    }
+
+    /// Get the matched text.
+    #[inline]
+    pub fn text(&self) -> &'h str {
+        self.text
+    }
+}
+
+impl From<Token<'_>> for MatchExt {
+    fn from(token: Token<'_>) -> Self {
+        MatchExt::new(
+            token.token_type,
+            token.span,
+            token.start_position,
+            token.end_position,
+        )
+    }
+}
@@ -60,10 +60,72 @@ impl std::fmt::Display for Lookahead {
     }
 }
 
+/// A lookbehind is a regular expression that restricts a match of a pattern so that it must be
+/// matched before the pattern.
+///
+/// If the lookbehind is negative, it must not be matched before the pattern.
+///
+/// With the help of a positive lookbehind you can define a semantic like
+/// ```text
+/// match pattern R only if it is preceded by pattern S
+/// ```
+/// On the other hand with a negative lookbehind you can define a semantic like
+/// ```text
+/// match pattern R only if it is NOT preceded by pattern S
+/// ```
+///
+/// The lookbehind pattern `S` must match a suffix of the text before the match start. It is matched
+/// against this text only, i.e. zero-width assertions at its end see the end of this text.
+///
+/// The lookbehind is an optional member of the [crate::Pattern] struct.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lookbehind {
+    /// If the lookbehind is positive.
+    pub is_positive: bool,
+    /// The lookbehind pattern.
+    pub pattern: String,
+}
+
+impl Lookbehind {
+    /// Create a new lookbehind.
+    pub fn new(is_positive: bool, pattern: String) -> Self {
+        Self {
+            is_positive,
+            pattern,
+        }
+    }
+
+    /// Get the pattern.
+    #[inline]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Check if the lookbehind is positive.
+    #[inline]
+    pub fn is_positive(&self) -> bool {
+        self.is_positive
+    }
+}
+
+impl std::fmt::Display for Lookbehind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_positive {
+            write!(f, "(?<={})", self.pattern.escape_default())
+        } else {
+            write!(f, "(?<!{})", self.pattern.escape_default())
+        }
+    }
+}
+
 /// A pattern that is used to match the input.
 /// The pattern is represented by a regular expression and a token type number.
 /// The token type number is used to identify the pattern in the scanner.
-/// The pattern also has an optional [Lookahead].
+/// The pattern also has an optional [Lookahead] and an optional [Lookbehind].
+/// Matches of a pattern that is marked as skipped are not returned by the scanner, see
+/// [Pattern::with_skip].
+/// The token type can be given a name, see [Pattern::with_name].
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct Pattern {
@@ -71,6 +133,18 @@ pub struct Pattern {
     token_type: usize,
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     lookahead: Option<Lookahead>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    lookbehind: Option<Lookbehind>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    skip: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    name: Option<String>,
 }
 
 impl Pattern {
@@ -80,6 +154,9 @@ impl Pattern {
             pattern,
             token_type,
             lookahead: None,
+            lookbehind: None,
+            skip: false,
+            name: None,
         }
     }
 
@@ -91,9 +168,37 @@ impl Pattern {
     /// Create a new pattern with lookahead.
     pub fn with_lookahead(self, lookahead: Lookahead) -> Self {
         Self {
-            pattern: self.pattern,
-            token_type: self.token_type,
             lookahead: Some(lookahead),
+            ..self
+        }
+    }
+
+    /// Create a new pattern with lookbehind.
+    pub fn with_lookbehind(self, lookbehind: Lookbehind) -> Self {
+        Self {
+            lookbehind: Some(lookbehind),
+            ..self
+        }
+    }
+
+    /// Create a new pattern that is marked as skipped or not.
+    ///
+    /// The matches of a skipped pattern, like whitespace or comments, are consumed by the scanner
+    /// but not returned by [crate::FindMatches] and not counted by [crate::FindMatches::peek_n].
+    /// Mode transitions on the token type are still applied.
+    pub fn with_skip(self, skip: bool) -> Self {
+        Self { skip, ..self }
+    }
+
+    /// Create a new pattern whose token type has the given name.
+    ///
+    /// The names of the token types are available through [crate::Scanner::token_name] and
+    /// [crate::Scanner::token_type_by_name] and are used in diagnostics. Patterns with the same
+    /// token type must have the same name and a name must not be used for different token types.
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
         }
     }
 
@@ -103,6 +208,18 @@ impl Pattern {
         &self.pattern
     }
 
+    /// Get the name of the token type.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Check if the pattern is marked as skipped.
+    #[inline]
+    pub fn is_skip(&self) -> bool {
+        self.skip
+    }
+
     /// Get the token type.
     #[inline]
     pub fn terminal_id(&self) -> usize {
@@ -114,10 +231,19 @@ impl Pattern {
     pub fn lookahead(&self) -> Option<&Lookahead> {
         self.lookahead.as_ref()
     }
+
+    /// Get the lookbehind.
+    #[inline]
+    pub fn lookbehind(&self) -> Option<&Lookbehind> {
+        self.lookbehind.as_ref()
+    }
 }
 
 impl std::fmt::Display for Pattern {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(lookbehind) = &self.lookbehind {
+            write!(f, "{}", lookbehind)?
+        }
         write!(f, "{}", self.pattern.escape_default())?;
         if let Some(lookahead) = &self.lookahead {
             write!(f, "{}", lookahead)?
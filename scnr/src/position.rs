@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 /// A position in the haystack.
 /// The position is represented by a line and column number.
 /// The line and column numbers are 1-based.
+/// The column is counted in bytes by default, see [crate::ColumnUnit] for other units.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Position {
@@ -5,9 +5,18 @@ use std::fmt::Debug;
 
 use log::trace;
 
-use crate::internal::ScannerImpl;
+use crate::internal::{Haystack, ScannerImpl};
 
-use crate::{FindMatches, Result, ScannerMode};
+#[cfg(any(feature = "compiler", feature = "serde", feature = "dot_writer"))]
+use crate::Result;
+#[cfg(feature = "compiler")]
+use crate::ScannerMode;
+use crate::{FindMatches, FindTokens, UnmatchedInput};
+
+#[cfg(not(feature = "regex_automata"))]
+use crate::{incremental, StreamMatches, TextEdit, TokenWithMode};
+#[cfg(not(feature = "regex_automata"))]
+use std::ops::Range;
 
 /// A trait to switch between scanner modes.
 ///
@@ -52,6 +61,14 @@ pub trait ScannerModeSwitcher {
     fn current_mode(&self) -> usize;
     /// Returns the name of the scanner mode with the given index.
     fn mode_name(&self, index: usize) -> Option<&str>;
+    /// Returns the number of scanner modes on the mode stack, i.e. the number of push transitions
+    /// that are not yet popped, see [crate::ModeTransition]. Setting the scanner mode doesn't
+    /// change the mode stack.
+    ///
+    /// The default implementation returns 0 for implementors without a mode stack.
+    fn mode_stack_depth(&self) -> usize {
+        0
+    }
 }
 
 /// A Scanner.
@@ -72,10 +89,222 @@ pub struct Scanner {
 }
 
 impl Scanner {
+    /// Sets the handling of input that no pattern of the current scanner mode matches.
+    /// The default is [UnmatchedInput::Skip].
+    ///
+    /// Scanners that are built by the [crate::ScannerBuilder] get the policy of the builder.
+    /// This method is useful for scanners that are loaded or generated.
+    pub fn with_unmatched_input(mut self, unmatched_input: UnmatchedInput) -> Self {
+        self.inner.set_unmatched_input(unmatched_input);
+        self
+    }
+
+    /// Returns the name of the token type if its patterns are given a name, see
+    /// [crate::Pattern::with_name].
+    ///
+    /// ```rust
+    /// use scnr::{Pattern, ScannerBuilder, ScannerMode};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_scanner_mode(ScannerMode::new(
+    ///         "INITIAL",
+    ///         vec![
+    ///             Pattern::new(r"\{".to_string(), 17).with_name("LBrace"),
+    ///             Pattern::new(r"\s+".to_string(), 1),
+    ///         ],
+    ///         vec![],
+    ///     ))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(scanner.token_name(17), Some("LBrace"));
+    /// assert_eq!(scanner.token_name(1), None);
+    /// assert_eq!(scanner.token_type_by_name("LBrace"), Some(17));
+    /// ```
+    pub fn token_name(&self, token_type: usize) -> Option<&str> {
+        self.inner.token_names().name(token_type)
+    }
+
+    /// Returns the token type with the given name, see [Scanner::token_name].
+    pub fn token_type_by_name(&self, name: &str) -> Option<usize> {
+        self.inner.token_names().token_type(name)
+    }
+
     /// Returns an iterator over all non-overlapping matches.
     /// The iterator yields a [`crate::Match`] value until no more matches could be found.
     pub fn find_iter<'h>(&self, input: &'h str) -> FindMatches<'h> {
-        FindMatches::new(self.inner.clone(), input)
+        FindMatches::new(self.inner.clone(), Haystack::Str(input))
+    }
+
+    /// Returns an iterator over all non-overlapping matches that yields [crate::Token] values.
+    /// In addition to the token type and the span, a token provides the matched text and the
+    /// positions of the match, see [FindTokens].
+    pub fn find_tokens<'h>(&self, input: &'h str) -> FindTokens<'h> {
+        FindTokens::new(self.find_iter(input), input)
+    }
+
+    /// Returns an iterator over all non-overlapping matches in a byte slice.
+    /// The iterator yields a [`crate::Match`] value until no more matches could be found.
+    ///
+    /// The input is decoded as UTF-8 where possible. Each byte that is not part of a valid UTF-8
+    /// sequence is treated as a single character that can be matched by a byte literal or a byte
+    /// range with Unicode disabled, e.g. `(?-u:\xFF)` or `(?-u:[\x80-\xFF])`.
+    /// The spans of the matches are byte offsets into the input.
+    ///
+    /// ```rust
+    /// use scnr::ScannerBuilder;
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_patterns([r"[a-z]+", r"(?-u:[\x80-\xFF])"])
+    ///     .build()
+    ///     .unwrap();
+    /// let input = b"caf\xE9 ol\xE9";
+    /// let matches = scanner
+    ///     .find_iter_bytes(input)
+    ///     .map(|m| (m.token_type(), m.span().range()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(matches, vec![(0, 0..3), (1, 3..4), (0, 5..7), (1, 7..8)]);
+    /// ```
+    pub fn find_iter_bytes<'h>(&self, input: &'h [u8]) -> FindMatches<'h> {
+        FindMatches::new(self.inner.clone(), Haystack::Bytes(input))
+    }
+
+    /// Creates an iterator over all non-overlapping matches in the input that is read from the
+    /// reader. The input is read in chunks into a bounded buffer, so it doesn't need to fit into
+    /// memory. The iterator yields a [Result] for each match, because reading the input can fail.
+    /// See [StreamMatches] for details.
+    ///
+    /// This function is not available with the feature `regex_automata`.
+    ///
+    /// ```rust
+    /// use scnr::ScannerBuilder;
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_patterns([r"[a-z]+", r"\s+"])
+    ///     .build()
+    ///     .unwrap();
+    /// let input = std::io::Cursor::new("stream of words");
+    /// let spans = scanner
+    ///     .find_iter_reader(input)
+    ///     .map(|m| m.map(|m| m.span().range()))
+    ///     .collect::<scnr::Result<Vec<_>>>()
+    ///     .unwrap();
+    /// assert_eq!(spans, vec![0..6, 6..7, 7..9, 9..10, 10..15]);
+    /// ```
+    #[cfg(not(feature = "regex_automata"))]
+    pub fn find_iter_reader<R: std::io::Read>(&self, reader: R) -> StreamMatches<R> {
+        StreamMatches::new(self.inner.clone(), reader)
+    }
+
+    /// Scans the input and returns all matches together with the scanner modes in which they were
+    /// found. The result can be updated after an edit of the input with [Scanner::retokenize].
+    ///
+    /// In contrast to [Scanner::find_iter] the matches of patterns that are marked as skipped are
+    /// included, because an editor usually needs them, e.g. to highlight comments.
+    /// Runs of unmatched input are included as tokens if the scanner is configured with
+    /// [UnmatchedInput::Token]. Otherwise they are skipped, also with [UnmatchedInput::Error].
+    #[cfg(not(feature = "regex_automata"))]
+    pub fn tokenize(&self, input: &str) -> Vec<TokenWithMode> {
+        incremental::tokenize(self.inner.clone(), input)
+    }
+
+    /// Updates the tokens of a text after an edit, e.g. in an editor.
+    ///
+    /// * `text` - The text after the edit.
+    /// * `tokens` - The tokens of the text before the edit, as returned by [Scanner::tokenize] or
+    ///   by a previous call of this function. They are updated in place.
+    /// * `edit` - The edit that turned the old text into `text`.
+    ///
+    /// The scan restarts at the first token whose scan has read the edited input, see
+    /// [TokenWithMode::scan_end]. It stops as soon as a token is found behind the edit at the
+    /// position, in the scanner mode and with the mode stack of an old token, because from there
+    /// on the scanner finds the old tokens again. Their spans are moved by the length difference of
+    /// the edit. Old tokens whose lookbehinds have read back into the edit are scanned again, see
+    /// [TokenWithMode::scan_start].
+    ///
+    /// Returns the range of the re-scanned tokens in the updated vector.
+    ///
+    /// Scanner mode switches that a parser applies with [ScannerModeSwitcher::set_mode] are not
+    /// known here.
+    ///
+    /// ```rust
+    /// use scnr::{ScannerBuilder, TextEdit};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_patterns([r"[a-z]+", r"\s+", r"[0-9]+"])
+    ///     .build()
+    ///     .unwrap();
+    /// let mut tokens = scanner.tokenize("let a be 12");
+    /// // Replace "a" with "b2".
+    /// let changed = scanner.retokenize("let b2 be 12", &mut tokens, &TextEdit::new(4..5, "b2"));
+    /// assert_eq!(changed, 1..5);
+    /// assert_eq!(tokens, scanner.tokenize("let b2 be 12"));
+    /// ```
+    #[cfg(not(feature = "regex_automata"))]
+    pub fn retokenize(
+        &self,
+        text: &str,
+        tokens: &mut Vec<TokenWithMode>,
+        edit: &TextEdit,
+    ) -> Range<usize> {
+        incremental::retokenize(self.inner.clone(), text, tokens, edit)
+    }
+
+    /// Writes the compiled scanner to the writer.
+    /// It can be loaded with [Scanner::from_reader] or [Scanner::from_bytes] without compiling
+    /// the scanner modes again.
+    ///
+    /// The data start with a header that names the version of this crate and the backend. They
+    /// can only be loaded by the same version of the crate with the same backend. The handling of
+    /// unmatched input, see [Scanner::with_unmatched_input], is written with the scanner.
+    ///
+    /// With the feature `regex_automata` the scanner modes are written, because the regexes can't
+    /// be serialized. They are built again when the scanner is loaded.
+    #[cfg(feature = "serde")]
+    pub fn write_to(&self, writer: impl std::io::Write) -> Result<()> {
+        crate::internal::serialization::write_scanner(&self.inner, writer)
+    }
+
+    /// Returns the compiled scanner as bytes. See [Scanner::write_to] for details.
+    ///
+    /// ```rust
+    /// use scnr::{Scanner, ScannerBuilder};
+    ///
+    /// let scanner = ScannerBuilder::new()
+    ///     .add_patterns([r"[a-z]+", r"\s+"])
+    ///     .build()
+    ///     .unwrap();
+    /// let bytes = scanner.to_bytes().unwrap();
+    /// let loaded = Scanner::from_bytes(&bytes).unwrap();
+    /// let token_types = loaded
+    ///     .find_iter("load me")
+    ///     .map(|m| m.token_type())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(token_types, vec![0, 1, 0]);
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Loads a scanner that was written by [Scanner::write_to].
+    ///
+    /// An error of kind [crate::ScnrErrorKind::InvalidSerializedScanner] is returned if the
+    /// scanner was written by another version of this crate or with another backend, or if its
+    /// data are inconsistent.
+    #[cfg(feature = "serde")]
+    pub fn from_reader(reader: impl std::io::Read) -> Result<Self> {
+        Ok(Scanner {
+            inner: crate::internal::serialization::read_scanner(reader)?,
+        })
+    }
+
+    /// Loads a scanner from bytes that were created by [Scanner::to_bytes].
+    /// See [Scanner::from_reader] for details.
+    #[cfg(feature = "serde")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_reader(bytes)
     }
 
     /// Logs the compiled FSMs as a Graphviz DOT file with the help of the `log` crate.
@@ -125,9 +354,15 @@ impl ScannerModeSwitcher for Scanner {
     fn mode_name(&self, index: usize) -> Option<&str> {
         self.inner.mode_name(index)
     }
+
+    /// Returns the depth of the mode stack.
+    fn mode_stack_depth(&self) -> usize {
+        self.inner.mode_stack_depth()
+    }
 }
 
 /// A scanner can be created from a vector of scanner modes.
+#[cfg(feature = "compiler")]
 impl TryFrom<Vec<ScannerMode>> for Scanner {
     type Error = crate::ScnrError;
 
@@ -138,11 +373,19 @@ impl TryFrom<Vec<ScannerMode>> for Scanner {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "compiler"))]
 mod tests {
     use super::*;
-    use crate::{Pattern, ScannerBuilder};
-    use std::{fs, sync::Once};
+    use crate::{
+        test_support::{pattern, scanner_mode},
+        ColumnUnit, LineIndex, LineTerminators, Lookbehind, MatchExt, MatchExtIterator,
+        ModeTransition, Pattern, PeekResult, Position, PositionProvider, ScannerBuilder, Token,
+        UnmatchedInput,
+    };
+    use std::{
+        fs,
+        sync::{Arc, Once},
+    };
 
     static INIT: Once = Once::new();
 
@@ -219,6 +462,779 @@ mod tests {
         assert_eq!(0, scanner.inner.clone().current_mode());
     }
 
+    #[test]
+    // Test the push and pop transitions of the mode stack.
+    fn test_scanner_mode_stack() {
+        init();
+        // A quote pushes STRING and `${` pushes CODE, the closing quote and `}` pop them again.
+        let code = scanner_mode(
+            "CODE",
+            [
+                pattern(r"[a-z]+", 0),
+                pattern(r"\u{22}", 1),
+                pattern(r"\}", 2),
+            ],
+            &[(1, ModeTransition::Push(1)), (2, ModeTransition::Pop)],
+        );
+        let string = scanner_mode(
+            "STRING",
+            [
+                pattern(r"\$\{", 3),
+                pattern(r"[^\u{22}$]+", 4),
+                pattern(r"\u{22}", 1),
+            ],
+            &[(1, ModeTransition::Pop), (3, ModeTransition::Push(0))],
+        );
+        let scanner = ScannerBuilder::new()
+            .add_scanner_modes(&[code, string])
+            .build()
+            .unwrap();
+
+        let input = r#"a"b${c"d"}"}e"#;
+        let mut find_iter = scanner.find_iter(input);
+        let mut states = Vec::new();
+        while let Some(ma) = find_iter.next() {
+            states.push((
+                &input[ma.range()],
+                find_iter.current_mode(),
+                find_iter.mode_stack_depth(),
+            ));
+        }
+        assert_eq!(
+            states,
+            vec![
+                ("a", 0, 0),
+                ("\"", 1, 1),
+                ("b", 1, 1),
+                ("${", 0, 2),
+                ("c", 0, 2),
+                ("\"", 1, 3),
+                ("d", 1, 3),
+                ("\"", 0, 2),
+                ("}", 1, 1),
+                ("\"", 0, 0),
+                // A pop on an empty mode stack keeps the scanner mode.
+                ("}", 0, 0),
+                ("e", 0, 0),
+            ]
+        );
+
+        // Peeking reports the scanner mode on top of the mode stack as target of a pop.
+        let mut find_iter = scanner.find_iter(input).with_offset(3);
+        find_iter.set_mode(1);
+        assert_eq!(find_iter.by_ref().take(3).count(), 3);
+        match find_iter.peek_n(3) {
+            PeekResult::MatchesReachedModeSwitch((matches, mode)) => {
+                assert_eq!(matches.len(), 2);
+                assert_eq!(mode, 0);
+            }
+            other => panic!("Unexpected peek result {:?}", other),
+        }
+    }
+
+    #[test]
+    // Test that peeking across mode switches predicts the matches and their scanner modes.
+    fn test_scanner_peek_across_modes() {
+        init();
+        let code = scanner_mode(
+            "CODE",
+            [
+                pattern(r"[a-z]+", 0),
+                pattern(r"\u{22}", 1),
+                pattern(r"\s+", 2).with_skip(true),
+            ],
+            &[(1, ModeTransition::Push(1))],
+        );
+        let string = scanner_mode(
+            "STRING",
+            [pattern(r"[^\u{22}]+", 3), pattern(r"\u{22}", 1)],
+            &[(1, ModeTransition::Pop)],
+        );
+        let scanner = ScannerBuilder::new()
+            .add_scanner_modes(&[code, string])
+            .build()
+            .unwrap();
+
+        let input = "a \"b c\" d \"e\" f";
+        let mut find_iter = scanner.find_iter(input);
+        let mut expected = Vec::new();
+        loop {
+            let mode = find_iter.current_mode();
+            match find_iter.next() {
+                Some(ma) => expected.push((ma, mode)),
+                None => break,
+            }
+        }
+        assert_eq!(expected.len(), 9);
+
+        let mut find_iter = scanner.find_iter(input);
+        assert_eq!(find_iter.by_ref().take(2).count(), 2);
+        assert_eq!(find_iter.current_mode(), 1);
+        assert_eq!(
+            find_iter.peek_n_across_modes(6),
+            PeekResult::Matches(expected[2..8].to_vec())
+        );
+        assert_eq!(
+            find_iter.peek_n_across_modes(20),
+            PeekResult::MatchesReachedEnd(expected[2..].to_vec())
+        );
+        // The state of the iterator is not changed.
+        assert_eq!(find_iter.current_mode(), 1);
+        assert_eq!(find_iter.mode_stack_depth(), 1);
+        assert_eq!(
+            find_iter.collect::<Vec<_>>(),
+            expected[2..].iter().map(|(ma, _)| *ma).collect::<Vec<_>>()
+        );
+
+        // The tokens iterator peeks the same tokens as it returns.
+        let tokens = scanner.find_tokens(input).collect::<Vec<_>>();
+        let mut find_tokens = scanner.find_tokens(input);
+        match find_tokens.peek_n_across_modes(20) {
+            PeekResult::MatchesReachedEnd(peeked) => {
+                assert_eq!(
+                    peeked.into_iter().map(|(t, _)| t).collect::<Vec<_>>(),
+                    tokens
+                )
+            }
+            other => panic!("Unexpected peek result {:?}", other),
+        }
+        assert_eq!(
+            scanner.find_iter("").peek_n_across_modes(1),
+            PeekResult::NotFound
+        );
+    }
+
+    #[test]
+    // Test that restoring a checkpoint restores the scanner mode and the mode stack.
+    fn test_scanner_checkpoint() {
+        init();
+        // The checkpoint is taken inside a string template, i.e. with two scanner modes on the
+        // mode stack.
+        let code = scanner_mode(
+            "CODE",
+            [
+                pattern(r"[a-z]+", 0),
+                pattern(r"\u{22}", 1),
+                pattern(r"\}", 2),
+                pattern(r"\s+", 5),
+            ],
+            &[(1, ModeTransition::Push(1)), (2, ModeTransition::Pop)],
+        );
+        let string = scanner_mode(
+            "STRING",
+            [
+                pattern(r"\$\{", 3),
+                pattern(r"[^\u{22}$]+", 4),
+                pattern(r"\u{22}", 1),
+            ],
+            &[(1, ModeTransition::Pop), (3, ModeTransition::Push(0))],
+        );
+        let scanner = ScannerBuilder::new()
+            .add_scanner_modes(&[code, string])
+            .build()
+            .unwrap();
+
+        let input = "a\"b${c\n\"d\n\"}\"\ne";
+        let mut find_iter = scanner.find_iter(input);
+        let states = |find_iter: &mut FindMatches| {
+            let mut states = Vec::new();
+            while let Some(ma) = find_iter.next() {
+                states.push((
+                    &input[ma.range()],
+                    PositionProvider::position(find_iter, ma.start()),
+                    find_iter.current_mode(),
+                    find_iter.mode_stack_depth(),
+                ));
+            }
+            states
+        };
+        assert_eq!(find_iter.by_ref().take(4).count(), 4);
+        let checkpoint = find_iter.checkpoint();
+        assert_eq!(checkpoint.offset(), find_iter.offset());
+        assert_eq!(checkpoint.mode(), 0);
+        let expected = states(&mut find_iter);
+        assert_eq!(
+            expected[..3],
+            [
+                ("c", Position::new(1, 6), 0, 2),
+                ("\n", Position::new(1, 7), 0, 2),
+                ("\"", Position::new(2, 1), 1, 3),
+            ]
+        );
+        assert_eq!(find_iter.current_mode(), 0);
+        assert_eq!(find_iter.mode_stack_depth(), 0);
+
+        // Restore the checkpoint twice and from a different scanner mode.
+        for _ in 0..2 {
+            find_iter.set_mode(1);
+            find_iter.restore(&checkpoint);
+            assert_eq!(find_iter.current_mode(), 0);
+            assert_eq!(find_iter.mode_stack_depth(), 2);
+            assert_eq!(states(&mut find_iter), expected);
+        }
+
+        // The tokens iterator restores checkpoints, too. The end positions of tokens that end with
+        // a newline are not compared, because the start of the next line is known after a restore.
+        let mut find_tokens = scanner.find_tokens(input);
+        let checkpoint = find_tokens.checkpoint();
+        let tokens = find_tokens
+            .by_ref()
+            .map(|t| (t.text(), t.start_position()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 13);
+        find_tokens.restore(&checkpoint);
+        assert_eq!(
+            find_tokens
+                .map(|t| (t.text(), t.start_position()))
+                .collect::<Vec<_>>(),
+            tokens
+        );
+    }
+
+    #[test]
+    // Test that skipped matches are neither returned nor peeked, but switch the scanner mode.
+    fn test_scanner_skipped_patterns() {
+        init();
+        let code = ScannerMode::new(
+            "CODE",
+            vec![
+                Pattern::new(r"\s+".to_string(), 0).with_skip(true),
+                Pattern::new(r"[a-z]+".to_string(), 1),
+                Pattern::new(r"#".to_string(), 2).with_skip(true),
+            ],
+            vec![(2, 1)],
+        );
+        let comment = ScannerMode::new(
+            "COMMENT",
+            vec![
+                Pattern::new(r"[^\n]+".to_string(), 3).with_skip(true),
+                Pattern::new(r"\n".to_string(), 4).with_skip(true),
+            ],
+            vec![(4, 0)],
+        );
+        let scanner = ScannerBuilder::new()
+            .add_scanner_modes(&[code, comment])
+            .build()
+            .unwrap();
+
+        let input = "a b # c d\ne";
+        let matches = scanner
+            .find_iter(input)
+            .map(|m| (m.token_type(), &input[m.range()]))
+            .collect::<Vec<_>>();
+        assert_eq!(matches, vec![(1, "a"), (1, "b"), (1, "e")]);
+
+        let mut find_iter = scanner.find_iter(input);
+        // The whitespace is not counted against n.
+        match find_iter.peek_n(2) {
+            PeekResult::Matches(matches) => {
+                assert_eq!(
+                    matches.iter().map(|m| m.start()).collect::<Vec<_>>(),
+                    [0, 2]
+                );
+            }
+            other => panic!("Unexpected peek result {:?}", other),
+        }
+        // The skipped `#` stops the peek operation with its mode switch.
+        match find_iter.peek_n(3) {
+            PeekResult::MatchesReachedModeSwitch((matches, mode)) => {
+                assert_eq!(matches.len(), 2);
+                assert_eq!(mode, 1);
+            }
+            other => panic!("Unexpected peek result {:?}", other),
+        }
+        assert_eq!(find_iter.by_ref().take(2).count(), 2);
+        // The next match is found after the comment in the initial scanner mode again.
+        assert_eq!(find_iter.next().map(|m| m.start()), Some(10));
+        assert_eq!(find_iter.current_mode(), 0);
+    }
+
+    #[test]
+    // Test the handling of unmatched input.
+    fn test_scanner_unmatched_input() {
+        init();
+        let scanner_mode = ScannerMode::new(
+            "INITIAL",
+            vec![
+                Pattern::new(r"[a-z]+".to_string(), 0),
+                Pattern::new(r"\s+".to_string(), 1).with_skip(true),
+            ],
+            vec![],
+        );
+        let input = "a ?? b\n!c";
+        let tokens = |unmatched_input: UnmatchedInput| {
+            let scanner = ScannerBuilder::new()
+                .with_unmatched_input(unmatched_input)
+                .add_scanner_mode(scanner_mode.clone())
+                .build()
+                .unwrap();
+            scanner
+                .find_iter(input)
+                .map(|m| (m.token_type(), &input[m.range()]))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tokens(UnmatchedInput::Skip),
+            vec![(0, "a"), (0, "b"), (0, "c")]
+        );
+        assert_eq!(
+            tokens(UnmatchedInput::Token(7)),
+            vec![(0, "a"), (7, "??"), (0, "b"), (7, "!"), (0, "c")]
+        );
+        assert_eq!(tokens(UnmatchedInput::Error), vec![(0, "a")]);
+
+        let scanner = ScannerBuilder::new()
+            .with_unmatched_input(UnmatchedInput::Token(7))
+            .add_scanner_mode(scanner_mode)
+            .build()
+            .unwrap();
+        // Peeking returns the unmatched input like the iterator.
+        let mut find_iter = scanner.find_iter(input);
+        match find_iter.peek_n(3) {
+            PeekResult::Matches(matches) => {
+                assert_eq!(
+                    matches.iter().map(|m| m.range()).collect::<Vec<_>>(),
+                    [0..1, 2..4, 5..6]
+                );
+            }
+            other => panic!("Unexpected peek result {:?}", other),
+        }
+        // An unmatched run at the end of the input.
+        let mut find_iter = scanner.find_iter("a ??");
+        assert_eq!(find_iter.next().map(|m| m.range()), Some(0..1));
+        assert_eq!(find_iter.next().map(|m| m.range()), Some(2..4));
+        assert_eq!(find_iter.next(), None);
+
+        // The error names the offset and the scanner mode. It doesn't move the position.
+        let mut find_iter = scanner
+            .with_unmatched_input(UnmatchedInput::Error)
+            .find_iter(input);
+        let checkpoint = find_iter.checkpoint();
+        assert_eq!(find_iter.next().map(|m| m.range()), Some(0..1));
+        assert!(find_iter.last_error().is_none());
+        assert!(find_iter.next().is_none());
+        assert_eq!(
+            find_iter.last_error().map(|error| error.to_string()),
+            Some("No pattern of scanner mode 'INITIAL' matches the input at offset 2".to_string())
+        );
+        for _ in 0..2 {
+            let error = find_iter.try_next_match().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "No pattern of scanner mode 'INITIAL' matches the input at offset 2"
+            );
+        }
+        // Restoring a checkpoint before the unmatched input drops the error.
+        find_iter.restore(&checkpoint);
+        assert!(find_iter.last_error().is_none());
+        assert_eq!(find_iter.next().map(|m| m.range()), Some(0..1));
+    }
+
+    #[test]
+    // Test the names of the token types.
+    fn test_scanner_token_names() {
+        init();
+        let scanner_modes = vec![
+            ScannerMode::new(
+                "INITIAL",
+                vec![
+                    Pattern::new(r"[a-z]+".to_string(), 0).with_name("Word"),
+                    Pattern::new(r"\s+".to_string(), 1),
+                    Pattern::new("\"".to_string(), 2).with_name("Quote"),
+                ],
+                vec![(2, 1)],
+            ),
+            ScannerMode::new(
+                "STRING",
+                vec![
+                    Pattern::new("[^\"]+".to_string(), 3).with_name("Text"),
+                    Pattern::new("\"".to_string(), 2).with_name("Quote"),
+                ],
+                vec![(2, 0)],
+            ),
+        ];
+        let scanner = ScannerBuilder::new()
+            .add_scanner_modes(&scanner_modes)
+            .build()
+            .unwrap();
+        assert_eq!(scanner.token_name(0), Some("Word"));
+        assert_eq!(scanner.token_name(1), None);
+        assert_eq!(scanner.token_name(3), Some("Text"));
+        assert_eq!(scanner.token_type_by_name("Quote"), Some(2));
+        assert_eq!(scanner.token_type_by_name("Number"), None);
+
+        #[cfg(feature = "serde")]
+        {
+            let loaded = Scanner::from_bytes(&scanner.to_bytes().unwrap()).unwrap();
+            assert_eq!(loaded.token_name(3), Some("Text"));
+            assert_eq!(loaded.token_type_by_name("Quote"), Some(2));
+        }
+
+        // A name must not be used for different token types.
+        let error = ScannerBuilder::new()
+            .add_scanner_mode(ScannerMode::new(
+                "INITIAL",
+                vec![
+                    Pattern::new("a".to_string(), 0).with_name("Letter"),
+                    Pattern::new("b".to_string(), 1).with_name("Letter"),
+                ],
+                vec![],
+            ))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The name 'Letter' of token type 1 conflicts with another token name"
+        );
+    }
+
+    #[test]
+    // Test the column units of the positions.
+    fn test_scanner_column_units() {
+        init();
+        let scanner = ScannerBuilder::new()
+            .add_patterns([r"[^\s]+", r"[ \t]+", r"\n"])
+            .build()
+            .unwrap();
+        let input = "über\t€\n\t😀 x\n";
+        let positions = |column_unit: ColumnUnit| {
+            scanner
+                .find_iter(input)
+                .with_column_unit(column_unit)
+                .with_positions()
+                .map(|m| {
+                    (
+                        m.start_position().line,
+                        m.start_position().column,
+                        m.end_position().column,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            positions(ColumnUnit::Byte),
+            [
+                (1, 1, 6),
+                (1, 6, 7),
+                (1, 7, 10),
+                (1, 10, 11),
+                (2, 1, 2),
+                (2, 2, 6),
+                (2, 6, 7),
+                (2, 7, 8),
+                (2, 8, 9)
+            ]
+        );
+        assert_eq!(
+            positions(ColumnUnit::Char),
+            [
+                (1, 1, 5),
+                (1, 5, 6),
+                (1, 6, 7),
+                (1, 7, 8),
+                (2, 1, 2),
+                (2, 2, 3),
+                (2, 3, 4),
+                (2, 4, 5),
+                (2, 5, 6)
+            ]
+        );
+        assert_eq!(
+            positions(ColumnUnit::Utf16)[5..],
+            [(2, 2, 4), (2, 4, 5), (2, 5, 6), (2, 6, 7)]
+        );
+        assert_eq!(
+            positions(ColumnUnit::CharsWithTabs { tab_width: 4 })[..7],
+            [
+                (1, 1, 5),
+                (1, 5, 9),
+                (1, 9, 10),
+                (1, 10, 11),
+                (2, 1, 5),
+                (2, 5, 6),
+                (2, 6, 7)
+            ]
+        );
+
+        // Peeked tokens have the same positions as consumed tokens.
+        for column_unit in [ColumnUnit::Char, ColumnUnit::Utf16] {
+            let tokens = scanner
+                .find_tokens(input)
+                .with_column_unit(column_unit)
+                .collect::<Vec<_>>();
+            let mut find_tokens = scanner.find_tokens(input).with_column_unit(column_unit);
+            find_tokens.by_ref().take(2).for_each(drop);
+            match find_tokens.peek_n(5) {
+                PeekResult::Matches(peeked) => assert_eq!(peeked, tokens[2..7]),
+                other => panic!("Unexpected peek result {:?}", other),
+            }
+        }
+
+        // An offset within a character counts up to the start of the character.
+        let mut find_iter = scanner.find_iter("äö x").with_column_unit(ColumnUnit::Char);
+        find_iter.by_ref().for_each(drop);
+        assert_eq!(find_iter.position(1), Position::new(1, 1));
+        assert_eq!(find_iter.position(2), Position::new(1, 2));
+    }
+
+    #[test]
+    // Test the positions calculated from a shared line index.
+    fn test_scanner_line_index() {
+        init();
+        let scanner = ScannerBuilder::new()
+            .add_patterns([r"[a-zä]+", r"\r\n|\r|\n|\u{2028}", r" +"])
+            .build()
+            .unwrap();
+        let input = "ab\rcd\r\nä e\u{2028}f";
+        let line_index = Arc::new(LineIndex::new(input, LineTerminators::Unicode));
+        let find_iter = scanner
+            .find_iter(input)
+            .with_line_index(Arc::clone(&line_index));
+        // Positions behind the current offset are known before the scanner passes them.
+        assert_eq!(find_iter.position(input.len()), Position::new(4, 2));
+        let matches = find_iter
+            .with_positions()
+            .map(|m| (m.start_position(), m.end_position()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matches[..4],
+            [
+                (Position::new(1, 1), Position::new(1, 3)),
+                (Position::new(1, 3), Position::new(2, 1)),
+                (Position::new(2, 1), Position::new(2, 3)),
+                (Position::new(2, 3), Position::new(3, 1)),
+            ]
+        );
+        assert_eq!(matches.len(), 9);
+        for (m, (start, end)) in scanner.find_iter(input).zip(&matches) {
+            assert_eq!(line_index.offset(*start), Some(m.start()));
+            assert_eq!(line_index.offset(*end), Some(m.end()));
+        }
+
+        // A second iterator shares the index and counts the columns in characters.
+        let tokens = scanner
+            .find_tokens(input)
+            .with_line_index(Arc::clone(&line_index))
+            .with_column_unit(ColumnUnit::Char)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens[5].text(), " ");
+        assert_eq!(tokens[5].start_position(), Position::new(3, 2));
+        let mut find_tokens = scanner
+            .find_tokens(input)
+            .with_line_index(line_index)
+            .with_column_unit(ColumnUnit::Char);
+        match find_tokens.peek_n(10) {
+            PeekResult::MatchesReachedEnd(peeked) => assert_eq!(peeked, tokens),
+            other => panic!("Unexpected peek result {:?}", other),
+        }
+    }
+
+    #[test]
+    // Test that tokens provide the text and the positions of the matches.
+    fn test_scanner_find_tokens() {
+        init();
+        let scanner = ScannerBuilder::new()
+            .add_patterns([r"[a-z]+", r"\s+", r"\d+"])
+            .build()
+            .unwrap();
+        let input = "ab cd\n\n12 ef\n gh";
+        let expected = scanner
+            .find_iter(input)
+            .with_positions()
+            .collect::<Vec<_>>();
+        let tokens = scanner.find_tokens(input).collect::<Vec<_>>();
+        assert_eq!(
+            tokens.iter().map(|t| t.text()).collect::<Vec<_>>(),
+            ["ab", " ", "cd", "\n\n", "12", " ", "ef", "\n ", "gh"]
+        );
+        assert!(tokens.iter().all(|t| t.text() == &input[t.range()]));
+        assert_eq!(
+            tokens
+                .iter()
+                .copied()
+                .map(MatchExt::from)
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        // Peeked tokens equal the tokens that are consumed afterwards.
+        for offset in 0..tokens.len() {
+            let mut find_tokens = scanner.find_tokens(input);
+            find_tokens.by_ref().take(offset).for_each(drop);
+            match find_tokens.peek_n(3) {
+                PeekResult::Matches(peeked) | PeekResult::MatchesReachedEnd(peeked) => {
+                    assert_eq!(
+                        peeked,
+                        tokens[offset..].iter().take(3).copied().collect::<Vec<_>>()
+                    );
+                }
+                other => panic!("Unexpected peek result {:?}", other),
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        {
+            // The text is borrowed from the json, so it must not contain escape sequences.
+            let json = serde_json::to_string(&tokens[..3]).unwrap();
+            let deserialized: Vec<Token> = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, tokens[..3]);
+        }
+    }
+
+    #[test]
+    // Test that line anchors and word boundaries see the characters around the start position.
+    fn test_scanner_with_assertions() {
+        init();
+        let scanner = ScannerBuilder::new()
+            .add_patterns([r"(?m)^#include", r"\bif\b", r"[a-z]+", r"\s+", r"#"])
+            .build()
+            .unwrap();
+        let input = "if iffy\n#include x #include";
+        let tokens = |offset: usize| {
+            scanner
+                .find_iter(input)
+                .with_offset(offset)
+                .map(|m| (m.token_type(), &input[m.start()..m.end()]))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tokens(0),
+            vec![
+                (1, "if"),
+                (3, " "),
+                (2, "iffy"),
+                (3, "\n"),
+                (0, "#include"),
+                (3, " "),
+                (2, "x"),
+                (3, " "),
+                (4, "#"),
+                (2, "include"),
+            ]
+        );
+        // The scan starts in the middle of the word `iffy`.
+        assert_eq!(tokens(4)[0], (2, "ffy"));
+        // The scan starts at a line start.
+        assert_eq!(tokens(8)[0], (0, "#include"));
+        // The scan starts at a `#` that is not at a line start.
+        assert_eq!(tokens(19)[0], (4, "#"));
+    }
+
+    #[test]
+    // Test that a pattern whose lookbehind fails leaves the match to the other patterns.
+    fn test_scanner_with_lookbehind() {
+        init();
+        let scanner_mode = ScannerMode::new(
+            "INITIAL",
+            vec![
+                Pattern::new(r"-".to_string(), 1)
+                    .with_lookbehind(Lookbehind::new(true, r"[=(]\s*".to_string())),
+                Pattern::new(r"-".to_string(), 2),
+                Pattern::new(r"\.[0-9]+".to_string(), 3)
+                    .with_lookbehind(Lookbehind::new(false, r"\w".to_string())),
+                Pattern::new(r"\.".to_string(), 4),
+                Pattern::new(r"\w+".to_string(), 5),
+                Pattern::new(r"[=()\s]".to_string(), 6),
+            ],
+            vec![],
+        );
+        let scanner = ScannerBuilder::new()
+            .add_scanner_mode(scanner_mode)
+            .build()
+            .unwrap();
+        let input = "x = -a - x.5 - .5";
+        let tokens = scanner
+            .find_iter(input)
+            .filter(|m| m.token_type() != 6)
+            .map(|m| (m.token_type(), &input[m.start()..m.end()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                (5, "x"),
+                (1, "-"),
+                (5, "a"),
+                (2, "-"),
+                (5, "x"),
+                (4, "."),
+                (5, "5"),
+                (2, "-"),
+                (3, ".5"),
+            ]
+        );
+    }
+
+    #[test]
+    // Test that a byte haystack is decoded as UTF-8 where possible and that invalid bytes can be
+    // matched with byte escapes.
+    fn test_scanner_with_byte_input() {
+        init();
+        let scanner = ScannerBuilder::new()
+            .add_patterns([r"[a-z]+", r"é", r"(?-u:[\x80-\xFF])+", r"\s+"])
+            .build()
+            .unwrap();
+        let input = b"caf\xE9 \xC3\xA9t\xE9\n\xFF\xFEx";
+        let tokens = scanner
+            .find_iter_bytes(input)
+            .map(|m| (m.token_type(), &input[m.range()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                (0, &b"caf"[..]),
+                (2, b"\xE9"),
+                (3, b" "),
+                (1, "é".as_bytes()),
+                (0, b"t"),
+                (2, b"\xE9"),
+                (3, b"\n"),
+                (2, b"\xFF\xFE"),
+                (0, b"x"),
+            ]
+        );
+        let last = scanner
+            .find_iter_bytes(input)
+            .with_positions()
+            .last()
+            .unwrap();
+        assert_eq!(last.start_position(), crate::Position::new(2, 3));
+        // The scan can start behind an invalid byte.
+        let first = scanner
+            .find_iter_bytes(input)
+            .with_offset(11)
+            .next()
+            .unwrap();
+        assert_eq!((first.token_type(), first.range()), (2, 11..12));
+    }
+
+    #[test]
+    // Test that raw bytes are distinct from all characters. The byte escape `\xFF` doesn't match
+    // the character U+10FFFF and the dot doesn't match a raw byte.
+    fn test_scanner_raw_bytes_are_no_characters() {
+        init();
+        let scanner = ScannerBuilder::new()
+            .add_patterns([r"(?-u:\xFF)", r"\u{10FFFF}", r"."])
+            .build()
+            .unwrap();
+        let input = "\u{10FFFF}\u{FF}".as_bytes();
+        let tokens = scanner
+            .find_iter_bytes(input)
+            .map(|m| (m.token_type(), m.range()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![(1, 0..4), (2, 4..6)]);
+        let tokens = scanner
+            .find_iter("\u{10FFFF}")
+            .map(|m| (m.token_type(), m.range()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![(1, 0..4)]);
+        let input = b"\xFF\xFEa";
+        let tokens = scanner
+            .find_iter_bytes(input)
+            .map(|m| (m.token_type(), m.range()))
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![(0, 0..1), (2, 2..3)]);
+    }
+
     // A test that checks the behavior of the scanner when so called 'pathological regular expressions'
     // are used. These are regular expressions that are very slow to match.
     // The test checks if the scanner is able to handle these cases and does not hang.
@@ -1,11 +1,16 @@
 use crate::{
-    internal::SCANNER_CACHE, scanner::Scanner, scanner_mode::ScannerMode, Pattern, Result,
+    internal::{ScannerImpl, SCANNER_CACHE},
+    scanner::Scanner,
+    scanner_mode::ScannerMode,
+    CompileLimits, Pattern, Result, UnmatchedInput,
 };
 
 /// A builder for creating a scanner.
 #[derive(Debug, Clone, Default)]
 pub struct ScannerBuilder {
     scanner_modes: Vec<ScannerMode>,
+    limits: CompileLimits,
+    unmatched_input: UnmatchedInput,
 }
 
 impl ScannerBuilder {
@@ -13,9 +18,25 @@ impl ScannerBuilder {
     pub fn new() -> Self {
         Self {
             scanner_modes: Vec::new(),
+            limits: CompileLimits::default(),
+            unmatched_input: UnmatchedInput::default(),
         }
     }
 
+    /// Sets the limits that are checked during the compilation of the scanner.
+    /// If this method is not called, [CompileLimits::default] is used.
+    pub fn with_compile_limits(mut self, limits: CompileLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the handling of input that no pattern of the current scanner mode matches.
+    /// If this method is not called, [UnmatchedInput::Skip] is used.
+    pub fn with_unmatched_input(mut self, unmatched_input: UnmatchedInput) -> Self {
+        self.unmatched_input = unmatched_input;
+        self
+    }
+
     /// Adds only patterns to the scanner builder.
     /// This is useful for simple use cases where only one scanner mode is needed.
     /// The scanner mode is named `INITIAL` implicitly.
@@ -32,6 +53,8 @@ impl ScannerBuilder {
             .map(|(i, pattern)| Pattern::new(pattern.as_ref().to_string(), i))
             .collect::<Vec<_>>();
         SimpleScannerBuilder::new(patterns)
+            .with_compile_limits(self.limits)
+            .with_unmatched_input(self.unmatched_input)
     }
 
     /// Adds a scanner mode to the scanner builder.
@@ -48,9 +71,13 @@ impl ScannerBuilder {
 
     /// Builds the scanner from the scanner builder.
     pub fn build(self) -> Result<Scanner> {
-        Ok(Scanner {
-            inner: SCANNER_CACHE.write().unwrap().get(&self.scanner_modes)?,
-        })
+        let scanner = Scanner {
+            inner: SCANNER_CACHE
+                .write()
+                .unwrap()
+                .get(&self.scanner_modes, &self.limits)?,
+        };
+        Ok(scanner.with_unmatched_input(self.unmatched_input))
     }
 
     /// Builds the scanner from the scanner builder without caching it.
@@ -60,9 +87,10 @@ impl ScannerBuilder {
     /// method instead.
     #[allow(dead_code)]
     pub fn build_uncached(self) -> Result<Scanner> {
-        Ok(Scanner {
-            inner: self.scanner_modes.try_into()?,
-        })
+        let scanner = Scanner {
+            inner: ScannerImpl::try_from_scanner_modes(&self.scanner_modes, &self.limits)?,
+        };
+        Ok(scanner.with_unmatched_input(self.unmatched_input))
     }
 }
 
@@ -75,6 +103,8 @@ impl ScannerBuilder {
 #[derive(Debug, Clone)]
 pub struct SimpleScannerBuilder {
     scanner_mode: ScannerMode,
+    limits: CompileLimits,
+    unmatched_input: UnmatchedInput,
 }
 
 impl SimpleScannerBuilder {
@@ -85,20 +115,40 @@ impl SimpleScannerBuilder {
     {
         Self {
             scanner_mode: ScannerMode::new("INITIAL", patterns, vec![]),
+            limits: CompileLimits::default(),
+            unmatched_input: UnmatchedInput::default(),
         }
     }
 
+    /// Sets the limits that are checked during the compilation of the scanner.
+    /// If this method is not called, the limits of the [ScannerBuilder] are used.
+    pub fn with_compile_limits(mut self, limits: CompileLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the handling of input that no pattern matches.
+    /// If this method is not called, the policy of the [ScannerBuilder] is used.
+    pub fn with_unmatched_input(mut self, unmatched_input: UnmatchedInput) -> Self {
+        self.unmatched_input = unmatched_input;
+        self
+    }
+
     /// Builds the scanner from the simple scanner builder.
     pub fn build(self) -> Result<Scanner> {
-        Ok(Scanner {
-            inner: SCANNER_CACHE.write().unwrap().get(&[self.scanner_mode])?,
-        })
+        let scanner = Scanner {
+            inner: SCANNER_CACHE
+                .write()
+                .unwrap()
+                .get(&[self.scanner_mode], &self.limits)?,
+        };
+        Ok(scanner.with_unmatched_input(self.unmatched_input))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Pattern, ScannerModeSwitcher};
+    use crate::{CompileLimit, Pattern, ScannerModeSwitcher, ScnrErrorKind};
 
     use super::*;
 
@@ -197,4 +247,100 @@ mod tests {
             &"// Line comment2"
         );
     }
+
+    #[test]
+    fn test_repetition_limit() {
+        init();
+        let scanner_mode = ScannerMode::new(
+            "HEX",
+            vec![
+                Pattern::new(r"[0-9a-f]{1,64}".to_string(), 1),
+                Pattern::new(r"[0-9a-f]{1,65}".to_string(), 2),
+            ],
+            vec![],
+        );
+        let result = ScannerBuilder::new()
+            .add_scanner_mode(scanner_mode)
+            .with_compile_limits(CompileLimits {
+                max_repetition: 64,
+                ..Default::default()
+            })
+            .build();
+        let err = result.unwrap_err();
+        assert!(matches!(
+            &*err.source,
+            ScnrErrorKind::CompileLimitExceeded {
+                limit: CompileLimit::Repetition(64),
+                pattern,
+                mode,
+            } if pattern == "[0-9a-f]{1,65}" && mode == "HEX"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "The maximum repetition count 64 is exceeded by pattern '[0-9a-f]{1,65}' in scanner mode 'HEX'"
+        );
+    }
+
+    #[test]
+    fn test_repetition_limit_of_simple_scanner() {
+        init();
+        let limits = CompileLimits {
+            max_repetition: 64,
+            ..Default::default()
+        };
+        assert!(ScannerBuilder::new()
+            .with_compile_limits(limits)
+            .add_patterns([r"[0-9a-f]{1,64}"])
+            .build()
+            .is_ok());
+        assert!(ScannerBuilder::new()
+            .add_patterns([r"[0-9a-f]{1,65}"])
+            .with_compile_limits(limits)
+            .build()
+            .is_err());
+    }
+
+    #[cfg(not(feature = "regex_automata"))]
+    #[test]
+    fn test_nfa_states_limit() {
+        init();
+        let err = ScannerBuilder::new()
+            .add_patterns([r"\w+", r"((a{100}){100}){100}"])
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            &*err.source,
+            ScnrErrorKind::CompileLimitExceeded {
+                limit: CompileLimit::NfaStates(100_000),
+                pattern,
+                mode,
+            } if pattern == "((a{100}){100}){100}" && mode == "INITIAL"
+        ));
+    }
+
+    #[cfg(not(feature = "regex_automata"))]
+    #[test]
+    fn test_dfa_states_limit() {
+        init();
+        let patterns = [r"[a-z]+", r"[0-9]{150}"];
+        let limits = CompileLimits {
+            max_dfa_states: 100,
+            ..Default::default()
+        };
+        let err = ScannerBuilder::new()
+            .with_compile_limits(limits)
+            .add_patterns(patterns)
+            .build()
+            .unwrap_err();
+        assert!(matches!(
+            &*err.source,
+            ScnrErrorKind::CompileLimitExceeded {
+                limit: CompileLimit::DfaStates(100),
+                pattern,
+                mode,
+            } if pattern == "[0-9]{150}" && mode == "INITIAL"
+        ));
+        // The default limits are sufficient for the same patterns.
+        assert!(ScannerBuilder::new().add_patterns(patterns).build().is_ok());
+    }
 }
@@ -1,5 +1,7 @@
+use log::trace;
+
 use crate::{
-    internal::{ScannerModeID, TerminalID, TerminalIDBase},
+    internal::{ModeStack, TerminalID, TerminalIDBase},
     Pattern,
 };
 #[cfg(feature = "serde")]
@@ -20,9 +22,113 @@ pub struct ScannerMode {
     pub(crate) patterns: Vec<Pattern>,
 
     /// The transitions between the scanner modes triggered by a token type number.
-    /// The entries are tuples of the token type numbers and the new scanner mode index and are
-    /// sorted by token type number.
-    pub(crate) transitions: Vec<(TerminalID, ScannerModeID)>,
+    /// The entries are tuples of the token type numbers and the mode transitions and are sorted by
+    /// token type number.
+    pub(crate) transitions: Vec<(TerminalID, ModeTransition)>,
+}
+
+/// A transition to another scanner mode that is triggered by a match of a token type.
+///
+/// The scanner keeps a stack of scanner modes per iterator. It allows to return to the scanner mode
+/// that was active before, e.g. at the end of an interpolation in a string that can itself contain
+/// strings.
+///
+/// In the JSON format a `Set` transition is written as the plain scanner mode index, like in former
+/// versions. The other kinds are written as `{"push": 1}` and `"pop"`.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "ModeTransitionRepr", into = "ModeTransitionRepr")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModeTransition {
+    /// Switches to the scanner mode with the given index. The mode stack is not changed.
+    Set(usize),
+    /// Pushes the current scanner mode onto the mode stack and switches to the scanner mode with
+    /// the given index.
+    Push(usize),
+    /// Switches back to the scanner mode on top of the mode stack and removes it from the stack.
+    /// If the mode stack is empty, the current scanner mode is kept.
+    Pop,
+}
+
+impl ModeTransition {
+    /// Returns the scanner mode that is active after the transition.
+    pub(crate) fn target(self, current_mode: usize, mode_stack: &ModeStack) -> usize {
+        match self {
+            ModeTransition::Set(mode) | ModeTransition::Push(mode) => mode,
+            ModeTransition::Pop => mode_stack.last().unwrap_or(current_mode),
+        }
+    }
+
+    /// Applies the transition to the current scanner mode and the mode stack.
+    pub(crate) fn apply(self, current_mode: &mut usize, mode_stack: &mut ModeStack) {
+        match self {
+            ModeTransition::Set(mode) => *current_mode = mode,
+            ModeTransition::Push(mode) => {
+                mode_stack.push(*current_mode);
+                *current_mode = mode;
+            }
+            ModeTransition::Pop => {
+                if let Some(mode) = mode_stack.pop() {
+                    *current_mode = mode;
+                } else {
+                    trace!(
+                        "Pop on an empty mode stack keeps scanner mode {}",
+                        current_mode
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The serialized form of a [ModeTransition]. A plain scanner mode index is a `Set` transition.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ModeTransitionRepr {
+    Index(usize),
+    Tagged(TaggedModeTransition),
+}
+
+/// The serialized form of the mode transitions with their kind as tag.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TaggedModeTransition {
+    Set(usize),
+    Push(usize),
+    Pop,
+}
+
+#[cfg(feature = "serde")]
+impl From<ModeTransitionRepr> for ModeTransition {
+    fn from(repr: ModeTransitionRepr) -> Self {
+        match repr {
+            ModeTransitionRepr::Index(mode)
+            | ModeTransitionRepr::Tagged(TaggedModeTransition::Set(mode)) => {
+                ModeTransition::Set(mode)
+            }
+            ModeTransitionRepr::Tagged(TaggedModeTransition::Push(mode)) => {
+                ModeTransition::Push(mode)
+            }
+            ModeTransitionRepr::Tagged(TaggedModeTransition::Pop) => ModeTransition::Pop,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ModeTransition> for ModeTransitionRepr {
+    fn from(transition: ModeTransition) -> Self {
+        match transition {
+            ModeTransition::Set(mode) => ModeTransitionRepr::Index(mode),
+            ModeTransition::Push(mode) => {
+                ModeTransitionRepr::Tagged(TaggedModeTransition::Push(mode))
+            }
+            ModeTransition::Pop => ModeTransitionRepr::Tagged(TaggedModeTransition::Pop),
+        }
+    }
 }
 
 impl ScannerMode {
@@ -30,13 +136,14 @@ impl ScannerMode {
     /// # Arguments
     /// * `name` - The name of the scanner mode.
     /// * `patterns` - The regular expressions that are valid token types in this mode, bundled with
-    ///     their token type numbers.
+    ///   their token type numbers.
     /// * `mode_transitions` - The transitions between the scanner modes triggered by a token type
-    ///     number. It is a vector of tuples of the token type numbers and the new scanner mode
-    ///     index. The entries should be sorted by token type number.
-    ///     The scanner mode index is the index of the scanner mode in the scanner mode vector of
-    ///     the scanner and is determined by the order of the insertions of scanner modes into the
-    ///     scanner.
+    ///   number. It is a vector of tuples of the token type numbers and the new scanner mode
+    ///   index. The entries should be sorted by token type number. They are `Set` transitions,
+    ///   use [ScannerMode::with_transition] for other kinds of transitions.
+    ///   The scanner mode index is the index of the scanner mode in the scanner mode vector of
+    ///   the scanner and is determined by the order of the insertions of scanner modes into the
+    ///   scanner.
     /// # Returns
     /// The new scanner mode.
     pub fn new<P, T>(name: &str, patterns: P, mode_transitions: T) -> Self
@@ -47,7 +154,7 @@ impl ScannerMode {
         let patterns = patterns.into_iter().collect::<Vec<_>>();
         let transitions = mode_transitions
             .into_iter()
-            .map(|(t, m)| (TerminalID::new(t as TerminalIDBase), ScannerModeID::new(m)))
+            .map(|(t, m)| (TerminalID::new(t as TerminalIDBase), ModeTransition::Set(m)))
             .collect::<Vec<_>>();
         debug_assert!(
             transitions.windows(2).all(|w| w[0].0 < w[1].0),
@@ -60,6 +167,29 @@ impl ScannerMode {
         }
     }
 
+    /// Adds a transition that is triggered by the given token type number. An existing transition
+    /// of the token type is replaced.
+    ///
+    /// ```rust
+    /// use scnr::{ModeTransition, Pattern, ScannerMode};
+    ///
+    /// let code = ScannerMode::new("CODE", [Pattern::new(r"\}".to_string(), 1)], [])
+    ///     .with_transition(1, ModeTransition::Pop);
+    /// let string = ScannerMode::new("STRING", [Pattern::new(r"\$\{".to_string(), 2)], [])
+    ///     .with_transition(2, ModeTransition::Push(0));
+    /// ```
+    pub fn with_transition(mut self, token_type: usize, transition: ModeTransition) -> Self {
+        let terminal_id = TerminalID::new(token_type as TerminalIDBase);
+        match self
+            .transitions
+            .binary_search_by_key(&terminal_id, |(t, _)| *t)
+        {
+            Ok(index) => self.transitions[index].1 = transition,
+            Err(index) => self.transitions.insert(index, (terminal_id, transition)),
+        }
+        self
+    }
+
     /// Returns the name of the scanner mode.
     pub fn name(&self) -> &str {
         &self.name
@@ -108,4 +238,39 @@ mod tests {
         let deserialized: ScannerMode = serde_json::from_str(&serialized).unwrap();
         assert_eq!(scanner_mode, deserialized);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_mode_transition_serialization() {
+        init();
+        let scanner_mode = ScannerMode::new(
+            "INITIAL",
+            vec![Pattern::new(r"\{".to_string(), 1)],
+            vec![(1, 1)],
+        )
+        .with_transition(3, ModeTransition::Pop)
+        .with_transition(2, ModeTransition::Push(1));
+        assert_eq!(
+            scanner_mode.transitions,
+            vec![
+                (TerminalID::new(1), ModeTransition::Set(1)),
+                (TerminalID::new(2), ModeTransition::Push(1)),
+                (TerminalID::new(3), ModeTransition::Pop),
+            ]
+        );
+
+        let serialized = serde_json::to_string(&scanner_mode.transitions).unwrap();
+        assert_eq!(serialized, r#"[[1,1],[2,{"push":1}],[3,"pop"]]"#);
+        let deserialized: ScannerMode =
+            serde_json::from_str(&serde_json::to_string(&scanner_mode).unwrap()).unwrap();
+        assert_eq!(scanner_mode, deserialized);
+
+        // A set transition can also be written with its tag.
+        let transitions: Vec<(TerminalID, ModeTransition)> =
+            serde_json::from_str(r#"[[1,{"set":2}]]"#).unwrap();
+        assert_eq!(
+            transitions,
+            vec![(TerminalID::new(1), ModeTransition::Set(2))]
+        );
+    }
 }
@@ -0,0 +1,370 @@
+use std::io::{ErrorKind, Read};
+
+use log::trace;
+
+use crate::{
+    internal::{Haystack, ScannerImpl},
+    Match, Position, PositionProvider, Result, ScannerModeSwitcher, ScnrError, ScnrErrorKind, Span,
+    UnmatchedInput,
+};
+
+/// The default number of bytes that are read from the reader at once.
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The default limit of the buffer size.
+const DEFAULT_MAX_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+/// The number of bytes before the current position that are kept when the buffer is compacted.
+/// They are needed to evaluate zero-width assertions and lookbehinds.
+const HISTORY_SIZE: usize = 1024;
+
+/// An iterator over all non-overlapping matches in a stream.
+///
+/// The iterator reads the input from a [Read] implementation into a buffer that slides over the
+/// stream. Only the current token, the input that a lookahead inspects behind it and a short
+/// history are kept in the buffer, so the input can be much larger than the memory.
+///
+/// The iterator yields the same matches as [crate::FindMatches] on the whole input. The spans of
+/// the matches are byte offsets from the start of the stream. The input is decoded as UTF-8 like
+/// the input of [crate::Scanner::find_iter_bytes], also if a character is split across reads.
+///
+/// A token that doesn't fit into the buffer results in an error of kind
+/// [ScnrErrorKind::BufferLimitExceeded]. Errors of the reader are returned as
+/// [ScnrErrorKind::IoError]. The iterator ends after an error.
+///
+/// Lookbehinds and zero-width assertions see at least 1024 bytes before the start of a match.
+///
+/// * `R` is the type of the reader.
+///
+/// This iterator can be created with the [`crate::Scanner::find_iter_reader`] method.
+pub struct StreamMatches<R> {
+    // The scanner used to find matches.
+    scanner_impl: ScannerImpl,
+    // The reader the input is read from.
+    reader: R,
+    // The buffer with the input that is currently needed.
+    buffer: Vec<u8>,
+    // The offset of the first byte of the buffer in the stream.
+    buffer_start: usize,
+    // The position in the buffer where the next search starts.
+    position: usize,
+    // The length of the part of the buffer that is searched. An incomplete UTF-8 sequence at the
+    // end of the buffer is held back until the rest of it has been read.
+    valid_len: usize,
+    // The offset in the stream that was set by `set_offset`. The input up to this offset is
+    // skipped.
+    skip_to: usize,
+    // The offset in the stream where the current run of unmatched characters starts. The run is
+    // returned as one match if the scanner is configured with `UnmatchedInput::Token`.
+    unmatched_start: Option<usize>,
+    // Set if the reader is exhausted.
+    eof: bool,
+    // Set after an error was returned.
+    failed: bool,
+    // The number of bytes that are read from the reader at once.
+    chunk_size: usize,
+    // The limit of the buffer size.
+    max_buffer_size: usize,
+    // The offsets of the line starts in the stream, starting with the line that contains the
+    // start of the buffer. It is used to calculate line and column numbers of offsets.
+    line_offsets: Vec<usize>,
+    // The number of lines before the first line in `line_offsets`.
+    lines_before: usize,
+    // The last byte that was consumed. It is used to detect the starts of lines.
+    last_byte: u8,
+}
+
+impl<R: Read> StreamMatches<R> {
+    /// Creates a new `StreamMatches` iterator.
+    pub(crate) fn new(mut scanner_impl: ScannerImpl, reader: R) -> Self {
+        scanner_impl.reset();
+        Self {
+            scanner_impl,
+            reader,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            position: 0,
+            valid_len: 0,
+            skip_to: 0,
+            unmatched_start: None,
+            eof: false,
+            failed: false,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+            line_offsets: vec![0],
+            lines_before: 0,
+            last_byte: 0,
+        }
+    }
+
+    /// Sets the number of bytes that are read from the reader at once.
+    /// The default is 64 KiB.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Sets the limit of the buffer size in bytes. The buffer must hold the longest token
+    /// together with the input inspected by its lookahead and the history before it.
+    /// The default is 16 MiB.
+    pub fn with_max_buffer_size(mut self, max_buffer_size: usize) -> Self {
+        self.max_buffer_size = max_buffer_size;
+        self
+    }
+
+    /// Retrieve the current byte offset from the start of the stream.
+    /// This is the end offset of the last match found by the iterator.
+    pub fn offset(&self) -> usize {
+        self.buffer_start + self.position
+    }
+
+    /// Returns the bytes of the given match if they are still in the buffer.
+    /// The bytes of the last match returned by the iterator are always available.
+    pub fn matched_bytes(&self, matched: &Match) -> Option<&[u8]> {
+        let start = matched.start().checked_sub(self.buffer_start)?;
+        let end = matched.end() - self.buffer_start;
+        self.buffer.get(start..end)
+    }
+
+    /// Returns the next match in the stream or `None` if the stream is exhausted.
+    /// Unmatched input is handled as configured by [UnmatchedInput].
+    fn next_match(&mut self) -> Result<Option<Match>> {
+        loop {
+            if self.offset() < self.skip_to {
+                let end = (self.skip_to - self.buffer_start).min(self.valid_len);
+                self.consume(end);
+            }
+            if self.position >= self.valid_len || self.offset() < self.skip_to {
+                if self.eof {
+                    return Ok(self.take_unmatched_match());
+                }
+                self.fill_buffer()?;
+                continue;
+            }
+            let haystack = Haystack::Bytes(&self.buffer[..self.valid_len]);
+            let (matched, _, read_end) = self
+                .scanner_impl
+                .peek_from_partial(haystack, haystack.char_indices(self.position));
+            if read_end > self.valid_len - self.position && !self.eof {
+                // More input could result in another match.
+                self.fill_buffer()?;
+                continue;
+            }
+            if let Some(matched) = matched {
+                if self.unmatched_start.is_some() {
+                    // The match is found again by the next call.
+                    return Ok(self.take_unmatched_match());
+                }
+                let skipped = self.scanner_impl.is_skipped(matched.token_type());
+                self.scanner_impl.execute_possible_mode_switch(&matched);
+                let start = self.offset() + matched.start();
+                let end = self.position + matched.end();
+                self.consume(end);
+                if skipped {
+                    continue;
+                }
+                return Ok(Some(Match::new(
+                    matched.token_type(),
+                    Span::new(start, self.offset()),
+                )));
+            }
+            match self.scanner_impl.unmatched_input() {
+                UnmatchedInput::Skip => {}
+                UnmatchedInput::Token(_) => {
+                    self.unmatched_start.get_or_insert(self.offset());
+                }
+                UnmatchedInput::Error => {
+                    let mode = self.scanner_impl.current_mode();
+                    return Err(ScnrError::new(ScnrErrorKind::UnmatchedInput {
+                        offset: self.offset(),
+                        mode: self
+                            .scanner_impl
+                            .mode_name(mode)
+                            .unwrap_or_default()
+                            .to_string(),
+                    }));
+                }
+            }
+            // No match at the current position, so the character is skipped.
+            let mut char_indices = haystack.char_indices(self.position);
+            char_indices.next();
+            let end = self.position + char_indices.offset();
+            self.consume(end);
+        }
+    }
+
+    /// Returns the match of the current run of unmatched characters, if there is one, and ends the
+    /// run. A run is only started if the scanner is configured with [UnmatchedInput::Token].
+    fn take_unmatched_match(&mut self) -> Option<Match> {
+        let start = self.unmatched_start.take()?;
+        let UnmatchedInput::Token(token_type) = self.scanner_impl.unmatched_input() else {
+            unreachable!("Unmatched input is only returned as match with UnmatchedInput::Token");
+        };
+        Some(Match::new(token_type, Span::new(start, self.offset())))
+    }
+
+    /// Advances the position in the buffer to `end` and records the starts of the lines.
+    fn consume(&mut self, end: usize) {
+        for i in self.position..end {
+            if self.last_byte == b'\n' {
+                self.line_offsets.push(self.buffer_start + i);
+            }
+            self.last_byte = self.buffer[i];
+        }
+        self.position = end;
+    }
+
+    /// Drops the input that is no longer needed and reads the next chunk from the reader.
+    fn fill_buffer(&mut self) -> Result<()> {
+        self.compact();
+        let len = self.buffer.len();
+        if len >= self.max_buffer_size {
+            return Err(ScnrError::new(ScnrErrorKind::BufferLimitExceeded {
+                limit: self.max_buffer_size,
+                offset: self.offset(),
+            }));
+        }
+        let chunk_size = self.chunk_size.min(self.max_buffer_size - len);
+        self.buffer.resize(len + chunk_size, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[len..]) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    return Err(e.into());
+                }
+            }
+        };
+        self.buffer.truncate(len + read);
+        trace!("Read {} bytes at offset {}", read, self.buffer_start + len);
+        if read == 0 {
+            self.eof = true;
+            self.valid_len = self.buffer.len();
+        } else {
+            self.valid_len = complete_utf8_len(&self.buffer);
+        }
+        Ok(())
+    }
+
+    /// Removes the input before the history of the current position from the buffer.
+    fn compact(&mut self) {
+        let mut keep_from = self.position.saturating_sub(HISTORY_SIZE);
+        if let Some(start) = self.unmatched_start {
+            // Keep the run of unmatched characters for `matched_bytes`.
+            keep_from = keep_from.min(start - self.buffer_start);
+        }
+        // Start the buffer at a character boundary.
+        while keep_from < self.position && (self.buffer[keep_from] & 0xC0) == 0x80 {
+            keep_from += 1;
+        }
+        if keep_from == 0 {
+            return;
+        }
+        self.buffer.drain(..keep_from);
+        self.buffer_start += keep_from;
+        self.position -= keep_from;
+        self.valid_len -= keep_from;
+        // Keep the start of the line that contains the start of the buffer.
+        let first_line = self
+            .line_offsets
+            .partition_point(|&offset| offset <= self.buffer_start)
+            - 1;
+        self.line_offsets.drain(..first_line);
+        self.lines_before += first_line;
+    }
+}
+
+/// Returns the length of the bytes without an incomplete UTF-8 sequence at their end.
+/// Invalid bytes are not held back, because more input doesn't change them.
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    let len = bytes.len();
+    for i in (len.saturating_sub(3)..len).rev() {
+        let b = bytes[i];
+        if b & 0xC0 == 0x80 {
+            // A continuation byte.
+            continue;
+        }
+        let char_len = match b {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return len,
+        };
+        return if len - i < char_len
+            && std::str::from_utf8(&bytes[i..]).is_err_and(|e| e.error_len().is_none())
+        {
+            i
+        } else {
+            len
+        };
+    }
+    len
+}
+
+impl<R: Read> Iterator for StreamMatches<R> {
+    type Item = Result<Match>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_match() {
+            Ok(matched) => matched.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R> PositionProvider for StreamMatches<R> {
+    /// Returns the line and column numbers of the given offset.
+    /// The positions are available for the offsets of the input in the buffer, i.e. at least for
+    /// the last match returned by the iterator. For older offsets the first line in the buffer is
+    /// returned.
+    fn position(&self, offset: usize) -> Position {
+        match self.line_offsets.binary_search(&offset) {
+            Ok(i) => Position::new(self.lines_before + i + 1, 1),
+            Err(0) => Position::new(self.lines_before + 1, 1),
+            Err(i) => Position::new(self.lines_before + i, offset - self.line_offsets[i - 1] + 1),
+        }
+    }
+
+    /// Skips the input up to the given offset in the stream.
+    /// A stream can't go back, so offsets before the current offset are ignored.
+    fn set_offset(&mut self, offset: usize) {
+        trace!("Skip to offset {}", offset);
+        self.skip_to = offset;
+    }
+}
+
+impl<R> ScannerModeSwitcher for StreamMatches<R> {
+    fn set_mode(&mut self, mode: usize) {
+        trace!("Set scanner mode to {}", mode);
+        self.scanner_impl.set_mode(mode);
+    }
+
+    fn current_mode(&self) -> usize {
+        self.scanner_impl.current_mode()
+    }
+
+    fn mode_name(&self, index: usize) -> Option<&str> {
+        self.scanner_impl.mode_name(index)
+    }
+
+    fn mode_stack_depth(&self) -> usize {
+        self.scanner_impl.mode_stack_depth()
+    }
+}
+
+impl<R> std::fmt::Debug for StreamMatches<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamMatches")
+            .field("offset", &(self.buffer_start + self.position))
+            .field("buffer_len", &self.buffer.len())
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
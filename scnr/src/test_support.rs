@@ -0,0 +1,22 @@
+//! Module with helpers that create the scanner modes of the unit tests.
+
+use crate::{ModeTransition, Pattern, ScannerMode};
+
+/// Creates a pattern that matches the regex with the given token type.
+pub(crate) fn pattern(regex: &str, token_type: usize) -> Pattern {
+    Pattern::new(regex.to_string(), token_type)
+}
+
+/// Creates a scanner mode with the given patterns and transitions.
+pub(crate) fn scanner_mode(
+    name: &str,
+    patterns: impl IntoIterator<Item = Pattern>,
+    transitions: &[(usize, ModeTransition)],
+) -> ScannerMode {
+    transitions.iter().fold(
+        ScannerMode::new(name, patterns, vec![]),
+        |scanner_mode, (token_type, transition)| {
+            scanner_mode.with_transition(*token_type, *transition)
+        },
+    )
+}
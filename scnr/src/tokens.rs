@@ -0,0 +1,133 @@
+use std::marker::PhantomData;
+
+use crate::{
+    FindMatches, Match, Position, PositionProvider, ScannerMode, ScannerModeSwitcher, ScnrError,
+};
+#[cfg(feature = "compiler")]
+use crate::{Result, Scanner, ScannerBuilder};
+
+/// A type whose values are the token types of a scanner.
+///
+/// The trait is usually implemented with the derive macro of the same name, which is available
+/// with the feature `derive`. The variants of a fieldless enum become the token types, their
+/// discriminants are the token type numbers and their names are the token names, see
+/// [Scanner::token_name].
+///
+/// The attributes that define the scanner modes, the patterns, the transitions and the skipped
+/// token types are described at the [derive macro](macro@crate::Tokens).
+///
+/// The order of the variants determines the priority of the patterns. Unknown scanner modes and
+/// patterns that are used twice in the same scanner mode are rejected at compile time.
+///
+/// ```ignore
+/// #[derive(scnr::Tokens, Clone, Copy, Debug, PartialEq)]
+/// #[modes(INITIAL, STRING)]
+/// enum Tok {
+///     #[pattern(r"\d+")]
+///     Number,
+///     #[mode(INITIAL, STRING)]
+///     #[transition(INITIAL => STRING, STRING => INITIAL)]
+///     #[pattern("\"")]
+///     Quote,
+///     #[mode(STRING)]
+///     #[pattern(r#"[^"]+"#)]
+///     Text,
+/// }
+///
+/// let scanner = Tok::scanner()?;
+/// for ma in scanner.find_iter(INPUT).typed::<Tok>() {
+///     println!("{:?}", ma.token_type());
+/// }
+/// ```
+pub trait Tokens: Copy + Sized {
+    /// Returns the scanner modes with the patterns of all token types and the transitions between
+    /// the scanner modes.
+    fn scanner_modes() -> Vec<ScannerMode>;
+
+    /// Returns the token type of the given token type number or `None` if the number doesn't
+    /// belong to a token type.
+    fn from_token_type(token_type: usize) -> Option<Self>;
+
+    /// Returns the token type number of the token type.
+    fn token_type(self) -> usize;
+
+    /// Builds a scanner from the scanner modes of the token types.
+    #[cfg(feature = "compiler")]
+    fn scanner() -> Result<Scanner> {
+        ScannerBuilder::new()
+            .add_scanner_modes(&Self::scanner_modes())
+            .build()
+    }
+}
+
+/// An iterator over all non-overlapping matches with typed token types.
+///
+/// The iterator is created with the [`FindMatches::typed`] method. Matches whose token type number
+/// doesn't belong to a token type of `T` are skipped. They can only occur if the scanner was not
+/// built from the scanner modes of `T`.
+///
+/// * `'h` represents the lifetime of the haystack being searched.
+#[derive(Debug)]
+pub struct TypedMatches<'h, T> {
+    inner: FindMatches<'h>,
+    phantom: PhantomData<T>,
+}
+
+impl<'h, T: Tokens> TypedMatches<'h, T> {
+    /// Creates a new `TypedMatches` iterator.
+    pub(crate) fn new(inner: FindMatches<'h>) -> Self {
+        Self {
+            inner,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the underlying iterator over the untyped matches.
+    pub fn into_inner(self) -> FindMatches<'h> {
+        self.inner
+    }
+
+    /// Returns the error at which the iterator stopped at unmatched input.
+    /// See [FindMatches::last_error].
+    pub fn last_error(&self) -> Option<&ScnrError> {
+        self.inner.last_error()
+    }
+}
+
+impl<T: Tokens> Iterator for TypedMatches<'_, T> {
+    type Item = Match<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find_map(|ma| {
+            T::from_token_type(ma.token_type()).map(|token_type| Match::new(token_type, ma.span()))
+        })
+    }
+}
+
+impl<T> PositionProvider for TypedMatches<'_, T> {
+    fn position(&self, offset: usize) -> Position {
+        self.inner.position(offset)
+    }
+
+    fn set_offset(&mut self, offset: usize) {
+        self.inner.set_offset(offset);
+    }
+}
+
+impl<T> ScannerModeSwitcher for TypedMatches<'_, T> {
+    fn set_mode(&mut self, mode: usize) {
+        self.inner.set_mode(mode);
+    }
+
+    fn current_mode(&self) -> usize {
+        self.inner.current_mode()
+    }
+
+    fn mode_name(&self, index: usize) -> Option<&str> {
+        self.inner.mode_name(index)
+    }
+
+    fn mode_stack_depth(&self) -> usize {
+        self.inner.mode_stack_depth()
+    }
+}
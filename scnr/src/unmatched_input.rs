@@ -0,0 +1,42 @@
+//! Module with the policy for input that no pattern of the current scanner mode matches.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Determines how the scanner handles input that no pattern of the current scanner mode matches.
+///
+/// The policy is set with [crate::ScannerBuilder::with_unmatched_input] or
+/// [crate::Scanner::with_unmatched_input]. The default is [UnmatchedInput::Skip].
+///
+/// The policy is serialized with the scanner. In JSON it is written as `"skip"`, `{"token": 99}`
+/// or `"error"`.
+///
+/// ```rust
+/// use scnr::{ScannerBuilder, UnmatchedInput};
+///
+/// let scanner = ScannerBuilder::new()
+///     .with_unmatched_input(UnmatchedInput::Token(99))
+///     .add_patterns([r"[a-z]+", r"\s+"])
+///     .build()
+///     .unwrap();
+/// let tokens = scanner
+///     .find_iter("ab #?! c")
+///     .map(|m| (m.token_type(), m.range()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(tokens, vec![(0, 0..2), (1, 2..3), (99, 3..6), (1, 6..7), (0, 7..8)]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum UnmatchedInput {
+    /// The unmatched characters are skipped and don't appear in the matches.
+    #[default]
+    Skip,
+    /// A match with the given token type is returned that covers the maximal run of unmatched
+    /// characters. The token type should not be used by any pattern of the scanner.
+    Token(usize),
+    /// The scanner stops at the first unmatched character. The error is available through
+    /// [crate::FindMatches::try_next_match] and [crate::FindMatches::last_error] and is returned
+    /// by [crate::StreamMatches].
+    Error,
+}
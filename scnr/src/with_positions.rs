@@ -46,6 +46,10 @@ where
     fn mode_name(&self, index: usize) -> Option<&str> {
         self.iter.mode_name(index)
     }
+
+    fn mode_stack_depth(&self) -> usize {
+        self.iter.mode_stack_depth()
+    }
 }
 
 /// An extension trait for iterators over matches.
@@ -0,0 +1,141 @@
+#![cfg(not(feature = "regex_automata"))]
+// Test that a generated scanner finds the same matches as the scanner it was generated from.
+// Run with `cargo test --test codegen_test`
+// Set the environment variable `SCNR_UPDATE_GENERATED` to write the generated scanner again after
+// changes of the code generation.
+// The generated scanner also runs without the feature `compiler`, e.g. with
+// `cargo test --no-default-features --test codegen_test`.
+
+#[cfg(feature = "compiler")]
+use std::fs;
+
+use scnr::ScannerModeSwitcher;
+#[cfg(feature = "compiler")]
+use scnr::{
+    Lookahead, Lookbehind, MatchExt, MatchExtIterator, Pattern, ScannerBuilder, ScannerMode,
+};
+
+mod generated {
+    include!("data/codegen_scanner.rs");
+}
+
+#[cfg(feature = "compiler")]
+const GENERATED_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/codegen_scanner.rs");
+
+const INPUT: &str = r#"f(x) = -1 /* a */ * b /* c */
+y=(-2) - x
+"str \"ing\"" z 12ab 34
+"#;
+
+#[cfg(feature = "compiler")]
+fn scanner_modes() -> Vec<ScannerMode> {
+    vec![
+        ScannerMode::new(
+            "INITIAL",
+            vec![
+                Pattern::new(r"\r\n|\r|\n".to_string(), 0),
+                Pattern::new(r"[ \t]+".to_string(), 1),
+                Pattern::new(r"[a-zA-Z_][a-zA-Z0-9_]*".to_string(), 2)
+                    .with_lookahead(Lookahead::new(true, r"\(".to_string())),
+                Pattern::new(r"[a-zA-Z_][a-zA-Z0-9_]*".to_string(), 3),
+                Pattern::new("-".to_string(), 4)
+                    .with_lookbehind(Lookbehind::new(true, r"[=(] *".to_string())),
+                Pattern::new("-".to_string(), 5),
+                Pattern::new(r"[0-9]+\b".to_string(), 6),
+                Pattern::new(r"/\*.*?\*/".to_string(), 7)
+                    .with_name("Comment")
+                    .with_skip(true),
+                Pattern::new(r"[=()*]".to_string(), 8),
+                Pattern::new(r#"""#.to_string(), 9).with_name("Quote"),
+                Pattern::new(".".to_string(), 10),
+            ],
+            vec![(9, 1)],
+        ),
+        ScannerMode::new(
+            "STRING",
+            vec![
+                Pattern::new(r#"\\["\\]"#.to_string(), 11),
+                Pattern::new(r#"[^"\\]+"#.to_string(), 12),
+                Pattern::new(r#"""#.to_string(), 9).with_name("Quote"),
+            ],
+            vec![(9, 0)],
+        ),
+    ]
+}
+
+#[cfg(feature = "compiler")]
+#[test]
+fn generated_scanner_is_up_to_date() {
+    let source = scnr::codegen::generate(&scanner_modes()).unwrap();
+    if std::env::var_os("SCNR_UPDATE_GENERATED").is_some() {
+        fs::write(GENERATED_PATH, &source).unwrap();
+    }
+    let expected = fs::read_to_string(GENERATED_PATH).unwrap();
+    assert_eq!(
+        source, expected,
+        "The generated scanner is outdated. Run the test with SCNR_UPDATE_GENERATED=1."
+    );
+    // The tables are wrapped like formatted Rust code.
+    assert!(source.lines().all(|line| line.len() <= 100));
+}
+
+#[cfg(feature = "compiler")]
+#[test]
+fn generated_scanner_finds_same_matches() {
+    let scanner = ScannerBuilder::new()
+        .add_scanner_modes(&scanner_modes())
+        .build()
+        .unwrap();
+    let generated = generated::scanner();
+
+    let expected: Vec<MatchExt> = scanner.find_iter(INPUT).with_positions().collect();
+    let matches: Vec<MatchExt> = generated.find_iter(INPUT).with_positions().collect();
+    assert!(expected.iter().any(|ma| ma.token_type() == 2));
+    assert!(expected.iter().any(|ma| ma.token_type() == 4));
+    assert!(expected.iter().any(|ma| ma.token_type() == 12));
+    assert_eq!(matches, expected);
+
+    // The generated scanner knows the token names.
+    assert_eq!(generated.token_name(7), Some("Comment"));
+    assert_eq!(generated.token_type_by_name("Quote"), Some(9));
+    assert_eq!(generated.token_name(2), None);
+
+    // The generated scanner switches scanner modes in the same way.
+    let mut find_iter = generated.find_iter(INPUT);
+    assert_eq!(find_iter.mode_name(1), Some("STRING"));
+    find_iter.set_mode(1);
+    assert_eq!(find_iter.next().map(|ma| ma.token_type()), Some(12));
+}
+
+#[test]
+fn generated_scanner_runs_without_compiler() {
+    let generated = generated::scanner();
+    let tokens = generated
+        .find_iter(INPUT)
+        .map(|ma| (ma.token_type(), &INPUT[ma.span().range()]))
+        .take(12)
+        .collect::<Vec<_>>();
+    // The lookahead, the lookbehind and the skipped comment are evaluated from the tables.
+    assert_eq!(
+        tokens,
+        vec![
+            (2, "f"),
+            (8, "("),
+            (3, "x"),
+            (8, ")"),
+            (1, " "),
+            (8, "="),
+            (1, " "),
+            (4, "-"),
+            (6, "1"),
+            (1, " "),
+            (1, " "),
+            (8, "*"),
+        ]
+    );
+    assert_eq!(generated.token_type_by_name("Comment"), Some(7));
+    let mut find_iter = generated.find_iter(INPUT);
+    find_iter.set_mode(1);
+    assert_eq!(find_iter.mode_name(1), Some("STRING"));
+    assert_eq!(find_iter.next().map(|ma| ma.token_type()), Some(12));
+}
@@ -0,0 +1,145 @@
+// This file was generated by scnr::codegen. Do not edit it by hand.
+
+const _: () = assert!(
+    scnr::codegen::is_compatible("0.9.0"),
+    "The scanner was generated by another version of scnr. Please generate it again."
+);
+
+const N: u32 = scnr::codegen::NONE;
+
+/// The compiled scanner modes.
+pub static SCANNER_MODES: &[scnr::codegen::StaticScannerMode] = &[
+    scnr::codegen::StaticScannerMode {
+        name: "INITIAL",
+        transitions: &[(9, scnr::ModeTransition::Set(1))],
+        skipped: &[7],
+        token_names: &[(7, "Comment"), (9, "Quote")],
+        dfa: scnr::codegen::StaticDfa {
+            patterns: &[
+                "\\r\\n|\\r|\\n", "[ \\t]+", "[a-zA-Z_][a-zA-Z0-9_]*", "[a-zA-Z_][a-zA-Z0-9_]*",
+                "-", "-", "[0-9]+\\b", "/\\*.*?\\*/", "[=()*]", "\"", ".",
+            ],
+            terminal_ids: &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+            ascii: [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 1, 0, 4, 0, 0, 0, 0, 0, 5, 5, 6, 0, 0, 7, 0, 8, 9, 9, 9, 9, 9, 9, 9, 9,
+                9, 9, 0, 0, 0, 5, 0, 0, 0, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10,
+                10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 0, 0, 0, 0, 10, 0, 10, 10, 10, 10,
+                10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10,
+                10, 0, 0, 0, 0, 0,
+            ],
+            ranges: &[('\u{80}', '\u{10ffff}', 0)],
+            bytes: &[],
+            symbol_count: 11,
+            transitions: &[
+                1, 2, 3, 4, 5, 6, 6, 7, 8, 9, 10, N, N, N, N, N, N, N, N, N, N, N, N, 2, N, N, N,
+                N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, 3, N, N, N, N, N, N, N, N,
+                N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                N, N, N, N, N, N, N, N, N, N, N, 11, N, N, N, N, N, N, N, N, N, N, N, N, N, 13, N,
+                N, N, N, N, N, N, N, N, N, 10, 10, 11, 11, N, N, 11, 11, 14, 11, 11, 11, 11, N, N,
+                N, N, N, N, N, N, N, 13, N, N, N, N, N, N, N, N, N, N, 13, N, 11, 11, N, N, 11, 11,
+                14, 11, 15, 11, 11, N, N, N, N, N, N, N, N, N, N, N,
+            ],
+            accepting: &[
+                &[], &[(10, N)], &[(1, N)], &[(0, N)], &[(0, N)], &[(9, N)], &[(8, N)],
+                &[(4, N), (5, N)], &[(10, N)], &[(10, N)], &[(2, N), (3, N)], &[], &[(6, N)], &[],
+                &[], &[(7, N)],
+            ],
+            looks: &[
+                &[], &[], &[], &[], &[], &[], &[], &[], &[],
+                &[
+                    9, 9, 9, 12, 12, 9, 9, 9, 9, 12, 12, 9, 9, 9, 9, 12, 12, 9, 12, 12, 12, 9, 9,
+                    12, 12, 12, 12, 9, 9, 12, 9, 9, 9, 12, 12, 9,
+                ],
+                &[], &[], &[],
+                &[
+                    13, 13, 13, 12, 12, 13, 13, 13, 13, 12, 12, 13, 13, 13, 13, 12, 12, 13, 12, 12,
+                    12, 13, 13, 12, 12, 12, 12, 13, 13, 12, 13, 13, 13, 12, 12, 13,
+                ],
+                &[], &[],
+            ],
+            lookaheads: &[
+                scnr::codegen::StaticLookaround {
+                    terminal_id: 2,
+                    is_positive: true,
+                    dfa: scnr::codegen::StaticDfa {
+                        patterns: &["\\("],
+                        terminal_ids: &[0],
+                        ascii: [
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, 0, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N,
+                        ],
+                        ranges: &[],
+                        bytes: &[],
+                        symbol_count: 1,
+                        transitions: &[1, N],
+                        accepting: &[&[], &[(0, N)]],
+                        looks: &[&[], &[]],
+                        lookaheads: &[],
+                        lookbehinds: &[],
+                    },
+                },
+            ],
+            lookbehinds: &[
+                scnr::codegen::StaticLookaround {
+                    terminal_id: 4,
+                    is_positive: true,
+                    dfa: scnr::codegen::StaticDfa {
+                        patterns: &["[=(] *"],
+                        terminal_ids: &[0],
+                        ascii: [
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, 0, N, N, N, N, N, N, N, 1, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, 1, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N, N,
+                            N, N, N, N, N, N, N, N,
+                        ],
+                        ranges: &[],
+                        bytes: &[],
+                        symbol_count: 2,
+                        transitions: &[0, 1, N, N],
+                        accepting: &[&[], &[(0, N)]],
+                        looks: &[&[], &[]],
+                        lookaheads: &[],
+                        lookbehinds: &[],
+                    },
+                },
+            ],
+        },
+    },
+    scnr::codegen::StaticScannerMode {
+        name: "STRING",
+        transitions: &[(9, scnr::ModeTransition::Set(0))],
+        skipped: &[],
+        token_names: &[(9, "Quote")],
+        dfa: scnr::codegen::StaticDfa {
+            patterns: &["\\\\[\"\\\\]", "[^\"\\\\]+", "\""],
+            terminal_ids: &[11, 12, 9],
+            ascii: [
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            ranges: &[('\u{80}', '\u{10ffff}', 0)],
+            bytes: &[],
+            symbol_count: 3,
+            transitions: &[1, 2, 3, 1, N, N, N, N, N, N, 4, 4, N, N, N],
+            accepting: &[&[], &[(12, N)], &[(9, N)], &[], &[(11, N)]],
+            looks: &[&[], &[], &[], &[], &[]],
+            lookaheads: &[],
+            lookbehinds: &[],
+        },
+    },
+];
+
+/// Creates the scanner from the compiled scanner modes.
+pub fn scanner() -> scnr::Scanner {
+    scnr::Scanner::from(SCANNER_MODES)
+}
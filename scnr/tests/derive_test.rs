@@ -0,0 +1,201 @@
+#![cfg(feature = "derive")]
+#![cfg(feature = "compiler")]
+// Test the derive macro for token types.
+// Run with `cargo test --test derive_test`
+
+use scnr::{Match, PositionProvider, ScannerModeSwitcher, Span, Tokens};
+
+#[derive(Tokens, Clone, Copy, Debug, PartialEq, Eq)]
+#[modes(INITIAL, STRING, COMMENT)]
+enum Tok {
+    #[mode(INITIAL, COMMENT)]
+    #[pattern(r"\r\n|\r|\n")]
+    Newline,
+    #[pattern(r"[ \t]+")]
+    Whitespace,
+    #[pattern(r"[a-zA-Z_]\w*")]
+    Identifier,
+    #[pattern(r"[0-9]+")]
+    Number,
+    #[mode(INITIAL, STRING)]
+    #[transition(INITIAL => STRING, STRING => INITIAL)]
+    #[pattern("\"")]
+    Quote,
+    #[mode(STRING)]
+    #[pattern(r#"\\["\\]"#)]
+    Escape,
+    #[mode(STRING)]
+    #[pattern(r#"[^"\\]+"#)]
+    Text,
+    #[transition(INITIAL => COMMENT)]
+    #[pattern(r"/\*")]
+    CommentStart,
+    #[mode(COMMENT)]
+    #[transition(COMMENT => INITIAL)]
+    #[pattern(r"\*/")]
+    CommentEnd,
+    #[mode(COMMENT)]
+    #[pattern(r"[^*\r\n]+|\*")]
+    CommentText,
+}
+
+#[derive(Tokens, Clone, Copy, Debug, PartialEq, Eq)]
+enum Numbered {
+    #[pattern("a")]
+    A = 10,
+    #[pattern("b")]
+    B = 20,
+}
+
+#[derive(Tokens, Clone, Copy, Debug, PartialEq, Eq)]
+#[modes(CODE, TEMPLATE)]
+enum TemplateTok {
+    #[pattern(r"[a-z]+")]
+    Word,
+    #[transition(CODE => push TEMPLATE)]
+    #[pattern("`")]
+    TemplateStart,
+    #[mode(TEMPLATE)]
+    #[transition(TEMPLATE => pop)]
+    #[pattern("`")]
+    TemplateEnd,
+    #[mode(TEMPLATE)]
+    #[transition(TEMPLATE => push CODE)]
+    #[pattern(r"\$\{")]
+    InterpolationStart,
+    #[transition(CODE => pop)]
+    #[pattern(r"\}")]
+    InterpolationEnd,
+    #[mode(TEMPLATE)]
+    #[pattern(r"[^`$]+")]
+    Text,
+}
+
+#[derive(Tokens, Clone, Copy, Debug, PartialEq, Eq)]
+#[modes(INITIAL, COMMENT)]
+enum Significant {
+    #[skip]
+    #[pattern(r"\s+")]
+    Whitespace,
+    #[pattern(r"[a-z]+")]
+    Word,
+    #[skip]
+    #[transition(INITIAL => COMMENT)]
+    #[pattern(r"/\*")]
+    CommentStart,
+    #[skip]
+    #[mode(COMMENT)]
+    #[transition(COMMENT => INITIAL)]
+    #[pattern(r"\*/")]
+    CommentEnd,
+    #[skip]
+    #[mode(COMMENT)]
+    #[pattern(r"[^*]+|\*")]
+    CommentText,
+}
+
+#[test]
+fn test_scanner_modes() {
+    let scanner_modes = Tok::scanner_modes();
+    assert_eq!(
+        scanner_modes.iter().map(|m| m.name()).collect::<Vec<_>>(),
+        ["INITIAL", "STRING", "COMMENT"]
+    );
+    assert_eq!(Tok::token_type(Tok::Quote), 4);
+    assert_eq!(Tok::from_token_type(6), Some(Tok::Text));
+    assert_eq!(Tok::from_token_type(10), None);
+}
+
+#[test]
+fn test_typed_matches() {
+    let scanner = Tok::scanner().unwrap();
+    let input = "a \"b\\\"c\" /* x * y */ 12\n";
+    let mut find_iter = scanner.find_iter(input).typed::<Tok>();
+    let tokens: Vec<(Tok, &str)> = find_iter
+        .by_ref()
+        .map(|ma| (ma.token_type(), &input[ma.range()]))
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            (Tok::Identifier, "a"),
+            (Tok::Whitespace, " "),
+            (Tok::Quote, "\""),
+            (Tok::Text, "b"),
+            (Tok::Escape, "\\\""),
+            (Tok::Text, "c"),
+            (Tok::Quote, "\""),
+            (Tok::Whitespace, " "),
+            (Tok::CommentStart, "/*"),
+            (Tok::CommentText, " x "),
+            (Tok::CommentText, "*"),
+            (Tok::CommentText, " y "),
+            (Tok::CommentEnd, "*/"),
+            (Tok::Whitespace, " "),
+            (Tok::Number, "12"),
+            (Tok::Newline, "\n"),
+        ]
+    );
+    assert_eq!(
+        find_iter.mode_name(find_iter.current_mode()),
+        Some("INITIAL")
+    );
+    assert_eq!(find_iter.position(2).column, 3);
+}
+
+#[test]
+fn test_discriminants_are_token_types() {
+    let scanner = Numbered::scanner().unwrap();
+    let matches: Vec<Match<Numbered>> = scanner.find_iter("ab").typed().collect();
+    assert_eq!(
+        matches,
+        [
+            Match::new(Numbered::A, Span::new(0, 1)),
+            Match::new(Numbered::B, Span::new(1, 2)),
+        ]
+    );
+    // The untyped matches carry the discriminants.
+    let token_types: Vec<usize> = scanner.find_iter("ab").map(|ma| ma.token_type()).collect();
+    assert_eq!(token_types, [10, 20]);
+    // The names of the variants are the token names.
+    assert_eq!(scanner.token_name(10), Some("A"));
+    assert_eq!(scanner.token_type_by_name("B"), Some(20));
+}
+
+#[test]
+fn test_push_and_pop_transitions() {
+    let scanner = TemplateTok::scanner().unwrap();
+    let mut find_iter = scanner.find_iter("a`b ${c`d`}`").typed::<TemplateTok>();
+    let mut tokens = Vec::new();
+    while let Some(ma) = find_iter.next() {
+        tokens.push((ma.token_type(), find_iter.mode_stack_depth()));
+    }
+    assert_eq!(
+        tokens,
+        [
+            (TemplateTok::Word, 0),
+            (TemplateTok::TemplateStart, 1),
+            (TemplateTok::Text, 1),
+            (TemplateTok::InterpolationStart, 2),
+            (TemplateTok::Word, 2),
+            (TemplateTok::TemplateStart, 3),
+            (TemplateTok::Text, 3),
+            (TemplateTok::TemplateEnd, 2),
+            (TemplateTok::InterpolationEnd, 1),
+            (TemplateTok::TemplateEnd, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_skipped_token_types() {
+    let scanner = Significant::scanner().unwrap();
+    let input = "a /* b * c */ d";
+    let mut find_iter = scanner.find_iter(input).typed::<Significant>();
+    let tokens: Vec<(Significant, &str)> = find_iter
+        .by_ref()
+        .map(|ma| (ma.token_type(), &input[ma.range()]))
+        .collect();
+    assert_eq!(tokens, [(Significant::Word, "a"), (Significant::Word, "d")]);
+    assert_eq!(find_iter.current_mode(), 0);
+}
@@ -0,0 +1,190 @@
+#![cfg(not(feature = "regex_automata"))]
+#![cfg(feature = "compiler")]
+// Test that the incremental re-tokenization yields the same tokens as a scan of the whole text.
+// Run with `cargo test --test incremental_test`
+
+use std::fs;
+
+use scnr::{Lookbehind, Pattern, ScannerBuilder, ScannerMode, TextEdit, UnmatchedInput};
+
+/// A simple linear congruential generator for reproducible edits.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound
+    }
+
+    /// Returns a random char boundary of the text.
+    fn boundary(&mut self, text: &str) -> usize {
+        let mut offset = self.next(text.len() + 1);
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+}
+
+const REPLACEMENTS: &[&str] = &["", "a", "\"", " ", "\n", "12", "/*", "*/", "//", "\\", "ü"];
+
+#[test]
+fn incremental_test() {
+    // Initialize the logger
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Iterate over all json files in the data directory that contain scanner modes
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data")).unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if path.extension().unwrap() != "json"
+            || path
+                .file_stem()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .ends_with("_tokens")
+        {
+            continue;
+        }
+
+        // Read the json file
+        let file = fs::File::open(&path).unwrap();
+        let scanner_modes: Vec<ScannerMode> = serde_json::from_reader(file)
+            .unwrap_or_else(|e| panic!("**** Failed to read json file {}: {}", path.display(), e));
+
+        // Unmatched input is either skipped or returned as tokens.
+        for unmatched_input in [UnmatchedInput::Skip, UnmatchedInput::Token(usize::MAX)] {
+            let scanner = ScannerBuilder::new()
+                .with_unmatched_input(unmatched_input)
+                .add_scanner_modes(&scanner_modes)
+                .build()
+                .unwrap();
+
+            let mut text = fs::read_to_string(path.with_extension("input")).unwrap();
+            let mut tokens = scanner.tokenize(&text);
+            let mut rng = Lcg(text.len() as u64);
+
+            for _ in 0..200 {
+                let start = rng.boundary(&text);
+                let end = start + rng.boundary(&text[start..]).min(8);
+                let end = (start..=end)
+                    .rev()
+                    .find(|&i| text.is_char_boundary(i))
+                    .unwrap();
+                // Insert either one of the replacements or a part of the text itself.
+                let replacement = if rng.next(2) == 0 {
+                    REPLACEMENTS[rng.next(REPLACEMENTS.len())].to_string()
+                } else {
+                    let from = rng.boundary(&text);
+                    let to = from + rng.boundary(&text[from..]).min(6);
+                    let to = (from..=to)
+                        .rev()
+                        .find(|&i| text.is_char_boundary(i))
+                        .unwrap();
+                    text[from..to].to_string()
+                };
+
+                let mut new_text = text.clone();
+                new_text.replace_range(start..end, &replacement);
+                let changed = scanner.retokenize(
+                    &new_text,
+                    &mut tokens,
+                    &TextEdit::new(start..end, &replacement),
+                );
+                let expected = scanner.tokenize(&new_text);
+                assert_eq!(
+                    tokens,
+                    expected,
+                    "**** Wrong tokens in {} with {:?} after replacing {:?} with {:?} in {:?}",
+                    path.display(),
+                    unmatched_input,
+                    start..end,
+                    replacement,
+                    text
+                );
+                assert!(changed.end <= tokens.len());
+                text = new_text;
+            }
+        }
+    }
+}
+
+#[test]
+fn retokenize_reports_changed_range() {
+    let scanner = ScannerBuilder::new()
+        .add_patterns([r"[a-z]+", r"\s+", r"[0-9]+"])
+        .build()
+        .unwrap();
+    let mut tokens = scanner.tokenize("abc 12 def 34 ghi");
+    assert_eq!(tokens.len(), 9);
+
+    // Extend the number in the middle. The scan of the number has read the space behind it.
+    let changed = scanner.retokenize(
+        "abc 12 def 345 ghi",
+        &mut tokens,
+        &TextEdit::new(13..13, "5"),
+    );
+    assert_eq!(changed, 6..8);
+    assert_eq!(tokens, scanner.tokenize("abc 12 def 345 ghi"));
+
+    // An edit that splits a token adds tokens.
+    let changed = scanner.retokenize("abc 12 d f 345 ghi", &mut tokens, &TextEdit::new(8..9, " "));
+    assert_eq!(changed, 4..7);
+    assert_eq!(tokens, scanner.tokenize("abc 12 d f 345 ghi"));
+
+    // Removing all text removes all tokens.
+    let changed = scanner.retokenize("", &mut tokens, &TextEdit::new(0..18, ""));
+    assert_eq!(changed, 0..0);
+    assert!(tokens.is_empty());
+}
+
+#[test]
+fn retokenize_follows_mode_switches() {
+    let file = fs::File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/string.json"
+    ))
+    .unwrap();
+    let scanner_modes: Vec<ScannerMode> = serde_json::from_reader(file).unwrap();
+    let scanner = ScannerBuilder::new()
+        .add_scanner_modes(&scanner_modes)
+        .build()
+        .unwrap();
+
+    let old_text = r#"a "b c" d "e" f"#;
+    let mut tokens = scanner.tokenize(old_text);
+    // Removing the first quote swaps the scanner modes of the rest of the text.
+    let new_text = r#"a b c" d "e" f"#;
+    let changed = scanner.retokenize(new_text, &mut tokens, &TextEdit::new(2..3, ""));
+    assert_eq!(tokens, scanner.tokenize(new_text));
+    assert_eq!(changed.end, tokens.len());
+}
+
+#[test]
+fn retokenize_checks_lookbehinds_again() {
+    let scanner = ScannerBuilder::new()
+        .add_scanner_mode(ScannerMode::new(
+            "INITIAL",
+            vec![
+                Pattern::new(" ".to_string(), 0),
+                Pattern::new("[a-c]".to_string(), 1),
+                Pattern::new("d".to_string(), 2)
+                    .with_lookbehind(Lookbehind::new(true, "abc".to_string())),
+                Pattern::new("d".to_string(), 3),
+            ],
+            vec![],
+        ))
+        .build()
+        .unwrap();
+
+    let mut tokens = scanner.tokenize("bcdb");
+    assert_eq!(tokens[2].matched().token_type(), 3);
+    // The insertion completes the lookbehind of the token behind the tokens that are found again.
+    scanner.retokenize("abcdb", &mut tokens, &TextEdit::new(0..0, "a"));
+    assert_eq!(tokens, scanner.tokenize("abcdb"));
+    assert_eq!(tokens[3].matched().token_type(), 2);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "serde")]
+#![cfg(feature = "compiler")]
+// Test that a serialized scanner finds the same matches as the scanner it was created from.
+// Run with `cargo test --test serialization_test`
+
+use std::fs;
+
+use scnr::{MatchExt, MatchExtIterator, Scanner, ScannerBuilder, ScannerMode};
+
+#[test]
+fn serialization_test() {
+    // Initialize the logger
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Iterate over all json files in the data directory that contain scanner modes
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data")).unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if path.extension().unwrap() != "json"
+            || path
+                .file_stem()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .ends_with("_tokens")
+        {
+            continue;
+        }
+
+        // Read the json file
+        let file = fs::File::open(&path).unwrap();
+        let scanner_modes: Vec<ScannerMode> = serde_json::from_reader(file)
+            .unwrap_or_else(|e| panic!("**** Failed to read json file {}: {}", path.display(), e));
+
+        let scanner = ScannerBuilder::new()
+            .add_scanner_modes(&scanner_modes)
+            .build()
+            .unwrap();
+
+        // Write the scanner to a file and load it again.
+        let serialized_path = std::env::temp_dir().join(format!(
+            "scnr_serialization_test_{}.scnr",
+            path.file_stem().unwrap().to_str().unwrap()
+        ));
+        scanner
+            .write_to(fs::File::create(&serialized_path).unwrap())
+            .unwrap();
+        let loaded = Scanner::from_reader(fs::File::open(&serialized_path).unwrap())
+            .unwrap_or_else(|e| panic!("**** Failed to load scanner {}: {}", path.display(), e));
+        let _ = fs::remove_file(&serialized_path);
+
+        let input = fs::read_to_string(path.with_extension("input")).unwrap();
+        let expected: Vec<MatchExt> = scanner.find_iter(&input).with_positions().collect();
+        let matches: Vec<MatchExt> = loaded.find_iter(&input).with_positions().collect();
+        assert!(!expected.is_empty(), "No matches for {}", path.display());
+        assert_eq!(matches, expected, "Failed for {}", path.display());
+
+        // The scanner can also be loaded from bytes.
+        let loaded = Scanner::from_bytes(&scanner.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded.find_iter(&input).count(), expected.len());
+    }
+}
@@ -0,0 +1,243 @@
+#![cfg(not(feature = "regex_automata"))]
+#![cfg(feature = "compiler")]
+// Test that the streaming scanner finds the same matches as the scanner on the whole input.
+// Run with `cargo test --test stream_test`
+
+use std::{fs, io::Read};
+
+use scnr::{
+    MatchExt, MatchExtIterator, PositionProvider, ScannerBuilder, ScannerMode, ScnrErrorKind,
+    UnmatchedInput,
+};
+
+/// A reader that returns at most `chunk` bytes per read.
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk: usize,
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.chunk.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn stream_test() {
+    // Initialize the logger
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Iterate over all json files in the data directory that contain scanner modes
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data")).unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if path.extension().unwrap() != "json"
+            || path
+                .file_stem()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .ends_with("_tokens")
+        {
+            continue;
+        }
+
+        // Read the json file
+        let file = fs::File::open(&path).unwrap();
+        let scanner_modes: Vec<ScannerMode> = serde_json::from_reader(file)
+            .unwrap_or_else(|e| panic!("**** Failed to read json file {}: {}", path.display(), e));
+
+        let scanner = ScannerBuilder::new()
+            .add_scanner_modes(&scanner_modes)
+            .build()
+            .unwrap();
+
+        let input = fs::read_to_string(path.with_extension("input")).unwrap();
+        let expected: Vec<MatchExt> = scanner.find_iter(&input).with_positions().collect();
+
+        for chunk in [1, 2, 3, 7, 64, 4096] {
+            let mut stream = scanner
+                .find_iter_reader(ChunkedReader {
+                    data: input.as_bytes(),
+                    chunk,
+                })
+                .with_chunk_size(chunk);
+            let mut matches = Vec::new();
+            while let Some(matched) = stream.next() {
+                let matched = matched.unwrap();
+                assert_eq!(
+                    stream.matched_bytes(&matched),
+                    Some(&input.as_bytes()[matched.range()])
+                );
+                matches.push((
+                    matched.token_type(),
+                    matched.span(),
+                    stream.position(matched.start()),
+                    stream.position(matched.end()),
+                ));
+            }
+            let expected = expected
+                .iter()
+                .map(|m| {
+                    (
+                        m.token_type(),
+                        m.span(),
+                        m.start_position(),
+                        m.end_position(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                matches,
+                expected,
+                "Failed for {} with chunk size {}",
+                path.display(),
+                chunk
+            );
+        }
+    }
+}
+
+#[test]
+fn stream_split_characters() {
+    let scanner = ScannerBuilder::new()
+        .add_patterns([r"\p{Greek}+", r"\s+", r"(?-u:\xFF)"])
+        .build()
+        .unwrap();
+    let input = "αβγ δε\n\u{3b6}".as_bytes().to_vec();
+    let mut input_with_invalid = input.clone();
+    input_with_invalid.extend_from_slice(b"\xFF\xCE");
+    for data in [&input, &input_with_invalid] {
+        let expected: Vec<_> = scanner
+            .find_iter_bytes(data)
+            .map(|m| (m.token_type(), m.span()))
+            .collect();
+        let matches: Vec<_> = scanner
+            .find_iter_reader(ChunkedReader { data, chunk: 1 })
+            .with_chunk_size(1)
+            .map(|m| m.map(|m| (m.token_type(), m.span())))
+            .collect::<scnr::Result<_>>()
+            .unwrap();
+        assert_eq!(matches, expected);
+    }
+}
+
+#[test]
+fn stream_buffer_limit() {
+    let scanner = ScannerBuilder::new()
+        .add_patterns([r"[a-z]+", r"\s+"])
+        .build()
+        .unwrap();
+    let input = format!("abc {} def", "x".repeat(100));
+    let mut stream = scanner
+        .find_iter_reader(input.as_bytes())
+        .with_chunk_size(16)
+        .with_max_buffer_size(64);
+    assert_eq!(stream.next().unwrap().unwrap().span().range(), 0..3);
+    assert_eq!(stream.next().unwrap().unwrap().span().range(), 3..4);
+    let error = stream.next().unwrap().unwrap_err();
+    assert!(
+        matches!(
+            *error.source,
+            ScnrErrorKind::BufferLimitExceeded {
+                limit: 64,
+                offset: 4
+            }
+        ),
+        "{}",
+        error
+    );
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn stream_io_error() {
+    struct FailingReader;
+    impl Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("broken pipe"))
+        }
+    }
+    let scanner = ScannerBuilder::new()
+        .add_patterns([r"[a-z]+"])
+        .build()
+        .unwrap();
+    let mut stream = scanner.find_iter_reader(FailingReader);
+    let error = stream.next().unwrap().unwrap_err();
+    assert!(matches!(*error.source, ScnrErrorKind::IoError(_)));
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn stream_set_offset() {
+    let scanner = ScannerBuilder::new()
+        .add_patterns([r"[a-z]+", r"\s+"])
+        .build()
+        .unwrap();
+    let input = "skip these words but not this";
+    let mut stream = scanner
+        .find_iter_reader(ChunkedReader {
+            data: input.as_bytes(),
+            chunk: 4,
+        })
+        .with_chunk_size(4);
+    assert_eq!(stream.next().unwrap().unwrap().span().range(), 0..4);
+    stream.set_offset(21);
+    let rest = stream
+        .map(|m| m.unwrap().span().range())
+        .collect::<Vec<_>>();
+    assert_eq!(rest, vec![21..24, 24..25, 25..29]);
+}
+
+#[test]
+fn stream_unmatched_input() {
+    let input = "ab #?! c ü% d";
+    let scanner = ScannerBuilder::new()
+        .with_unmatched_input(UnmatchedInput::Token(9))
+        .add_patterns([r"[a-z]+", r"\s+"])
+        .build()
+        .unwrap();
+    let expected: Vec<_> = scanner
+        .find_iter(input)
+        .map(|m| (m.token_type(), m.span()))
+        .collect();
+    assert_eq!(expected.iter().filter(|(t, _)| *t == 9).count(), 2);
+    for chunk in [1, 2, 64] {
+        let mut stream = scanner
+            .find_iter_reader(ChunkedReader {
+                data: input.as_bytes(),
+                chunk,
+            })
+            .with_chunk_size(chunk);
+        let mut matches = Vec::new();
+        while let Some(matched) = stream.next() {
+            let matched = matched.unwrap();
+            assert_eq!(
+                stream.matched_bytes(&matched),
+                Some(&input.as_bytes()[matched.range()])
+            );
+            matches.push((matched.token_type(), matched.span()));
+        }
+        assert_eq!(matches, expected, "Failed with chunk size {}", chunk);
+    }
+
+    // The stream ends with an error at the first unmatched character.
+    let mut stream = scanner
+        .with_unmatched_input(UnmatchedInput::Error)
+        .find_iter_reader(input.as_bytes());
+    assert_eq!(stream.next().unwrap().unwrap().span().range(), 0..2);
+    assert_eq!(stream.next().unwrap().unwrap().span().range(), 2..3);
+    let error = stream.next().unwrap().unwrap_err();
+    assert!(
+        matches!(
+            &*error.source,
+            ScnrErrorKind::UnmatchedInput { offset: 3, mode } if mode == "INITIAL"
+        ),
+        "{}",
+        error
+    );
+    assert!(stream.next().is_none());
+}
@@ -1,5 +1,6 @@
 #![cfg(feature = "dot_writer")]
 #![cfg(feature = "serde")]
+#![cfg(feature = "compiler")]
 // Outputs the compiled automata as in dot format for all the modes files in the data directory.
 // Run with `cargo test -- --nocapture trace_compiled_dfa_as_dot`
 
@@ -0,0 +1,372 @@
+//! # `scnr_derive`
+//!
+//! This crate provides the derive macro for the `Tokens` trait of the `scnr` crate.
+//! Use it through the re-export `scnr::Tokens`, which is available with the feature `derive` of
+//! `scnr`. The feature is enabled by default.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Attribute, Data, DeriveInput, Error, Fields, Ident, LitStr, Result, Token,
+};
+
+/// The name of the scanner mode if the enum has no `#[modes(...)]` attribute.
+const DEFAULT_MODE: &str = "INITIAL";
+
+/// Derives the `scnr::Tokens` trait for a fieldless enum.
+///
+/// * `#[modes(INITIAL, STRING)]` on the enum declares the scanner modes. The first one is the
+///   initial scanner mode. Without this attribute there is a single scanner mode `INITIAL`.
+/// * `#[pattern(r"...")]` on a variant defines the regex of the token type. It is required.
+/// * `#[mode(STRING)]` on a variant lists the scanner modes in which the token type is valid.
+///   Without this attribute the token type is valid in the initial scanner mode.
+/// * `#[transition(INITIAL => STRING)]` on a variant switches from the first scanner mode to the
+///   second one after a match of the token type. `INITIAL => push STRING` pushes the first scanner
+///   mode onto the mode stack before, and `STRING => pop` switches back to the scanner mode on top
+///   of the mode stack.
+/// * `#[skip]` on a variant marks the token type as skipped. Its matches, e.g. of whitespace or
+///   comments, are not returned by the scanner, but its transitions are applied.
+///
+/// The discriminants of the variants are the token type numbers and the order of the variants
+/// determines the priority of the patterns. The names of the variants become the token names, see
+/// `scnr::Scanner::token_name`.
+///
+/// ```
+/// use scnr::{ScannerModeSwitcher, Tokens};
+///
+/// #[derive(Tokens, Clone, Copy, Debug, PartialEq)]
+/// #[modes(INITIAL, STRING)]
+/// enum Tok {
+///     #[pattern(r"\d+")]
+///     Number,
+///     #[skip]
+///     #[pattern(r"\s+")]
+///     Whitespace,
+///     #[mode(INITIAL, STRING)]
+///     #[transition(INITIAL => STRING, STRING => INITIAL)]
+///     #[pattern("\"")]
+///     Quote,
+///     #[mode(STRING)]
+///     #[pattern(r#"[^"]+"#)]
+///     Text,
+/// }
+///
+/// let scanner = Tok::scanner().unwrap();
+/// let mut find_iter = scanner.find_iter(r#"1 "a b""#).typed::<Tok>();
+/// let tokens: Vec<Tok> = find_iter.by_ref().map(|ma| ma.token_type()).collect();
+/// assert_eq!(tokens, [Tok::Number, Tok::Quote, Tok::Text, Tok::Quote]);
+/// assert_eq!(find_iter.mode_name(find_iter.current_mode()), Some("INITIAL"));
+/// ```
+///
+/// Unknown scanner modes are rejected at compile time:
+/// ```compile_fail
+/// #[derive(scnr::Tokens, Clone, Copy)]
+/// enum Tok {
+///     #[mode(STRING)]
+///     #[pattern("\"")]
+///     Quote,
+/// }
+/// ```
+///
+/// Patterns that are used twice in the same scanner mode are rejected, too:
+/// ```compile_fail
+/// #[derive(scnr::Tokens, Clone, Copy)]
+/// enum Tok {
+///     #[pattern(r"\d+")]
+///     Number,
+///     #[pattern(r"\d+")]
+///     Integer,
+/// }
+/// ```
+#[proc_macro_derive(Tokens, attributes(modes, mode, pattern, transition, skip))]
+pub fn derive_tokens(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// A transition from one scanner mode to another one, written as `FROM => TO`,
+/// `FROM => push TO` or `FROM => pop`.
+struct Transition {
+    from: Ident,
+    kind: TransitionKind,
+}
+
+/// The kind of a transition with the name of the target scanner mode.
+enum TransitionKind {
+    Set(Ident),
+    Push(Ident),
+    Pop,
+}
+
+impl Parse for Transition {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let from = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let first: Ident = input.parse()?;
+        let kind = if first == "push" {
+            TransitionKind::Push(input.parse()?)
+        } else if first == "pop" {
+            TransitionKind::Pop
+        } else {
+            TransitionKind::Set(first)
+        };
+        Ok(Self { from, kind })
+    }
+}
+
+/// A variant of the enum with the data of its attributes.
+struct TokenVariant {
+    ident: Ident,
+    pattern: LitStr,
+    /// The indices of the scanner modes in which the token type is valid.
+    modes: Vec<usize>,
+    /// The transitions as pairs of the source scanner mode index and the mode transition.
+    transitions: Vec<(usize, proc_macro2::TokenStream)>,
+    /// Whether the token type is marked as skipped.
+    skip: bool,
+}
+
+/// Parses the comma separated identifiers of an attribute like `#[modes(INITIAL, STRING)]`.
+fn parse_idents(attr: &Attribute) -> Result<Vec<Ident>> {
+    Ok(attr
+        .parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?
+        .into_iter()
+        .collect())
+}
+
+/// Returns the index of the scanner mode or an error if the scanner mode is not declared.
+fn mode_index(mode_names: &[Ident], mode: &Ident) -> Result<usize> {
+    mode_names.iter().position(|m| m == mode).ok_or_else(|| {
+        Error::new(
+            mode.span(),
+            format!(
+                "unknown scanner mode `{}`, the declared scanner modes are: {}",
+                mode,
+                mode_names
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+    })
+}
+
+/// Collects the scanner modes declared by the `#[modes(...)]` attribute of the enum.
+fn parse_modes(input: &DeriveInput) -> Result<Vec<Ident>> {
+    let mut mode_names = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("modes")) {
+        if mode_names.is_some() {
+            return Err(Error::new_spanned(attr, "duplicate `modes` attribute"));
+        }
+        let names = parse_idents(attr)?;
+        if names.is_empty() {
+            return Err(Error::new_spanned(
+                attr,
+                "at least one scanner mode is required",
+            ));
+        }
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(Error::new(
+                    name.span(),
+                    format!("duplicate scanner mode `{}`", name),
+                ));
+            }
+        }
+        mode_names = Some(names);
+    }
+    Ok(mode_names.unwrap_or_else(|| vec![Ident::new(DEFAULT_MODE, Span::call_site())]))
+}
+
+/// Collects the data of the attributes of the variants.
+fn parse_variants(input: &DeriveInput, mode_names: &[Ident]) -> Result<Vec<TokenVariant>> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`Tokens` can only be derived for enums",
+        ));
+    };
+    let mut variants = Vec::with_capacity(data.variants.len());
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "the variants of token types can't have fields",
+            ));
+        }
+        let mut pattern: Option<LitStr> = None;
+        let mut modes = Vec::new();
+        let mut transitions = Vec::new();
+        let mut skip = false;
+        for attr in &variant.attrs {
+            if attr.path().is_ident("pattern") {
+                if pattern.is_some() {
+                    return Err(Error::new_spanned(attr, "duplicate `pattern` attribute"));
+                }
+                pattern = Some(attr.parse_args()?);
+            } else if attr.path().is_ident("mode") {
+                for mode in parse_idents(attr)? {
+                    let index = mode_index(mode_names, &mode)?;
+                    if !modes.contains(&index) {
+                        modes.push(index);
+                    }
+                }
+            } else if attr.path().is_ident("skip") {
+                attr.meta.require_path_only()?;
+                if skip {
+                    return Err(Error::new_spanned(attr, "duplicate `skip` attribute"));
+                }
+                skip = true;
+            } else if attr.path().is_ident("transition") {
+                for transition in
+                    attr.parse_args_with(Punctuated::<Transition, Token![,]>::parse_terminated)?
+                {
+                    let from = mode_index(mode_names, &transition.from)?;
+                    let to = match &transition.kind {
+                        TransitionKind::Set(to) => {
+                            let to = mode_index(mode_names, to)?;
+                            quote! { ::scnr::ModeTransition::Set(#to) }
+                        }
+                        TransitionKind::Push(to) => {
+                            let to = mode_index(mode_names, to)?;
+                            quote! { ::scnr::ModeTransition::Push(#to) }
+                        }
+                        TransitionKind::Pop => quote! { ::scnr::ModeTransition::Pop },
+                    };
+                    if transitions.iter().any(|(f, _)| *f == from) {
+                        return Err(Error::new(
+                            transition.from.span(),
+                            format!(
+                                "duplicate transition from scanner mode `{}`",
+                                transition.from
+                            ),
+                        ));
+                    }
+                    transitions.push((from, to));
+                }
+            }
+        }
+        let Some(pattern) = pattern else {
+            return Err(Error::new_spanned(
+                &variant.ident,
+                "missing `#[pattern(...)]` attribute",
+            ));
+        };
+        if modes.is_empty() {
+            modes.push(0);
+        }
+        if let Some((from, _)) = transitions.iter().find(|(from, _)| !modes.contains(from)) {
+            return Err(Error::new_spanned(
+                &variant.ident,
+                format!(
+                    "the transition from scanner mode `{}` requires the token type to be valid in \
+                    this scanner mode, add it to the `mode` attribute",
+                    mode_names[*from]
+                ),
+            ));
+        }
+        variants.push(TokenVariant {
+            ident: variant.ident.clone(),
+            pattern,
+            modes,
+            transitions,
+            skip,
+        });
+    }
+    Ok(variants)
+}
+
+/// Checks that no pattern is used twice in the same scanner mode.
+fn check_duplicate_patterns(variants: &[TokenVariant], mode_names: &[Ident]) -> Result<()> {
+    for (i, variant) in variants.iter().enumerate() {
+        let pattern = variant.pattern.value();
+        let duplicate = variants[..i].iter().find(|other| {
+            other.pattern.value() == pattern
+                && other.modes.iter().any(|m| variant.modes.contains(m))
+        });
+        if let Some(other) = duplicate {
+            let mode = other
+                .modes
+                .iter()
+                .find(|m| variant.modes.contains(m))
+                .map(|m| &mode_names[*m])
+                .expect("a common scanner mode");
+            return Err(Error::new(
+                variant.pattern.span(),
+                format!(
+                    "duplicate pattern {:?} in scanner mode `{}`, it is already used by `{}`",
+                    pattern, mode, other.ident
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn expand(input: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let mode_names = parse_modes(input)?;
+    let variants = parse_variants(input, &mode_names)?;
+    check_duplicate_patterns(&variants, &mode_names)?;
+
+    let scanner_modes = mode_names.iter().enumerate().map(|(index, mode_name)| {
+        let name = mode_name.to_string();
+        let patterns = variants
+            .iter()
+            .filter(|v| v.modes.contains(&index))
+            .map(|v| {
+                let ident = &v.ident;
+                let pattern = &v.pattern;
+                let token_name = ident.to_string();
+                let skip = v.skip.then(|| quote! { .with_skip(true) });
+                quote! {
+                    ::scnr::Pattern::new(::std::string::String::from(#pattern), Self::#ident as usize)
+                        .with_name(#token_name)
+                        #skip
+                }
+            });
+        let transitions = variants.iter().flat_map(|v| {
+            let ident = &v.ident;
+            v.transitions
+                .iter()
+                .filter(move |(from, _)| *from == index)
+                .map(move |(_, transition)| {
+                    quote! { .with_transition(Self::#ident as usize, #transition) }
+                })
+        });
+        quote! {
+            ::scnr::ScannerMode::new(#name, ::std::vec![#(#patterns),*], [])
+                #(#transitions)*
+        }
+    });
+    let idents = variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::scnr::Tokens for #name #ty_generics #where_clause {
+            fn scanner_modes() -> ::std::vec::Vec<::scnr::ScannerMode> {
+                ::std::vec![#(#scanner_modes),*]
+            }
+
+            fn from_token_type(token_type: usize) -> ::std::option::Option<Self> {
+                #(
+                    if token_type == Self::#idents as usize {
+                        return ::std::option::Option::Some(Self::#idents);
+                    }
+                )*
+                ::std::option::Option::None
+            }
+
+            fn token_type(self) -> usize {
+                self as usize
+            }
+        }
+    })
+}